@@ -0,0 +1,27 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('reports', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('reporter_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('user_id', 36).references('id').inTable('krumnet.users');
+    table.string('entry_id', 36).references('id').inTable('krumnet.game_round_entries');
+    table.string('reason').notNullable();
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique('id');
+  });
+  await knex.raw(`
+    alter table krumnet.reports
+    add constraint report_has_target check (user_id is not null or entry_id is not null)
+  `);
+  await knex.schema.withSchema('krumnet').table('game_round_entries', function(table) {
+    table.timestamp('hidden_at');
+    table.string('hidden_by', 36).references('id').inTable('krumnet.users');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').table('game_round_entries', function(table) {
+    table.dropColumn('hidden_by');
+    table.dropColumn('hidden_at');
+  });
+  await knex.schema.withSchema('krumnet').dropTable('reports');
+};
@@ -0,0 +1,36 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('game_teams', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('lobby_id', 36).references('id').inTable('krumnet.lobbies').notNullable();
+    table.string('game_id', 36).references('id').inTable('krumnet.games').notNullable();
+    table.integer('position').unsigned().notNullable();
+    table.string('name').notNullable();
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique('id');
+    table.unique(['game_id', 'position'], 'single_team_position');
+  });
+  await knex.schema.withSchema('krumnet').table('game_memberships', function(table) {
+    table.string('team_id', 36).references('id').inTable('krumnet.game_teams');
+  });
+  await knex.schema.withSchema('krumnet').table('game_round_entries', function(table) {
+    table.string('team_id', 36).references('id').inTable('krumnet.game_teams');
+    table.unique(['round_id', 'team_id'], 'single_team_entry');
+  });
+  await knex.schema.withSchema('krumnet').table('game_member_round_placement_results', function(table) {
+    table.string('team_id', 36).references('id').inTable('krumnet.game_teams');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').table('game_member_round_placement_results', function(table) {
+    table.dropColumn('team_id');
+  });
+  await knex.schema.withSchema('krumnet').table('game_round_entries', function(table) {
+    table.dropUnique(['round_id', 'team_id'], 'single_team_entry');
+    table.dropColumn('team_id');
+  });
+  await knex.schema.withSchema('krumnet').table('game_memberships', function(table) {
+    table.dropColumn('team_id');
+  });
+  await knex.schema.withSchema('krumnet').dropTable('game_teams');
+};
@@ -0,0 +1,24 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('user_ratings', function(table) {
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable().primary();
+    table.double('rating').notNullable();
+    table.integer('games_rated').notNullable().defaultTo(0);
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.timestamp('updated_at').defaultTo(knex.fn.now());
+  });
+  await knex.schema.withSchema('krumnet').createTable('game_rating_changes', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('lobby_id', 36).references('id').inTable('krumnet.lobbies').notNullable();
+    table.string('game_id', 36).references('id').inTable('krumnet.games').notNullable();
+    table.double('rating_before').notNullable();
+    table.double('rating_after').notNullable();
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique(['game_id', 'user_id'], 'single_game_rating_change');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('game_rating_changes');
+  await knex.schema.withSchema('krumnet').dropTable('user_ratings');
+};
@@ -0,0 +1,15 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('user_achievements', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('kind').notNullable();
+    table.string('game_id', 36).references('id').inTable('krumnet.games');
+    table.string('round_id', 36).references('id').inTable('krumnet.game_rounds');
+    table.timestamp('awarded_at').defaultTo(knex.fn.now());
+    table.unique(['user_id', 'kind'], 'single_user_achievement');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('user_achievements');
+};
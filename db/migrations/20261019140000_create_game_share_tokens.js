@@ -0,0 +1,15 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('game_share_tokens', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('game_id', 36).references('id').inTable('krumnet.games').notNullable();
+    table.string('created_by', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('token').notNullable();
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique('game_id', 'single_game_share_token');
+    table.unique('token');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('game_share_tokens');
+};
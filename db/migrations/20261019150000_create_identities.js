@@ -0,0 +1,46 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('identities', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('provider').notNullable();
+    table.string('subject').notNullable();
+    table.string('email');
+    table.string('name');
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique(['provider', 'subject'], 'single_provider_identity');
+  });
+
+  await knex.raw(`
+    insert into krumnet.identities
+      (user_id, provider, subject, email, name)
+    select
+      user_id, 'google', google_id, email, name
+    from
+      krumnet.google_accounts
+  `);
+
+  await knex.schema.withSchema('krumnet').dropTable('google_accounts');
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('google_accounts', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('email').unique().notNullable();
+    table.string('name').notNullable();
+    table.string('google_id').unique().notNullable();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+  });
+
+  await knex.raw(`
+    insert into krumnet.google_accounts
+      (user_id, google_id, email, name)
+    select
+      user_id, subject, email, name
+    from
+      krumnet.identities
+    where
+      provider = 'google'
+  `);
+
+  await knex.schema.withSchema('krumnet').dropTable('identities');
+};
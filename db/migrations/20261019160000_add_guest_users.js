@@ -0,0 +1,15 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.boolean('guest').defaultTo(false).notNullable();
+    table.timestamp('last_seen_at').defaultTo(knex.fn.now());
+    table.timestamp('expired_at');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.dropColumn('guest');
+    table.dropColumn('last_seen_at');
+    table.dropColumn('expired_at');
+  });
+};
@@ -0,0 +1,20 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.string('avatar_url', 2048);
+  });
+
+  await knex.schema.withSchema('krumnet').createTable('user_avatars', function(table) {
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable().primary();
+    table.string('content_type').notNullable();
+    table.binary('image').notNullable();
+    table.timestamp('updated_at').defaultTo(knex.fn.now());
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('user_avatars');
+
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.dropColumn('avatar_url');
+  });
+};
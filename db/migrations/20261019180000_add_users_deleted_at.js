@@ -0,0 +1,11 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.timestamp('deleted_at');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').alterTable('users', function(table) {
+    table.dropColumn('deleted_at');
+  });
+};
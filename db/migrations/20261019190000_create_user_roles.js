@@ -0,0 +1,14 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('user_roles', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('role').notNullable();
+    table.string('granted_by', 36).references('id').inTable('krumnet.users');
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.unique(['user_id', 'role'], 'single_user_role');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('user_roles');
+};
@@ -0,0 +1,18 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').createTable('api_keys', function(table) {
+    table.string('id', 36).defaultTo(knex.raw('uuid_generate_v4()')).notNullable().primary();
+    table.string('user_id', 36).references('id').inTable('krumnet.users').notNullable();
+    table.string('name').notNullable();
+    table.string('prefix', 16).notNullable();
+    table.string('key_hash', 64).notNullable().unique();
+    table.specificType('scopes', 'varchar(255)[]').notNullable();
+    table.integer('use_count').defaultTo(0).notNullable();
+    table.timestamp('created_at').defaultTo(knex.fn.now());
+    table.timestamp('last_used_at');
+    table.timestamp('revoked_at');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').dropTable('api_keys');
+};
@@ -0,0 +1,17 @@
+exports.up = async function(knex) {
+  await knex.schema.withSchema('krumnet').table('game_member_round_placement_results', function(table) {
+    table.dropUnique(['place', 'round_id'], 'single_round_winner');
+  });
+  await knex.schema.withSchema('krumnet').table('game_member_placement_results', function(table) {
+    table.dropUnique(['place', 'game_id'], 'single_game_winner');
+  });
+};
+
+exports.down = async function(knex) {
+  await knex.schema.withSchema('krumnet').table('game_member_placement_results', function(table) {
+    table.unique(['place', 'game_id'], 'single_game_winner');
+  });
+  await knex.schema.withSchema('krumnet').table('game_member_round_placement_results', function(table) {
+    table.unique(['place', 'round_id'], 'single_round_winner');
+  });
+};
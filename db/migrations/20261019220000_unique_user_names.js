@@ -0,0 +1,28 @@
+exports.up = async function(knex) {
+  // Names that already collide keep the oldest user's name; the others are told apart by their id.
+  await knex.raw(`
+    update krumnet.users
+    set
+      name = duplicates.name || ' ' || left(duplicates.id, 8)
+    from (
+      select
+        id, name, row_number() over (partition by lower(name) order by created_at, id) as position
+      from
+        krumnet.users
+      where
+        deleted_at is null
+    ) as duplicates
+    where
+      users.id = duplicates.id
+    and
+      duplicates.position > 1
+  `);
+
+  await knex.raw(`
+    create unique index unique_user_name on krumnet.users (lower(name)) where deleted_at is null
+  `);
+};
+
+exports.down = async function(knex) {
+  await knex.raw('drop index krumnet.unique_user_name');
+};
@@ -0,0 +1,77 @@
+// Achievements are awarded at most once per user by the background worker; the kind is the value
+// persisted alongside each award and the remaining details are only used for display.
+
+pub const ROUND_STREAK_LENGTH: i64 = 3;
+pub const VETERAN_GAME_COUNT: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Achievement {
+  UnanimousWinner,
+  RoundStreak,
+  Veteran,
+}
+
+impl Achievement {
+  pub fn all() -> [Achievement; 3] {
+    [
+      Achievement::UnanimousWinner,
+      Achievement::RoundStreak,
+      Achievement::Veteran,
+    ]
+  }
+
+  pub fn from_kind(kind: &str) -> Option<Self> {
+    Achievement::all()
+      .iter()
+      .find(|achievement| achievement.kind() == kind)
+      .copied()
+  }
+
+  pub fn kind(&self) -> &'static str {
+    match self {
+      Achievement::UnanimousWinner => "unanimous_winner",
+      Achievement::RoundStreak => "round_streak",
+      Achievement::Veteran => "veteran",
+    }
+  }
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      Achievement::UnanimousWinner => "Unanimous Winner",
+      Achievement::RoundStreak => "On a Roll",
+      Achievement::Veteran => "Veteran",
+    }
+  }
+
+  pub fn description(&self) -> String {
+    match self {
+      Achievement::UnanimousWinner => String::from("Won a round with a vote from every opponent"),
+      Achievement::RoundStreak => format!("Won {} rounds in a row", ROUND_STREAK_LENGTH),
+      Achievement::Veteran => format!("Played {} games", VETERAN_GAME_COUNT),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Achievement;
+
+  #[test]
+  fn kinds_round_trip() {
+    for achievement in Achievement::all().iter() {
+      assert_eq!(
+        Achievement::from_kind(achievement.kind()),
+        Some(*achievement)
+      );
+    }
+    assert_eq!(Achievement::from_kind("bogus"), None);
+  }
+
+  #[test]
+  fn descriptions() {
+    assert_eq!(
+      Achievement::RoundStreak.description(),
+      "Won 3 rounds in a row"
+    );
+  }
+}
@@ -0,0 +1,40 @@
+use sha2::{Digest, Sha256};
+
+use crate::constants::{API_KEY_DISPLAY_LENGTH, API_KEY_PREFIX};
+use crate::session::random_token;
+
+// Api keys are handed to the owner exactly once; only a digest is kept in the record store. The
+// shared prefix makes it cheap to tell a key apart from a session token in the authorization header.
+pub fn generate() -> String {
+  format!("{}{}", API_KEY_PREFIX, random_token())
+}
+
+pub fn is_api_key(value: &str) -> bool {
+  value.starts_with(API_KEY_PREFIX)
+}
+
+pub fn digest(key: &str) -> String {
+  format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+// The leading characters of a key, kept so owners can tell their keys apart when listing them.
+pub fn display_prefix(key: &str) -> String {
+  key.chars().take(API_KEY_DISPLAY_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::{digest, display_prefix, generate, is_api_key};
+
+  #[test]
+  fn generated_keys() {
+    let key = generate();
+    assert!(is_api_key(&key));
+    assert!(!is_api_key("eyJhbGciOiJIUzI1NiJ9"));
+    assert_eq!(digest(&key).len(), 64);
+    assert_eq!(digest(&key), digest(&key));
+    assert_ne!(digest(&key), digest(&generate()));
+    assert_eq!(display_prefix(&key).len(), 12);
+    assert!(key.starts_with(&display_prefix(&key)));
+  }
+}
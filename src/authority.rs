@@ -1,9 +1,91 @@
-#[derive(Debug, PartialEq)]
+use elaine::RequestMethod;
+
+use crate::http::is_safe_method;
+
+// Privileges granted to a user on top of what every player can do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+  Admin,
+}
+
+impl Role {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "admin" => Some(Role::Admin),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Role::Admin => "admin",
+    }
+  }
+}
+
+// What an api key is allowed to do on behalf of its owner; keys with only `Read` can not change
+// anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+  Read,
+  Write,
+}
+
+impl Scope {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "read" => Some(Scope::Read),
+      "write" => Some(Scope::Write),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Scope::Read => "read",
+      Scope::Write => "write",
+    }
+  }
+
+  pub fn permits(&self, method: &RequestMethod) -> bool {
+    match self {
+      Scope::Write => true,
+      Scope::Read => is_safe_method(method),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Authority {
-  User { id: String, token: String },
+  User {
+    id: String,
+    token: String,
+    roles: Vec<Role>,
+    // The id of the api key used in place of a session token, if any.
+    key: Option<String>,
+  },
   None,
 }
 
+impl Authority {
+  // A user without any additional roles.
+  pub fn user(id: String, token: String) -> Self {
+    Authority::User {
+      id,
+      token,
+      roles: Vec::new(),
+      key: None,
+    }
+  }
+
+  pub fn has_role(&self, role: Role) -> bool {
+    match self {
+      Authority::User { roles, .. } => roles.contains(&role),
+      Authority::None => false,
+    }
+  }
+}
+
 impl Default for Authority {
   fn default() -> Self {
     Authority::None
@@ -0,0 +1,40 @@
+with wins as (
+  select
+    results.user_id  as user_id,
+    results.round_id as round_id,
+    rounds.position  as position,
+    rounds.position - row_number() over (
+      partition by results.user_id order by rounds.position
+    )                as streak
+  from
+    krumnet.game_member_round_placement_results as results
+  inner join
+    krumnet.game_rounds as rounds
+  on
+    rounds.id = results.round_id
+  where
+    results.game_id = $1
+  and
+    rounds.completed_at is not null
+  and
+    results.place = 1
+) insert into
+  krumnet.user_achievements as achievements
+  (user_id, kind, game_id, round_id)
+select
+  wins.user_id                                              as user_id,
+  $2                                                        as kind,
+  $1                                                        as game_id,
+  (array_agg(wins.round_id order by wins.position desc))[1] as round_id
+from
+  wins
+group by
+  wins.user_id,
+  wins.streak
+having
+  count(wins.round_id) >= $3
+on conflict on constraint
+  single_user_achievement
+do nothing
+returning
+  id;
@@ -0,0 +1,40 @@
+insert into
+  krumnet.user_achievements as achievements
+  (user_id, kind, game_id, round_id)
+select
+  results.user_id  as user_id,
+  $2               as kind,
+  results.game_id  as game_id,
+  results.round_id as round_id
+from
+  krumnet.game_member_round_placement_results as results
+inner join
+  krumnet.game_rounds as rounds
+on
+  rounds.id = results.round_id
+where
+  results.game_id = $1
+and
+  rounds.completed_at is not null
+and
+  results.place = 1
+and
+  results.vote_count > 0
+and
+  results.vote_count = (
+    select
+      count(members.id)
+    from
+      krumnet.game_memberships as members
+    where
+      members.game_id = results.game_id
+    and
+      members.user_id <> results.user_id
+    and
+      (results.team_id is null or members.team_id <> results.team_id)
+  )
+on conflict on constraint
+  single_user_achievement
+do nothing
+returning
+  id;
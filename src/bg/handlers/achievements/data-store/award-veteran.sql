@@ -0,0 +1,27 @@
+insert into
+  krumnet.user_achievements as achievements
+  (user_id, kind, game_id)
+select
+  placements.user_id  as user_id,
+  $2                  as kind,
+  cast($1 as varchar) as game_id
+from
+  krumnet.game_member_placement_results as placements
+where
+  placements.user_id in (
+    select
+      members.user_id
+    from
+      krumnet.game_memberships as members
+    where
+      members.game_id = $1
+  )
+group by
+  placements.user_id
+having
+  count(placements.id) >= $3
+on conflict on constraint
+  single_user_achievement
+do nothing
+returning
+  id;
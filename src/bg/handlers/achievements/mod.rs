@@ -0,0 +1,185 @@
+use log::{info, warn};
+use sqlx::query_file;
+
+use crate::achievements::{Achievement, ROUND_STREAK_LENGTH, VETERAN_GAME_COUNT};
+use crate::interchange::jobs::AwardAchievements as AwardContext;
+use crate::{bg::context::Context, interchange};
+
+fn log_and_serialize<E: std::error::Error>(error: E) -> String {
+  warn!("{}", error);
+  format!("{}", error)
+}
+
+async fn award(
+  context: &Context,
+  game_id: &String,
+  achievement: Achievement,
+) -> Result<Vec<String>, String> {
+  let mut conn = context.records.acquire().await.map_err(log_and_serialize)?;
+  let kind = achievement.kind();
+
+  let ids = match achievement {
+    Achievement::UnanimousWinner => query_file!(
+      "src/bg/handlers/achievements/data-store/award-unanimous-winner.sql",
+      game_id,
+      kind
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(log_and_serialize)?
+    .into_iter()
+    .map(|row| row.id)
+    .collect(),
+    Achievement::RoundStreak => query_file!(
+      "src/bg/handlers/achievements/data-store/award-round-streak.sql",
+      game_id,
+      kind,
+      ROUND_STREAK_LENGTH
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(log_and_serialize)?
+    .into_iter()
+    .map(|row| row.id)
+    .collect(),
+    Achievement::Veteran => query_file!(
+      "src/bg/handlers/achievements/data-store/award-veteran.sql",
+      game_id,
+      kind,
+      VETERAN_GAME_COUNT
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(log_and_serialize)?
+    .into_iter()
+    .map(|row| row.id)
+    .collect(),
+  };
+
+  Ok(ids)
+}
+
+async fn award_inner(context: &Context, details: &AwardContext) -> Result<Vec<String>, String> {
+  let mut awarded = vec![];
+
+  for achievement in Achievement::all().iter() {
+    let ids = award(context, &details.game_id, *achievement).await?;
+
+    if !ids.is_empty() {
+      info!(
+        "awarded '{}' to {} members of game '{}'",
+        achievement.kind(),
+        ids.len(),
+        details.game_id
+      );
+    }
+
+    awarded.extend(ids);
+  }
+
+  Ok(awarded)
+}
+
+pub async fn award_achievements(
+  details: &AwardContext,
+  context: &Context,
+) -> interchange::jobs::Job {
+  interchange::jobs::Job::AwardAchievements(AwardContext {
+    game_id: details.game_id.clone(),
+    result: Some(award_inner(context, details).await),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::award_inner;
+  use crate::bg::{context::Context, test_helpers};
+  use crate::interchange::jobs::AwardAchievements;
+  use crate::test_helpers::{game_for_users, GameFixture};
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  // Records the user as the winner of every round in the game, with a vote from each opponent.
+  async fn sweep_game(context: &Context, game_id: &String, winner: &String) {
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "
+      insert into krumnet.game_member_round_placement_results
+        (user_id, lobby_id, member_id, game_id, round_id, place, vote_count)
+      select members.user_id, members.lobby_id, members.id, members.game_id, rounds.id,
+        case when members.user_id = $2 then 1 else 2 end,
+        case when members.user_id = $2 then 1 else 0 end
+      from krumnet.game_memberships as members
+      inner join krumnet.game_rounds as rounds on rounds.game_id = members.game_id
+      where members.game_id = $1
+      ",
+      game_id,
+      winner
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to place");
+
+    query!(
+      "
+      update krumnet.game_rounds
+      set started_at = now(), fulfilled_at = now() + interval '1 second', completed_at = now() + interval '2 seconds'
+      where game_id = $1
+      ",
+      game_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to complete");
+  }
+
+  async fn kinds_for(context: &Context, user_id: &String) -> Vec<String> {
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "select kind from krumnet.user_achievements where user_id = $1 order by kind asc",
+      user_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to query")
+    .into_iter()
+    .map(|row| row.kind)
+    .collect()
+  }
+
+  #[test]
+  fn award_once() {
+    block_on(async {
+      let (context, user_id) =
+        test_helpers::get_test_context_with_user("bg.achievements.award_once").await;
+      let other = test_helpers::make_user(&context, "bg.achievements.award_once.1").await;
+      let GameFixture { lobby_id, game_id } =
+        game_for_users(&context.records, &user_id, &[&other], None).await;
+      sweep_game(&context, &game_id, &user_id).await;
+
+      let details = AwardAchievements {
+        game_id: game_id.clone(),
+        result: None,
+      };
+
+      let awarded = award_inner(&context, &details).await.unwrap();
+      assert_eq!(awarded.len(), 2);
+      assert_eq!(
+        kinds_for(&context, &user_id).await,
+        vec![
+          String::from("round_streak"),
+          String::from("unanimous_winner")
+        ]
+      );
+      assert!(kinds_for(&context, &other).await.is_empty());
+
+      let awarded = award_inner(&context, &details).await.unwrap();
+      assert!(awarded.is_empty());
+
+      test_helpers::cleanup_game(&context, &game_id).await;
+      test_helpers::cleanup_lobby(&context, &lobby_id).await;
+      test_helpers::cleanup_user(&context, &user_id).await;
+      test_helpers::cleanup_user(&context, &other).await;
+    });
+  }
+}
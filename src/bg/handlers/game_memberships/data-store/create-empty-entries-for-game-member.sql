@@ -1,16 +1,21 @@
 insert into
   krumnet.game_round_entries
-  (user_id, round_id, member_id, game_id, lobby_id, entry, auto)
+  (user_id, round_id, member_id, game_id, lobby_id, team_id, entry, auto)
 select
   cast($1 as varchar),
   rounds.id,
   cast($2 as varchar),
   rounds.game_id,
   rounds.lobby_id,
+  members.team_id,
   '',
   true
 from
   krumnet.game_rounds as rounds
+left join
+  krumnet.game_memberships as members
+on
+  members.id = $2
 where
   rounds.id = any($3)
 returning
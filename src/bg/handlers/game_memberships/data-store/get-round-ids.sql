@@ -2,14 +2,33 @@ select
   rounds.id as round_id
 from
   krumnet.game_rounds as rounds
+left join
+  krumnet.game_memberships as members
+on
+  members.game_id = rounds.game_id
+and
+  members.user_id = $1
 left join
   krumnet.game_round_entries as entries
 on
   entries.round_id = rounds.id
 and
-  entries.user_id = $1
+  (entries.user_id = $1 or entries.team_id = members.team_id)
 where
   rounds.game_id = $2
+and
+  not exists (
+    select
+      1
+    from
+      krumnet.game_memberships as teammates
+    where
+      teammates.team_id = members.team_id
+    and
+      teammates.user_id != $1
+    and
+      teammates.left_at is null
+  )
 group by
   rounds.id
 having
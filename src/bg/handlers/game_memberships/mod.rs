@@ -82,6 +82,7 @@ mod tests {
   use crate::{
     bg::{context::Context, test_helpers},
     interchange,
+    test_helpers::{game_for_users, GameFixture},
   };
   use async_std::task::block_on;
   use sqlx::query;
@@ -258,6 +259,55 @@ mod tests {
     });
   }
 
+  #[test]
+  fn count_with_remaining_teammate() {
+    block_on(async {
+      let context = test_helpers::get_test_context().await;
+      let name = "bg.game_memberships.count_with_remaining_teammate";
+      let uid = test_helpers::make_user(&context, name).await;
+      let oid = test_helpers::make_user(&context, &format!("{}.other", name)).await;
+      let GameFixture { lobby_id, game_id } =
+        game_for_users(&context.records, &uid, &[&oid], Some(1)).await;
+      let job = interchange::jobs::CleanupGameMembership {
+        member_id: find_member(&context, &uid, &game_id).await,
+        user_id: uid,
+        lobby_id,
+        game_id,
+        result: None,
+      };
+      let mut conn = context.records.acquire().await.expect("no record store");
+
+      let leave =
+        "update krumnet.game_memberships set left_at = now() where user_id = $1 and game_id = $2";
+      sqlx::query(leave)
+        .bind(&job.user_id)
+        .bind(&job.game_id)
+        .execute(&mut conn)
+        .await
+        .expect("unable to leave");
+
+      let result = round_ids_without_entries(&context, &job)
+        .await
+        .expect("failed query");
+      assert_eq!(result.len(), 0);
+
+      sqlx::query(leave)
+        .bind(&oid)
+        .bind(&job.game_id)
+        .execute(&mut conn)
+        .await
+        .expect("unable to leave");
+
+      let result = round_ids_without_entries(&context, &job)
+        .await
+        .expect("failed query");
+      assert_eq!(result.len(), 3);
+
+      cleanup_job(&context, &job).await;
+      test_helpers::cleanup_user(&context, &oid).await;
+    });
+  }
+
   #[test]
   fn cleanup_with_all_missing() {
     block_on(async {
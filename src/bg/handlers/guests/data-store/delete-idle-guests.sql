@@ -0,0 +1,17 @@
+delete from
+  krumnet.users as users
+where
+  users.guest = true
+and
+  users.last_seen_at < now() - make_interval(secs => $1)
+and
+  not exists (
+    select
+      1
+    from
+      krumnet.lobby_memberships as memberships
+    where
+      memberships.user_id = users.id
+  )
+returning
+  users.id;
@@ -0,0 +1,12 @@
+update
+  krumnet.users as users
+set
+  expired_at = now()
+where
+  users.guest = true
+and
+  users.expired_at is null
+and
+  users.last_seen_at < now() - make_interval(secs => $1)
+returning
+  users.id;
@@ -0,0 +1,111 @@
+use log::{info, warn};
+use sqlx::query_file;
+
+use crate::interchange::jobs::{CleanupGuests as CleanupContext, CleanupGuestsResult};
+use crate::{bg::context::Context, interchange};
+
+fn log_and_serialize<E: std::error::Error>(error: E) -> String {
+  warn!("{}", error);
+  format!("{}", error)
+}
+
+async fn cleanup_inner(
+  context: &Context,
+  details: &CleanupContext,
+) -> Result<CleanupGuestsResult, String> {
+  let mut conn = context.records.acquire().await.map_err(log_and_serialize)?;
+  let idle = details.idle_timeout as f64;
+
+  let deleted = query_file!(
+    "src/bg/handlers/guests/data-store/delete-idle-guests.sql",
+    idle
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(log_and_serialize)?
+  .into_iter()
+  .map(|row| row.id)
+  .collect::<Vec<String>>();
+
+  let expired = query_file!(
+    "src/bg/handlers/guests/data-store/expire-idle-guests.sql",
+    idle
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(log_and_serialize)?
+  .into_iter()
+  .map(|row| row.id)
+  .collect::<Vec<String>>();
+
+  info!(
+    "deleted {} and expired {} idle guests",
+    deleted.len(),
+    expired.len()
+  );
+
+  Ok(CleanupGuestsResult { deleted, expired })
+}
+
+pub async fn cleanup(details: &CleanupContext, context: &Context) -> interchange::jobs::Job {
+  interchange::jobs::Job::CleanupGuests(CleanupContext {
+    idle_timeout: details.idle_timeout,
+    result: Some(cleanup_inner(context, details).await),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::cleanup_inner;
+  use crate::bg::{context::Context, test_helpers};
+  use crate::interchange::jobs::CleanupGuests;
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  async fn make_idle_guest(context: &Context, name: &str) -> String {
+    let user_id = test_helpers::make_user(context, name).await;
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "update krumnet.users set guest = true, last_seen_at = now() - interval '2 hours' where id = $1",
+      user_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to update");
+    user_id
+  }
+
+  #[test]
+  fn deletes_or_expires_idle_guests() {
+    block_on(async {
+      let context = test_helpers::get_test_context().await;
+      let lonely = make_idle_guest(&context, "guests.deletes_or_expires_idle_guests.1").await;
+      let player = make_idle_guest(&context, "guests.deletes_or_expires_idle_guests.2").await;
+      let (_, member) =
+        test_helpers::get_test_context_with_user("guests.deletes_or_expires_idle_guests.3").await;
+      let lobby_id = test_helpers::make_lobby(&context, &player).await;
+
+      let details = CleanupGuests {
+        idle_timeout: 60 * 60 * 24,
+        result: None,
+      };
+      let result = cleanup_inner(&context, &details).await.unwrap();
+      assert!(!result.deleted.contains(&lonely));
+      assert!(!result.expired.contains(&player));
+
+      let details = CleanupGuests {
+        idle_timeout: 60 * 60,
+        result: None,
+      };
+      let result = cleanup_inner(&context, &details).await.unwrap();
+      assert!(result.deleted.contains(&lonely));
+      assert!(!result.deleted.contains(&player));
+      assert!(result.expired.contains(&player));
+      assert!(!result.expired.contains(&member));
+
+      test_helpers::cleanup_lobby(&context, &lobby_id).await;
+      test_helpers::cleanup_user(&context, &player).await;
+      test_helpers::cleanup_user(&context, &member).await;
+    });
+  }
+}
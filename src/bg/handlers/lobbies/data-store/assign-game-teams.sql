@@ -0,0 +1,33 @@
+with new_teams as (
+  insert into krumnet.game_teams as teams
+    (game_id, lobby_id, position, name)
+  select
+    $1, $2, positions.position, concat('Team ', positions.position + 1)
+  from
+    generate_series(0, cast($3 as integer) - 1) as positions (position)
+  returning
+    id,
+    position
+), shuffled_members as (
+  select
+    members.id                                 as member_id,
+    row_number() over (order by random()) - 1  as i
+  from
+    krumnet.game_memberships as members
+  where
+    members.game_id = $1
+) update
+  krumnet.game_memberships as members
+set
+  team_id = new_teams.id
+from
+  shuffled_members
+inner join
+  new_teams
+on
+  new_teams.position = shuffled_members.i % cast($3 as integer)
+where
+  members.id = shuffled_members.member_id
+returning
+  members.id      as member_id,
+  members.team_id as team_id;
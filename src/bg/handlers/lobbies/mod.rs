@@ -1,6 +1,8 @@
 use crate::{
   interchange::jobs::{CreateGame, CreateLobby, Job},
-  names, RecordStore,
+  names,
+  records::Transaction,
+  RecordStore,
 };
 use log::{debug, info, warn};
 use sqlx::query_file;
@@ -76,6 +78,18 @@ pub async fn make_game(
   job_id: &String,
   creator: &String,
   lobby_id: &String,
+) -> std::result::Result<String, String> {
+  make_game_with_teams(records, job_id, creator, lobby_id, None).await
+}
+
+// Creates the game and its memberships, distributing the members across teams when requested. All
+// of it happens in a single transaction so a team game is never left without its teams.
+pub async fn make_game_with_teams(
+  records: &RecordStore,
+  job_id: &String,
+  creator: &String,
+  lobby_id: &String,
+  teams: Option<u8>,
 ) -> std::result::Result<String, String> {
   let user = find_user(creator, records).await?;
   debug!(
@@ -84,7 +98,7 @@ pub async fn make_game(
   );
   let name = names::get();
 
-  let mut conn = records.acquire().await.map_err(warn_and_stringify)?;
+  let mut tx = records.begin().await.map_err(warn_and_stringify)?;
 
   let gid = query_file!(
     "src/bg/handlers/lobbies/data-store/create-game-for-lobby.sql",
@@ -92,7 +106,7 @@ pub async fn make_game(
     name,
     job_id
   )
-  .fetch_all(&mut conn)
+  .fetch_all(&mut tx)
   .await
   .map_err(warn_and_stringify)?
   .into_iter()
@@ -107,19 +121,72 @@ pub async fn make_game(
     gid,
     lobby_id
   )
-  .fetch_all(&mut conn)
+  .fetch_all(&mut tx)
   .await
   .map_err(warn_and_stringify)?;
 
+  if let Some(teams) = teams {
+    insert_teams(&mut tx, &gid, lobby_id, teams).await?;
+  }
+
+  tx.commit().await.map_err(warn_and_stringify)?;
+
   Ok(String::from(gid))
 }
 
+async fn insert_teams(
+  tx: &mut Transaction,
+  game_id: &String,
+  lobby_id: &String,
+  teams: u8,
+) -> std::result::Result<usize, String> {
+  let assigned = query_file!(
+    "src/bg/handlers/lobbies/data-store/assign-game-teams.sql",
+    game_id,
+    lobby_id,
+    i32::from(teams)
+  )
+  .fetch_all(tx)
+  .await
+  .map_err(warn_and_stringify)?
+  .len();
+
+  info!(
+    "assigned {} members of game '{}' to {} teams",
+    assigned, game_id, teams
+  );
+
+  Ok(assigned)
+}
+
+// Randomly distributes the members of a game across the requested number of teams, returning the
+// amount of members that were assigned.
+pub async fn assign_teams(
+  records: &RecordStore,
+  game_id: &String,
+  lobby_id: &String,
+  teams: u8,
+) -> std::result::Result<usize, String> {
+  let mut tx = records.begin().await.map_err(warn_and_stringify)?;
+  let assigned = insert_teams(&mut tx, game_id, lobby_id, teams).await?;
+  tx.commit().await.map_err(warn_and_stringify)?;
+  Ok(assigned)
+}
+
 pub async fn create_game(job_id: &String, details: &CreateGame, records: &RecordStore) -> Job {
-  let result = make_game(records, job_id, &details.creator, &details.lobby_id).await;
+  let result = make_game_with_teams(
+    records,
+    job_id,
+    &details.creator,
+    &details.lobby_id,
+    details.teams,
+  )
+  .await;
 
   Job::CreateGame(CreateGame {
     result: Some(result),
     lobby_id: details.lobby_id.clone(),
     creator: details.creator.clone(),
+    teams: details.teams,
   })
 }
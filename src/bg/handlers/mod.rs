@@ -1,4 +1,8 @@
+pub mod achievements;
 pub mod game_memberships;
+pub mod guests;
 pub mod lobbies;
 pub mod lobby_memberships;
+pub mod ratings;
 pub mod rounds;
+pub mod users;
@@ -0,0 +1,23 @@
+with change as (
+  insert into krumnet.game_rating_changes as changes
+    (game_id, lobby_id, user_id, rating_before, rating_after)
+  values
+    ($1, $2, $3, $4, $5)
+  on conflict on constraint
+    single_game_rating_change
+  do nothing
+  returning
+    user_id,
+    rating_after
+) insert into krumnet.user_ratings as ratings
+    (user_id, rating, games_rated)
+  select
+    change.user_id, change.rating_after, 1
+  from
+    change
+  on conflict (user_id) do update set
+    rating      = excluded.rating,
+    games_rated = ratings.games_rated + 1,
+    updated_at  = now()
+  returning
+    user_id;
@@ -0,0 +1,29 @@
+select
+  placements.user_id          as user_id,
+  placements.lobby_id         as lobby_id,
+  placements.place            as place,
+  members.team_id             as team_id,
+  coalesce(ratings.rating, $2) as rating
+from
+  krumnet.game_member_placement_results as placements
+inner join
+  krumnet.game_memberships as members
+on
+  members.id = placements.member_id
+left join
+  krumnet.user_ratings as ratings
+on
+  ratings.user_id = placements.user_id
+where
+  placements.game_id = $1
+and
+  not exists (
+    select
+      1
+    from
+      krumnet.game_rating_changes as changes
+    where
+      changes.game_id = $1
+  )
+order by
+  placements.place asc;
@@ -0,0 +1 @@
+lock table krumnet.user_ratings in share row exclusive mode;
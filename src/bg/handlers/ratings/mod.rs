@@ -0,0 +1,200 @@
+use log::{debug, info, warn};
+use sqlx::query_file;
+
+use crate::interchange::jobs::UpdateRatings as UpdateContext;
+use crate::{bg::context::Context, interchange, ratings, records::Transaction};
+
+fn log_and_serialize<E: std::error::Error>(error: E) -> String {
+  warn!("{}", error);
+  format!("{}", error)
+}
+
+struct GameStanding {
+  lobby_id: String,
+  standing: ratings::Standing,
+}
+
+async fn standings_for_game(
+  tx: &mut Transaction,
+  game_id: &String,
+) -> Result<Vec<GameStanding>, String> {
+  query_file!(
+    "src/bg/handlers/ratings/data-store/load-game-standings.sql",
+    game_id,
+    ratings::INITIAL_RATING
+  )
+  .fetch_all(tx)
+  .await
+  .map_err(log_and_serialize)?
+  .into_iter()
+  .map(|row| {
+    Ok(GameStanding {
+      lobby_id: row.lobby_id,
+      standing: ratings::Standing {
+        user_id: row.user_id,
+        place: row.place,
+        team_id: row.team_id,
+        rating: row
+          .rating
+          .ok_or_else(|| format!("Unable to load rating for game '{}'", game_id))?,
+      },
+    })
+  })
+  .collect()
+}
+
+// Every player of a game is rated in one transaction; either the whole game is rated or none of it
+// is, so a failure part way through can be retried. The table lock makes concurrent rating updates
+// wait for each other, so ratings are always read after any other game's changes were committed.
+async fn update_inner(context: &Context, details: &UpdateContext) -> Result<Vec<String>, String> {
+  let mut tx = context.records.begin().await.map_err(log_and_serialize)?;
+
+  query_file!("src/bg/handlers/ratings/data-store/lock-ratings.sql")
+    .execute(&mut tx)
+    .await
+    .map_err(log_and_serialize)?;
+
+  let standings = standings_for_game(&mut tx, &details.game_id).await?;
+
+  if standings.is_empty() {
+    info!("game '{}' has no unrated placements", details.game_id);
+    return Ok(vec![]);
+  }
+
+  let updated = ratings::update(
+    &standings
+      .iter()
+      .map(|game_standing| game_standing.standing.clone())
+      .collect::<Vec<ratings::Standing>>(),
+  );
+
+  let mut user_ids = Vec::with_capacity(updated.len());
+
+  for (game_standing, (user_id, rating)) in standings.iter().zip(updated) {
+    debug!(
+      "rating for '{}' in game '{}': {} -> {}",
+      user_id, details.game_id, game_standing.standing.rating, rating
+    );
+
+    let applied = query_file!(
+      "src/bg/handlers/ratings/data-store/apply-rating-change.sql",
+      details.game_id,
+      game_standing.lobby_id,
+      user_id,
+      game_standing.standing.rating,
+      rating
+    )
+    .fetch_all(&mut tx)
+    .await
+    .map_err(log_and_serialize)?;
+
+    user_ids.extend(applied.into_iter().map(|row| row.user_id));
+  }
+
+  tx.commit().await.map_err(log_and_serialize)?;
+
+  Ok(user_ids)
+}
+
+pub async fn update(details: &UpdateContext, context: &Context) -> interchange::jobs::Job {
+  info!("updating ratings for game '{}'", details.game_id);
+
+  interchange::jobs::Job::UpdateRatings(UpdateContext {
+    game_id: details.game_id.clone(),
+    result: Some(update_inner(context, details).await),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::update_inner;
+  use crate::bg::{context::Context, test_helpers};
+  use crate::interchange::jobs::UpdateRatings;
+  use crate::test_helpers::{game_for_users, GameFixture};
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  async fn place_game(context: &Context, game_id: &String, winner: &String) {
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "
+      insert into krumnet.game_member_placement_results (user_id, lobby_id, member_id, game_id, place, vote_count)
+      select members.user_id, members.lobby_id, members.id, members.game_id,
+        case when members.user_id = $2 then 1 else 2 end, 0
+      from krumnet.game_memberships as members where members.game_id = $1
+      ",
+      game_id,
+      winner
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to place");
+  }
+
+  async fn rating_for(context: &Context, user_id: &String) -> Option<(f64, i32)> {
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "select rating, games_rated from krumnet.user_ratings where user_id = $1",
+      user_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to query")
+    .into_iter()
+    .next()
+    .map(|row| (row.rating, row.games_rated))
+  }
+
+  async fn cleanup_ratings(context: &Context, game_id: &String, user_ids: &[&String]) {
+    let mut conn = context.records.acquire().await.expect("unable to connect");
+    query!(
+      "delete from krumnet.game_rating_changes where game_id = $1",
+      game_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
+
+    for user_id in user_ids {
+      query!(
+        "delete from krumnet.user_ratings where user_id = $1",
+        *user_id
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to delete");
+    }
+  }
+
+  #[test]
+  fn rate_game_once() {
+    block_on(async {
+      let (context, user_id) =
+        test_helpers::get_test_context_with_user("bg.ratings.rate_game_once").await;
+      let other = test_helpers::make_user(&context, "bg.ratings.rate_game_once.1").await;
+      let GameFixture { lobby_id, game_id } =
+        game_for_users(&context.records, &user_id, &[&other], None).await;
+      place_game(&context, &game_id, &user_id).await;
+
+      let details = UpdateRatings {
+        game_id: game_id.clone(),
+        result: None,
+      };
+
+      let updated = update_inner(&context, &details).await.unwrap();
+      assert_eq!(updated.len(), 2);
+      assert_eq!(rating_for(&context, &user_id).await, Some((1516.0, 1)));
+      assert_eq!(rating_for(&context, &other).await, Some((1484.0, 1)));
+
+      let updated = update_inner(&context, &details).await.unwrap();
+      assert!(updated.is_empty());
+      assert_eq!(rating_for(&context, &user_id).await, Some((1516.0, 1)));
+
+      cleanup_ratings(&context, &game_id, &[&user_id, &other]).await;
+      test_helpers::cleanup_game(&context, &game_id).await;
+      test_helpers::cleanup_lobby(&context, &lobby_id).await;
+      test_helpers::cleanup_user(&context, &user_id).await;
+      test_helpers::cleanup_user(&context, &other).await;
+    });
+  }
+}
@@ -1,5 +1,5 @@
 select
-  count(votes.id) as count
+  count(distinct votes.member_id) as count
 from
   krumnet.game_round_entry_votes as votes
 left join
@@ -1,26 +1,67 @@
-insert into
+with totals as (
+  select
+    round_placements.user_id         as user_id,
+    round_placements.lobby_id        as lobby_id,
+    round_placements.member_id       as member_id,
+    round_placements.game_id         as game_id,
+    members.team_id                  as team_id,
+    sum(round_placements.place)      as score,
+    sum(round_placements.vote_count) as vote_count
+  from
+    krumnet.game_member_round_placement_results as round_placements
+  inner join
+    krumnet.game_memberships as members
+  on
+    members.id = round_placements.member_id
+  where
+    round_placements.game_id = $1
+  group by
+    round_placements.game_id,
+    round_placements.lobby_id,
+    round_placements.user_id,
+    round_placements.member_id,
+    members.team_id
+), team_scores as (
+  select
+    team_rounds.team_id    as team_id,
+    sum(team_rounds.place) as score
+  from (
+    select
+      round_placements.team_id    as team_id,
+      min(round_placements.place) as place
+    from
+      krumnet.game_member_round_placement_results as round_placements
+    where
+      round_placements.game_id = $1
+    and
+      round_placements.team_id is not null
+    group by
+      round_placements.team_id,
+      round_placements.round_id
+  ) as team_rounds
+  group by
+    team_rounds.team_id
+) insert into
   krumnet.game_member_placement_results as game_placements
   (user_id, lobby_id, member_id, game_id, place, vote_count)
 select
-  round_placements.user_id         as user_id,
-  round_placements.lobby_id        as lobby_id,
-  round_placements.member_id       as member_id,
-  round_placements.game_id         as game_id,
-  row_number() over ()             as placement,
-  sum(round_placements.vote_count) as vote_count
+  totals.user_id    as user_id,
+  totals.lobby_id   as lobby_id,
+  totals.member_id  as member_id,
+  totals.game_id    as game_id,
+  case
+    when totals.team_id is null then row_number() over (order by totals.score asc)
+    else dense_rank() over (order by team_scores.score asc)
+  end               as placement,
+  totals.vote_count as vote_count
 from
-  krumnet.game_member_round_placement_results as round_placements
-where
-  round_placements.game_id = $1
-group by
-  round_placements.game_id,
-  round_placements.lobby_id,
-  round_placements.user_id,
-  round_placements.member_id
-order by
-  sum(round_placements.place) asc
+  totals
+left join
+  team_scores
+on
+  team_scores.team_id = totals.team_id
 on conflict on constraint
-  single_game_winner
+  single_member_game_placement
 do update set
   created_at = now()
 returning
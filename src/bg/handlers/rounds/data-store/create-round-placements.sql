@@ -1,28 +1,48 @@
-insert into
+with scores as (
+  select
+    entries.user_id   as user_id,
+    entries.lobby_id  as lobby_id,
+    entries.member_id as member_id,
+    entries.game_id   as game_id,
+    entries.round_id  as round_id,
+    entries.team_id   as team_id,
+    count(votes.id)   as vote_count
+  from
+    krumnet.game_round_entries as entries
+  left join
+    krumnet.game_round_entry_votes as votes
+  on
+    entries.id = votes.entry_id
+  where
+    entries.round_id = $1
+  group by
+    entries.id
+), places as (
+  select
+    scores.*,
+    row_number() over (order by scores.vote_count desc) as placement
+  from
+    scores
+) insert into
   krumnet.game_member_round_placement_results as placements
-  (user_id, lobby_id, member_id, game_id, round_id, place, vote_count)
+  (user_id, lobby_id, member_id, game_id, round_id, team_id, place, vote_count)
 select
-  entries.user_id                                   as submitter,
-  entries.lobby_id                                  as lobby_id,
-  entries.member_id                                 as member_id,
-  entries.game_id                                   as game_id,
-  entries.round_id                                  as round_id,
-  row_number() over (order by count(votes.id) desc) as placement,
-  count(votes.id)                                   as vote_count
+  coalesce(teammates.user_id, places.user_id) as user_id,
+  places.lobby_id                             as lobby_id,
+  coalesce(teammates.id, places.member_id)    as member_id,
+  places.game_id                              as game_id,
+  places.round_id                             as round_id,
+  places.team_id                              as team_id,
+  places.placement                            as placement,
+  places.vote_count                           as vote_count
 from
-  krumnet.game_round_entries as entries
+  places
 left join
-  krumnet.game_round_entry_votes as votes
+  krumnet.game_memberships as teammates
 on
-  entries.id = votes.entry_id
-where
-  entries.round_id = $1
-group by
-  entries.id
-order by
-  count(votes.id) desc
+  teammates.team_id = places.team_id
 on conflict on constraint
-  single_round_winner
+  single_member_round_placement
 do update set
   created_at = now()
 returning
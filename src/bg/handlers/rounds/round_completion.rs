@@ -1,4 +1,4 @@
-use super::utils::{count_entries, count_members};
+use super::utils::{count_entries, count_expected_entries, count_members};
 use crate::{bg::context::Context, interchange};
 use log::{debug, info, warn};
 use sqlx::query_file;
@@ -129,6 +129,21 @@ async fn mark_game_ended(context: &Context, game_id: &String) -> Result<(), Stri
   Ok(())
 }
 
+async fn queue_achievements(context: &Context, game_id: &String) -> Result<(), String> {
+  let job = interchange::jobs::Job::AwardAchievements(interchange::jobs::AwardAchievements {
+    game_id: game_id.clone(),
+    result: None,
+  });
+
+  info!("queuing achievements for game '{}'", game_id);
+  context
+    .jobs
+    .queue(&job)
+    .await
+    .map(|_| ())
+    .map_err(warn_and_stringify)
+}
+
 async fn round_completion_result(
   context: &Context,
   details: &interchange::jobs::CheckRoundCompletion,
@@ -137,8 +152,9 @@ async fn round_completion_result(
   let member_count = count_members(&context, &details.round_id).await?;
   let vote_count = count_votes(&details.round_id, context).await?;
   let entry_count = count_entries(&context, &details.round_id).await?;
+  let expected_count = count_expected_entries(context, &details.round_id).await?;
 
-  if vote_count != member_count || member_count != entry_count {
+  if vote_count != member_count || expected_count != entry_count {
     let rid = &details.round_id;
     info!("round {} incomplete ({}/{})", rid, vote_count, member_count);
     return Ok(interchange::jobs::CheckRoundCompletionResult::Incomplete);
@@ -156,6 +172,7 @@ async fn round_completion_result(
 
   if count != 0 {
     info!("{} remaining rounds for game '{}'", count, details.game_id);
+    queue_achievements(context, &details.game_id).await?;
     return Ok(interchange::jobs::CheckRoundCompletionResult::Intermediate(
       placement_ids,
     ));
@@ -168,6 +185,16 @@ async fn round_completion_result(
   info!("created placement results - {:?}", placement_ids);
 
   mark_game_ended(&context, &details.game_id).await?;
+
+  let job = interchange::jobs::Job::UpdateRatings(interchange::jobs::UpdateRatings {
+    game_id: details.game_id.clone(),
+    result: None,
+  });
+
+  info!("queuing rating update for game '{}'", details.game_id);
+  context.jobs.queue(&job).await.map_err(warn_and_stringify)?;
+  queue_achievements(context, &details.game_id).await?;
+
   Ok(interchange::jobs::CheckRoundCompletionResult::Final(
     placement_ids,
   ))
@@ -194,6 +221,7 @@ mod test {
     bg::handlers::rounds::check_round_fulfillment,
     bg::{context::Context, test_helpers},
     interchange,
+    test_helpers::{game_for_users, GameFixture},
   };
   use async_std::task::block_on;
   use sqlx::query;
@@ -440,4 +468,95 @@ mod test {
       cleanup_test_context(&context, test_context).await
     });
   }
+
+  #[test]
+  fn team_members_share_placements() {
+    block_on(async {
+      let test_name = "bg.handlers.round_completion.team_members_share_placements";
+      let (context, user_id) = test_helpers::get_test_context_with_user(test_name).await;
+      let mut others = vec![];
+
+      for i in 0..3 {
+        others.push(test_helpers::make_user(&context, &format!("{}.{}", test_name, i)).await);
+      }
+
+      let members = others.iter().collect::<Vec<&String>>();
+      let GameFixture { lobby_id, game_id } =
+        game_for_users(&context.records, &user_id, &members, Some(2)).await;
+      let mut conn = context.records.acquire().await.expect("unable to connect");
+      let test_context = TestContext {
+        user_id: user_id.clone(),
+        game_id: game_id.clone(),
+        lobby_id: lobby_id.clone(),
+      };
+
+      for position in 0..3 {
+        let round_id = get_round_id(&context, &game_id, position).await;
+
+        // One member of each team submits, and everyone votes for the first team's entry.
+        query!(
+          "
+          insert into krumnet.game_round_entries (user_id, member_id, round_id, game_id, lobby_id, team_id)
+          select distinct on (members.team_id)
+            members.user_id, members.id, $2, members.game_id, members.lobby_id, members.team_id
+          from krumnet.game_memberships as members where members.game_id = $1
+          order by members.team_id, members.id
+          ",
+          game_id,
+          round_id
+        )
+        .execute(&mut conn)
+        .await
+        .expect("unable to enter");
+        fulfill(&context, &round_id).await;
+        query!(
+          "
+          insert into krumnet.game_round_entry_votes (user_id, member_id, round_id, game_id, lobby_id, entry_id)
+          select members.user_id, members.id, $2, members.game_id, members.lobby_id, entries.id
+          from krumnet.game_memberships as members
+          inner join krumnet.game_teams as teams on teams.game_id = members.game_id and teams.position = 0
+          inner join krumnet.game_round_entries as entries on entries.team_id = teams.id and entries.round_id = $2
+          where members.game_id = $1
+          ",
+          game_id,
+          round_id
+        )
+        .execute(&mut conn)
+        .await
+        .expect("unable to vote");
+        complete(&context, &test_context, &round_id)
+          .await
+          .expect("unable to complete");
+
+        let rows = get_round_placements(&context, &round_id).await;
+        assert_eq!(rows.len(), 4);
+      }
+
+      let mut places = query!(
+        "
+        select teams.position, placements.place, placements.vote_count
+        from krumnet.game_member_placement_results as placements
+        inner join krumnet.game_memberships as members on members.id = placements.member_id
+        inner join krumnet.game_teams as teams on teams.id = members.team_id
+        where placements.game_id = $1
+        ",
+        game_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .expect("unable to load placements")
+      .into_iter()
+      .map(|row| (row.position, row.place, row.vote_count))
+      .collect::<Vec<(i32, i32, i32)>>();
+      places.sort();
+      assert_eq!(places, vec![(0, 1, 12), (0, 1, 12), (1, 2, 0), (1, 2, 0)]);
+
+      test_helpers::cleanup_game(&context, &game_id).await;
+      test_helpers::cleanup_lobby(&context, &lobby_id).await;
+      test_helpers::cleanup_user(&context, &user_id).await;
+      for oid in others {
+        test_helpers::cleanup_user(&context, &oid).await;
+      }
+    });
+  }
 }
@@ -1,4 +1,4 @@
-use super::utils::{count_entries, count_members};
+use super::utils::{count_entries, count_expected_entries};
 use crate::{bg::context::Context, interchange};
 use log::{debug, info, warn};
 use sqlx::query_file;
@@ -11,14 +11,14 @@ fn warn_and_stringify<E: std::error::Error>(e: E) -> String {
 async fn round_fulfillment_result(context: &Context, round_id: &String) -> Result<u8, String> {
   info!("checking fulfillment of round '{}'", round_id);
   let entry_count = count_entries(context, round_id).await?;
-  let member_count = count_members(context, round_id).await?;
+  let expected_count = count_expected_entries(context, round_id).await?;
 
   debug!(
-    "found member count {} and entry count {}",
-    member_count, entry_count
+    "found expected count {} and entry count {}",
+    expected_count, entry_count
   );
 
-  let diff = member_count as u8 - entry_count as u8;
+  let diff = expected_count as u8 - entry_count as u8;
 
   if diff != 0 {
     debug!("round has {} entries remaining, moving on", diff);
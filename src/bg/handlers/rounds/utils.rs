@@ -48,10 +48,36 @@ pub async fn count_members(context: &Context, round_id: &String) -> Result<i64,
   .ok_or(format!("Unable to count members for round '{}'", round_id))
 }
 
+// In free-for-all games every member is expected to submit an entry, while team games only expect
+// a single entry from each team.
+pub async fn count_expected_entries(context: &Context, round_id: &String) -> Result<i64, String> {
+  let mut conn = context
+    .records
+    .acquire()
+    .await
+    .map_err(warn_and_stringify)?;
+
+  query_file!(
+    "src/bg/handlers/rounds/data-store/count-expected-entries-for-round.sql",
+    round_id
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(warn_and_stringify)?
+  .into_iter()
+  .next()
+  .and_then(|row| row.expected_count)
+  .ok_or(format!(
+    "Unable to count expected entries for round '{}'",
+    round_id
+  ))
+}
+
 #[cfg(test)]
 mod tests {
-  use super::count_members;
-  use crate::bg::{context::Context, test_helpers};
+  use super::{count_expected_entries, count_members};
+  use crate::bg::{context::Context, handlers::lobbies::assign_teams, test_helpers};
+  use crate::test_helpers::{game_for_users, GameFixture};
   use async_std::task::block_on;
   use sqlx::query;
 
@@ -120,4 +146,42 @@ mod tests {
       test_helpers::cleanup_user(&context, &oid).await;
     });
   }
+
+  #[test]
+  fn count_expected_for_teams() {
+    block_on(async {
+      let (context, user_id) =
+        test_helpers::get_test_context_with_user("bg.rounds.utils.count_expected_for_teams").await;
+      let mut others = vec![];
+
+      for i in 0..3 {
+        let name = format!("bg.rounds.utils.count_expected_for_teams.{}", i);
+        others.push(test_helpers::make_user(&context, &name).await);
+      }
+
+      let members = others.iter().collect::<Vec<&String>>();
+      let GameFixture { lobby_id, game_id } =
+        game_for_users(&context.records, &user_id, &members, None).await;
+      let round_id = round_for_game(&context, &game_id, 0).await;
+      assert_eq!(
+        count_expected_entries(&context, &round_id).await.unwrap(),
+        4
+      );
+
+      let assigned = assign_teams(&context.records, &game_id, &lobby_id, 2).await;
+      assert_eq!(assigned.unwrap(), 4);
+      assert_eq!(count_members(&context, &round_id).await.unwrap(), 4);
+      assert_eq!(
+        count_expected_entries(&context, &round_id).await.unwrap(),
+        2
+      );
+
+      test_helpers::cleanup_game(&context, &game_id).await;
+      test_helpers::cleanup_lobby(&context, &lobby_id).await;
+      test_helpers::cleanup_user(&context, &user_id).await;
+      for oid in others {
+        test_helpers::cleanup_user(&context, &oid).await;
+      }
+    });
+  }
 }
@@ -0,0 +1,44 @@
+with removed_identities as (
+  delete from
+    krumnet.identities
+  where
+    user_id = $1
+  returning
+    id
+), removed_avatars as (
+  delete from
+    krumnet.user_avatars
+  where
+    user_id = $1
+  returning
+    user_id
+), removed_share_tokens as (
+  delete from
+    krumnet.game_share_tokens
+  where
+    created_by = $1
+  returning
+    id
+), removed_api_keys as (
+  delete from
+    krumnet.api_keys
+  where
+    user_id = $1
+  returning
+    id
+)
+update
+  krumnet.users
+set
+  name = $2,
+  default_email = 'deleted-' || id || '@' || $3,
+  avatar_url = null,
+  guest = false,
+  expired_at = coalesce(expired_at, now()),
+  deleted_at = now()
+where
+  id = $1
+and
+  deleted_at is null
+returning
+  id;
@@ -0,0 +1,87 @@
+use log::{info, warn};
+use sqlx::query_file;
+
+use crate::constants::{DELETED_EMAIL_DOMAIN, DELETED_USER_NAME};
+use crate::interchange::jobs::AnonymizeUser as AnonymizeContext;
+use crate::{bg::context::Context, interchange};
+
+fn log_and_serialize<E: std::error::Error>(error: E) -> String {
+  warn!("{}", error);
+  format!("{}", error)
+}
+
+async fn anonymize_inner(context: &Context, details: &AnonymizeContext) -> Result<String, String> {
+  let mut conn = context.records.acquire().await.map_err(log_and_serialize)?;
+
+  query_file!(
+    "src/bg/handlers/users/data-store/anonymize-user.sql",
+    details.user_id,
+    DELETED_USER_NAME,
+    DELETED_EMAIL_DOMAIN
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(log_and_serialize)?
+  .into_iter()
+  .next()
+  .map(|row| row.id)
+  .ok_or_else(|| format!("user '{}' missing or already deleted", details.user_id))
+}
+
+pub async fn anonymize(details: &AnonymizeContext, context: &Context) -> interchange::jobs::Job {
+  info!("anonymizing user '{}'", details.user_id);
+
+  interchange::jobs::Job::AnonymizeUser(AnonymizeContext {
+    user_id: details.user_id.clone(),
+    result: Some(anonymize_inner(context, details).await),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::anonymize_inner;
+  use crate::bg::test_helpers;
+  use crate::interchange::jobs::AnonymizeUser;
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  #[test]
+  fn anonymize_once() {
+    block_on(async {
+      let (context, user_id) =
+        test_helpers::get_test_context_with_user("users.anonymize_once").await;
+      let mut conn = context.records.acquire().await.expect("unable to connect");
+      query!(
+        "insert into krumnet.identities (user_id, provider, subject) values ($1, 'google', $1)",
+        user_id
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to insert identity");
+
+      let details = AnonymizeUser {
+        user_id: user_id.clone(),
+        result: None,
+      };
+      assert_eq!(
+        anonymize_inner(&context, &details).await,
+        Ok(user_id.clone())
+      );
+      assert!(anonymize_inner(&context, &details).await.is_err());
+
+      let row = query!(
+        "select name, default_email, expired_at, (select count(*) from krumnet.identities where user_id = $1) as identities from krumnet.users where id = $1",
+        user_id
+      )
+      .fetch_one(&mut conn)
+      .await
+      .expect("unable to load user");
+      assert_eq!(row.name, "Deleted user");
+      assert!(!row.default_email.contains("users.anonymize_once"));
+      assert!(row.expired_at.is_some());
+      assert_eq!(row.identities, Some(0));
+
+      test_helpers::cleanup_user(&context, &user_id).await;
+    });
+  }
+}
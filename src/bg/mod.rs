@@ -40,6 +40,22 @@ pub mod test_helpers {
   pub async fn cleanup_game(context: &Context, game_id: &String) {
     let mut conn = context.records.acquire().await.expect("no record store");
 
+    query!(
+      "delete from krumnet.game_share_tokens as tokens where tokens.game_id = $1",
+      game_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete game share tokens");
+
+    query!(
+      "delete from krumnet.user_achievements as achievements where achievements.game_id = $1",
+      game_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete game achievements");
+
     query!(
       "delete from krumnet.game_member_round_placement_results as results where results.game_id = $1",
       game_id
@@ -88,6 +104,14 @@ pub mod test_helpers {
     .await
     .expect("unable to delete game members");
 
+    query!(
+      "delete from krumnet.game_teams as teams where teams.game_id = $1",
+      game_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete game teams");
+
     query!(
       "delete from krumnet.games as games where games.id = $1",
       game_id
@@ -5,11 +5,14 @@ use log::{debug, info, warn};
 use std::env::args;
 use std::io::Result;
 use std::process::exit;
+use std::time::{Duration, Instant};
 
 use krumnet::{
   bg::context::Context,
-  bg::handlers::{game_memberships, lobbies, lobby_memberships, rounds},
-  interchange::jobs::{Job, QueuedJob},
+  bg::handlers::{
+    achievements, game_memberships, guests, lobbies, lobby_memberships, ratings, rounds, users,
+  },
+  interchange::jobs::{CleanupGuests, Job, QueuedJob},
   version, Configuration, JobStore, RecordStore,
 };
 
@@ -37,6 +40,10 @@ async fn execute<'a>(ctx: &Context, job: &QueuedJob) -> QueuedJob {
     Job::CreateGame(details) => lobbies::create_game(&job.id, &details, &ctx.records).await,
     Job::CleanupGameMembership(details) => game_memberships::cleanup(&details, &ctx).await,
     Job::CheckRoundCompletion(details) => rounds::check_round_completion(&details, &ctx).await,
+    Job::UpdateRatings(details) => ratings::update(details, ctx).await,
+    Job::AwardAchievements(details) => achievements::award_achievements(details, ctx).await,
+    Job::CleanupGuests(details) => guests::cleanup(details, ctx).await,
+    Job::AnonymizeUser(details) => users::anonymize(details, ctx).await,
   };
 
   QueuedJob {
@@ -71,10 +78,31 @@ fn main() -> Result<()> {
     };
 
     let mut fails = 0;
+    let guest_interval = Duration::from_secs(opts.config.guests.cleanup_interval);
+    let mut last_guest_cleanup: Option<Instant> = None;
 
     info!("backend stores connected successfully, starting dequeue");
 
     loop {
+      let cleanup_due = match last_guest_cleanup {
+        Some(last) => last.elapsed() >= guest_interval,
+        None => true,
+      };
+
+      if cleanup_due {
+        let cleanup = Job::CleanupGuests(CleanupGuests {
+          idle_timeout: opts.config.guests.idle_timeout,
+          result: None,
+        });
+
+        match jobs.queue(&cleanup).await {
+          Ok(id) => info!("queued guest cleanup job '{}'", id),
+          Err(e) => warn!("unable to queue guest cleanup - {}", e),
+        }
+
+        last_guest_cleanup = Some(Instant::now());
+      }
+
       let next = jobs.dequeue().await;
 
       match next {
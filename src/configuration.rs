@@ -2,20 +2,32 @@ extern crate serde;
 
 use log::{debug, warn};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env::var_os;
 use std::fs::read;
 use std::io::{Error, ErrorKind};
 use std::path::Path;
 use std::str::FromStr;
 
+use crate::errors;
+
 const DEFAULT_CONFIG_FILE: &'static str = "krumnet-config.json";
 const DEFAULT_POSTGRES_URI: &'static str = "postgresql://postgres@0.0.0.0:5432/krumnet";
+const DEFAULT_MIN_ENTRY_LENGTH: usize = 1;
+const DEFAULT_MAX_ENTRY_LENGTH: usize = 280;
+const DEFAULT_GUEST_IDLE_TIMEOUT: u64 = 60 * 60 * 24 * 7;
+const DEFAULT_GUEST_CLEANUP_INTERVAL: u64 = 60 * 60;
+const DEFAULT_KEEP_ALIVE_IDLE_TIMEOUT: u64 = 5;
+const DEFAULT_KEEP_ALIVE_MAX_REQUESTS: usize = 100;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Configuration {
   #[serde(default)]
   pub google: GoogleCredentials,
 
+  #[serde(default)]
+  pub providers: HashMap<String, ProviderConfiguration>,
+
   #[serde(default)]
   pub krumi: KrumiConfiguration,
 
@@ -28,14 +40,65 @@ pub struct Configuration {
   #[serde(default)]
   pub job_store: JobStoreConfiguration,
 
+  #[serde(default)]
+  pub entries: EntryConfiguration,
+
+  #[serde(default)]
+  pub guests: GuestConfiguration,
+
+  #[serde(default)]
+  pub keep_alive: KeepAliveConfiguration,
+
+  #[serde(default)]
+  pub admins: Vec<String>,
+
+  #[serde(default)]
+  pub dev_login: bool,
+
   #[serde(default)]
   pub addr: String,
 }
 
+// Hosts that are only reachable from the machine running krumnet.
+const LOCAL_HOSTS: [&str; 4] = ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"];
+
+fn is_local_uri(uri: &str) -> bool {
+  match url::Url::parse(uri) {
+    Ok(parsed) => parsed
+      .host_str()
+      .map(|host| LOCAL_HOSTS.contains(&host) || host.ends_with(".localhost"))
+      .unwrap_or(false),
+    Err(_) => uri.is_empty(),
+  }
+}
+
 impl Configuration {
   pub fn load(source: &str) -> Result<Self, Error> {
     Configuration::from_str(source)
   }
+
+  // Checks for combinations of settings that should never be used together. The dev login provider
+  // lets anyone sign in as anybody, so it is only allowed when every uri krumi and google are
+  // configured with points back at the local machine.
+  pub fn validate(&self) -> Result<(), Error> {
+    if !self.dev_login {
+      return Ok(());
+    }
+
+    let uris = [
+      &self.krumi.auth_uri,
+      &self.krumi.cors_origin,
+      &self.google.redirect_uri,
+    ];
+
+    match uris.iter().find(|uri| !is_local_uri(uri)) {
+      Some(uri) => Err(errors::e(format!(
+        "dev_login cannot be enabled with non-local uri '{}' configured",
+        uri
+      ))),
+      None => Ok(()),
+    }
+  }
 }
 
 impl Default for Configuration {
@@ -57,11 +120,17 @@ impl Default for Configuration {
     let krumi = KrumiConfiguration::default();
     Configuration {
       google,
+      providers: HashMap::new(),
       krumi,
       addr: String::from("0.0.0.0:8080"),
       session_store: SessionStoreConfiguration::default(),
       record_store: RecordStoreConfiguration::default(),
       job_store: JobStoreConfiguration::default(),
+      entries: EntryConfiguration::default(),
+      guests: GuestConfiguration::default(),
+      keep_alive: KeepAliveConfiguration::default(),
+      admins: Vec::new(),
+      dev_login: false,
     }
   }
 }
@@ -82,6 +151,44 @@ impl FromStr for Configuration {
   }
 }
 
+fn default_oidc_scopes() -> Vec<String> {
+  vec!["openid".into(), "email".into(), "profile".into()]
+}
+
+fn default_github_scopes() -> Vec<String> {
+  vec!["read:user".into(), "user:email".into()]
+}
+
+// Any OpenID Connect provider; endpoints are loaded from the discovery document of the issuer.
+#[derive(Clone, Debug, Deserialize)]
+pub struct OidcConfiguration {
+  pub issuer: String,
+  pub client_id: String,
+  pub client_secret: String,
+  pub redirect_uri: String,
+
+  #[serde(default = "default_oidc_scopes")]
+  pub scopes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GithubConfiguration {
+  pub client_id: String,
+  pub client_secret: String,
+  pub redirect_uri: String,
+
+  #[serde(default = "default_github_scopes")]
+  pub scopes: Vec<String>,
+}
+
+// Login providers in addition to google, keyed by the name used in their `/auth/{provider}` routes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProviderConfiguration {
+  Oidc(OidcConfiguration),
+  Github(GithubConfiguration),
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct GoogleCredentials {
   #[serde(default)]
@@ -130,6 +237,11 @@ pub struct KrumiConfiguration {
 
   #[serde(default)]
   pub cors_origin: String,
+
+  // When set, logins finish by setting an HttpOnly session cookie instead of handing the tokens to
+  // krumi in the query string of the callback.
+  #[serde(default)]
+  pub session_cookie: bool,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -168,6 +280,92 @@ pub struct SessionStoreConfiguration {
   pub secret: String,
   pub session_prefix: String,
   pub expiration_timeout: Option<u64>,
+  pub refresh_timeout: Option<u64>,
+  pub issuer: Option<String>,
+
+  #[serde(default)]
+  pub previous_secrets: Vec<String>,
+}
+
+// Determines what happens to a round entry that contains a word from the configured word list;
+// either the entry is rejected outright or the offending words are masked before it is stored.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryFilterMode {
+  Reject,
+  Mask,
+}
+
+// Written out by hand; `#[default]` on a variant needs a newer toolchain than the one CI builds with.
+#[allow(clippy::derivable_impls)]
+impl Default for EntryFilterMode {
+  fn default() -> Self {
+    EntryFilterMode::Reject
+  }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EntryFilterConfiguration {
+  #[serde(default)]
+  pub words: Vec<String>,
+
+  #[serde(default)]
+  pub mode: EntryFilterMode,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EntryConfiguration {
+  pub min_length: usize,
+  pub max_length: usize,
+  pub filter: EntryFilterConfiguration,
+}
+
+impl Default for EntryConfiguration {
+  fn default() -> Self {
+    EntryConfiguration {
+      min_length: DEFAULT_MIN_ENTRY_LENGTH,
+      max_length: DEFAULT_MAX_ENTRY_LENGTH,
+      filter: EntryFilterConfiguration::default(),
+    }
+  }
+}
+
+// Guest accounts have no identity to log back in with, so once they have been idle for longer than
+// `idle_timeout` seconds they are expired by a job the worker queues every `cleanup_interval`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GuestConfiguration {
+  pub idle_timeout: u64,
+  pub cleanup_interval: u64,
+}
+
+impl Default for GuestConfiguration {
+  fn default() -> Self {
+    GuestConfiguration {
+      idle_timeout: DEFAULT_GUEST_IDLE_TIMEOUT,
+      cleanup_interval: DEFAULT_GUEST_CLEANUP_INTERVAL,
+    }
+  }
+}
+
+// Connections are kept open between requests for clients that allow it; a connection is closed once
+// no new request has started within `idle_timeout` seconds or after it has served `max_requests`.
+// Setting `max_requests` to 1 closes every connection after its first response.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct KeepAliveConfiguration {
+  pub idle_timeout: u64,
+  pub max_requests: usize,
+}
+
+impl Default for KeepAliveConfiguration {
+  fn default() -> Self {
+    KeepAliveConfiguration {
+      idle_timeout: DEFAULT_KEEP_ALIVE_IDLE_TIMEOUT,
+      max_requests: DEFAULT_KEEP_ALIVE_MAX_REQUESTS,
+    }
+  }
 }
 
 #[cfg(test)]
@@ -199,4 +397,19 @@ mod test {
     let result = Configuration::load("does-not-exist");
     assert_eq!(result.is_err(), true);
   }
+
+  #[test]
+  fn dev_login_local_only() {
+    let mut config = Configuration::load("krumnet-config.example.json").unwrap();
+    assert!(config.validate().is_ok());
+
+    config.dev_login = true;
+    assert!(config.validate().is_ok());
+
+    config.krumi.auth_uri = String::from("https://krumpled.com/auth/callback");
+    assert!(config.validate().is_err());
+
+    config.dev_login = false;
+    assert!(config.validate().is_ok());
+  }
 }
@@ -1,16 +1,49 @@
 pub const MAX_FILE_SIZE: usize = 1000000usize;
 pub const MAX_LOBBY_MEMBERS: u8 = 10;
+pub const DEFAULT_PAGE_SIZE: i64 = 25;
+pub const MAX_PAGE_SIZE: i64 = 100;
+pub const MAX_BATCH_IDS: usize = 25;
+pub const DEFAULT_REFRESH_TIMEOUT: u64 = 60 * 60 * 24 * 30;
+pub const DEFAULT_SESSION_ISSUER: &str = "krumnet";
+pub const TOKEN_LEEWAY: u64 = 60;
+pub const SESSION_TOUCH_INTERVAL: u64 = 60;
+pub const OAUTH_STATE_TIMEOUT: u64 = 60 * 10;
+pub const MAX_USER_NAME_LENGTH: usize = 32;
+pub const MAX_AVATAR_URL_LENGTH: usize = 2048;
+pub const MAX_AVATAR_SIZE: usize = 256 * 1024;
+pub const GUEST_EMAIL_DOMAIN: &str = "guests.krumnet.invalid";
+pub const DELETED_USER_NAME: &str = "Deleted user";
+pub const DELETED_EMAIL_DOMAIN: &str = "deleted.krumnet.invalid";
+pub const API_KEY_PREFIX: &str = "krk_";
+pub const API_KEY_DISPLAY_LENGTH: usize = 12;
+pub const MAX_API_KEY_NAME_LENGTH: usize = 64;
+pub const MAX_API_KEYS: i64 = 10;
+pub const SESSION_COOKIE_NAME: &str = "krumnet_session";
+pub const CSRF_COOKIE_NAME: &str = "krumnet_csrf";
+pub const OAUTH_COOKIE_NAME: &str = "krumnet_oauth";
+pub const CSRF_HEADER_NAME: &str = "x-csrf-token";
 
 pub const GOOGLE_TOKEN_URL: &'static str = "https://www.googleapis.com/oauth2/v4/token";
 pub const GOOGLE_AUTH_URL: &'static str = "https://accounts.google.com/o/oauth2/v2/auth";
 pub const GOOGLE_INFO_URL: &'static str = "https://openidconnect.googleapis.com/v1/userinfo";
 
-pub const GOOGLE_AUTH_RESPONSE_TYPE_KEY: &'static str = "response_type";
-pub const GOOGLE_AUTH_RESPONSE_TYPE_VALUE: &'static str = "code";
-pub const GOOGLE_AUTH_CLIENT_ID_KEY: &'static str = "client_id";
-pub const GOOGLE_AUTH_REDIRECT_URI_KEY: &'static str = "redirect_uri";
-pub const GOOGLE_AUTH_SCOPE_KEY: &'static str = "scope";
+pub const GITHUB_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+pub const GITHUB_AUTH_URL: &str = "https://github.com/login/oauth/authorize";
+pub const GITHUB_USER_URL: &str = "https://api.github.com/user";
+pub const GITHUB_EMAILS_URL: &str = "https://api.github.com/user/emails";
+
+pub const OIDC_DISCOVERY_PATH: &str = ".well-known/openid-configuration";
+
+pub const OAUTH_RESPONSE_TYPE_KEY: &'static str = "response_type";
+pub const OAUTH_RESPONSE_TYPE_VALUE: &'static str = "code";
+pub const OAUTH_CLIENT_ID_KEY: &'static str = "client_id";
+pub const OAUTH_REDIRECT_URI_KEY: &'static str = "redirect_uri";
+pub const OAUTH_SCOPE_KEY: &'static str = "scope";
 pub const GOOGLE_AUTH_SCOPE_VALUE: &'static str = "email profile";
+pub const OAUTH_STATE_KEY: &str = "state";
+pub const OAUTH_CODE_CHALLENGE_KEY: &str = "code_challenge";
+pub const OAUTH_CODE_CHALLENGE_METHOD_KEY: &str = "code_challenge_method";
+pub const OAUTH_CODE_CHALLENGE_METHOD_VALUE: &str = "S256";
 
 pub const KRUMI_SESSION_ID_KEY: &'static str = "session_id";
 
@@ -40,3 +73,39 @@ pub fn google_token_url() -> String {
 pub fn google_token_url() -> String {
   String::from(&mockito::server_url())
 }
+
+#[cfg(not(test))]
+pub fn github_auth_url() -> String {
+  String::from(GITHUB_AUTH_URL)
+}
+#[cfg(test)]
+pub fn github_auth_url() -> String {
+  format!("{}/github/authorize", mockito::server_url())
+}
+
+#[cfg(not(test))]
+pub fn github_token_url() -> String {
+  String::from(GITHUB_TOKEN_URL)
+}
+#[cfg(test)]
+pub fn github_token_url() -> String {
+  format!("{}/github/token", mockito::server_url())
+}
+
+#[cfg(not(test))]
+pub fn github_user_url() -> String {
+  String::from(GITHUB_USER_URL)
+}
+#[cfg(test)]
+pub fn github_user_url() -> String {
+  format!("{}/github/user", mockito::server_url())
+}
+
+#[cfg(not(test))]
+pub fn github_emails_url() -> String {
+  String::from(GITHUB_EMAILS_URL)
+}
+#[cfg(test)]
+pub fn github_emails_url() -> String {
+  format!("{}/github/user/emails", mockito::server_url())
+}
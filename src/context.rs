@@ -1,12 +1,16 @@
 use async_std::sync::Arc;
-use elaine::Head;
+use elaine::{Head, RequestMethod};
 use log::{debug, warn};
 use sqlx::query_file;
 use std::io::Result;
 
-use crate::http::AUTHORIZATION;
+use crate::constants::{CSRF_HEADER_NAME, OAUTH_COOKIE_NAME, SESSION_COOKIE_NAME};
+use crate::http::{
+  bearer_token, cookie_value, header::USER_AGENT, is_safe_method, AUTHORIZATION, COOKIE,
+};
 use crate::{
-  errors, Authority, Configuration, JobStore, RecordConnection, RecordStore, SessionStore,
+  api_keys, errors, session, Authority, Configuration, JobStore, RecordConnection, RecordStore,
+  Role, Scope, SessionStore,
 };
 
 pub struct Context {
@@ -16,6 +20,8 @@ pub struct Context {
   _jobs: Arc<JobStore>,
   _config: Configuration,
   _pending: usize,
+  _user_agent: Option<String>,
+  _oauth_browser: Option<String>,
 }
 
 impl Context {
@@ -27,6 +33,15 @@ impl Context {
     self._pending
   }
 
+  pub fn user_agent(&self) -> Option<&str> {
+    self._user_agent.as_deref()
+  }
+
+  // The cookie set when this browser started an oauth flow, if any.
+  pub fn oauth_browser(&self) -> Option<&str> {
+    self._oauth_browser.as_deref()
+  }
+
   pub fn jobs(&self) -> &JobStore {
     &self._jobs
   }
@@ -62,7 +77,7 @@ impl std::fmt::Debug for Context {
   }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct ContextBuilder {
   _session: Option<Arc<SessionStore>>,
   _records: Option<Arc<RecordStore>>,
@@ -79,43 +94,161 @@ pub async fn load_authorization(
 ) -> Result<Authority> {
   let uid = session.get(&token).await?;
   let mut conn = records.acquire().await?;
-  let tenant = query_file!("src/data-store/user-for-session.sql", uid)
+  let row = query_file!("src/data-store/user-for-session.sql", uid)
     .fetch_all(&mut conn)
     .await
     .map_err(errors::humanize_error)?
     .into_iter()
-    .nth(0)
-    .and_then(|row| {
-      let id = row.user_id;
-
-      debug!("found user '{:?}'", id);
+    .nth(0);
+
+  let tenant = match row {
+    Some(row) => {
+      debug!("found user '{:?}'", row.user_id);
+
+      // Guests are expired based on their activity, which is tracked here rather than with the
+      // session since the cleanup job only has access to the record store.
+      if row.guest {
+        query_file!("src/data-store/touch-guest.sql", row.user_id)
+          .execute(&mut conn)
+          .await
+          .map_err(errors::humanize_error)?;
+      }
+
+      let roles = query_file!("src/data-store/load-user-roles.sql", row.user_id)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(errors::humanize_error)?
+        .into_iter()
+        .filter_map(|role| Role::from_name(&role.role))
+        .collect();
 
       Some(Authority::User {
-        id,
+        id: row.user_id,
         token: token.clone(),
+        roles,
+        key: None,
       })
-    });
+    }
+    None => None,
+  };
+
+  if let Some(Authority::User { id, .. }) = &tenant {
+    session.touch(&token, id).await?;
+  }
 
   Ok(tenant.unwrap_or(Authority::None))
 }
 
+// Exchanges an api key for the user that owns it, recording its use. Keys never carry the roles of
+// their owner and are only accepted for requests their scopes permit.
+pub async fn load_key_authorization(
+  key: &str,
+  method: &RequestMethod,
+  records: &RecordStore,
+) -> Result<Authority> {
+  let mut conn = records.acquire().await?;
+  let row = query_file!("src/data-store/find-api-key.sql", api_keys::digest(key))
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+  let row = match row {
+    Some(row) => row,
+    None => {
+      warn!(
+        "rejected unknown api key '{}'",
+        api_keys::display_prefix(key)
+      );
+      return Ok(Authority::None);
+    }
+  };
+
+  let permitted = row
+    .scopes
+    .iter()
+    .filter_map(|name| Scope::from_name(name))
+    .any(|scope| scope.permits(method));
+
+  if !permitted {
+    warn!("api key '{}' not scoped for {:?}", row.key_id, method);
+    return Ok(Authority::None);
+  }
+
+  query_file!("src/data-store/touch-api-key.sql", row.key_id)
+    .execute(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?;
+
+  debug!("found user '{}' for api key '{}'", row.user_id, row.key_id);
+  Ok(Authority::User {
+    id: row.user_id,
+    token: String::new(),
+    roles: Vec::new(),
+    key: Some(row.key_id),
+  })
+}
+
+// Cookie sessions are only accepted for requests that can change something when they also carry
+// the matching csrf token in a header, which a page on another site has no way of reading.
+fn cookie_token(head: &Head, method: &RequestMethod) -> Option<String> {
+  let token = head
+    .find_header(COOKIE)
+    .and_then(|header| cookie_value(&header, SESSION_COOKIE_NAME))
+    .filter(|token| !token.is_empty())?;
+
+  if is_safe_method(method) {
+    return Some(token);
+  }
+
+  match head.find_header(CSRF_HEADER_NAME) {
+    Some(csrf) if csrf == session::csrf_token(&token) => Some(token),
+    _ => {
+      warn!("rejecting cookie session without matching csrf token");
+      None
+    }
+  }
+}
+
 async fn load_auth(
   head: &Head,
   session: &SessionStore,
   records: &RecordStore,
+  cookies: bool,
 ) -> Result<Authority> {
-  if let Some(value) = head.find_header(AUTHORIZATION) {
-    debug!("found authorization header - {}", value);
-    return load_authorization(value, session, records)
+  let method = head.method().ok_or_else(|| errors::e("invalid method"))?;
+
+  let token = match head.find_header(AUTHORIZATION) {
+    Some(value) => Some(String::from(bearer_token(&value))),
+    None if cookies => cookie_token(head, &method),
+    None => None,
+  };
+
+  let token = match token {
+    Some(token) => token,
+    None => {
+      debug!("no authorization header present");
+      return Ok(Authority::None);
+    }
+  };
+
+  if api_keys::is_api_key(&token) {
+    return load_key_authorization(&token, &method, records)
       .await
       .or_else(|e| {
-        warn!("unable to load authorization - {}", e);
+        warn!("unable to load api key authorization - {}", e);
         Ok(Authority::None)
       });
   }
 
-  debug!("no authorization header present");
-  Ok(Authority::None)
+  debug!("found authorization token - {}", token);
+  load_authorization(token, session, records)
+    .await
+    .or_else(|e| {
+      warn!("unable to load authorization - {}", e);
+      Ok(Authority::None)
+    })
 }
 
 impl ContextBuilder {
@@ -171,6 +304,8 @@ impl ContextBuilder {
       _session,
       _records,
       _pending: 0,
+      _user_agent: None,
+      _oauth_browser: None,
     })
   }
 
@@ -185,9 +320,43 @@ impl ContextBuilder {
       .as_ref()
       .ok_or(errors::e("missing session configuration for context"))?;
 
-    let auth = load_auth(head, session, records).await?;
+    let cookies = self
+      ._config
+      .as_ref()
+      .map(|c| c.krumi.session_cookie)
+      .unwrap_or_default();
+
+    let auth = match load_auth(head, session, records, cookies).await? {
+      // Users listed in the configuration are always admins, which allows granting the first role.
+      Authority::User {
+        id,
+        token,
+        mut roles,
+        key: None,
+      } => {
+        let configured = self._config.as_ref().map(|c| c.admins.contains(&id));
+
+        if configured == Some(true) && !roles.contains(&Role::Admin) {
+          roles.push(Role::Admin);
+        }
+
+        Authority::User {
+          id,
+          token,
+          roles,
+          key: None,
+        }
+      }
+      auth => auth,
+    };
+
     Ok(Context {
       _pending: head.len().unwrap_or_default(),
+      _user_agent: head.find_header(USER_AGENT),
+      _oauth_browser: head
+        .find_header(COOKIE)
+        .and_then(|header| cookie_value(&header, OAUTH_COOKIE_NAME))
+        .filter(|value| !value.is_empty()),
       ..self.with_authority(auth)?
     })
   }
@@ -197,7 +366,7 @@ impl ContextBuilder {
 pub mod test_helpers {
   use super::Context;
   pub use crate::configuration::test_helpers::load_test_config as load_config;
-  use crate::{Authority, JobStore, RecordStore, SessionStore};
+  use crate::{Authority, Configuration, JobStore, RecordStore, SessionStore};
   use async_std::task::block_on;
   use sqlx::query;
   use std::sync::Arc;
@@ -206,6 +375,25 @@ pub mod test_helpers {
     let config = load_config().unwrap();
     let records = RecordStore::open(&config).await.unwrap();
     let mut conn = records.acquire().await.expect("unable to connect");
+    query!("delete from krumnet.identities where user_id = $1", id)
+      .execute(&mut conn)
+      .await
+      .expect("unable to delete");
+    query!("delete from krumnet.user_avatars where user_id = $1", id)
+      .execute(&mut conn)
+      .await
+      .expect("unable to delete");
+    query!("delete from krumnet.api_keys where user_id = $1", id)
+      .execute(&mut conn)
+      .await
+      .expect("unable to delete");
+    query!(
+      "delete from krumnet.user_roles where user_id = $1 or granted_by = $1",
+      id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
     query!("delete from krumnet.users where id = $1", id)
       .execute(&mut conn)
       .await
@@ -243,10 +431,7 @@ pub mod test_helpers {
     let session = Arc::new(SessionStore::open(&config).await.unwrap());
     let records = Arc::new(records);
     let jobs = Arc::new(JobStore::open(&config).await.unwrap());
-    let auth = Authority::User {
-      id: user_id.clone(),
-      token: String::from(""),
-    };
+    let auth = Authority::user(user_id.clone(), String::new());
 
     let ctx = Context::builder()
       .configuration(&config)
@@ -259,9 +444,19 @@ pub mod test_helpers {
     (ctx, user_id)
   }
 
+  pub fn with_oauth_browser(context: Context, browser: &str) -> Context {
+    Context {
+      _oauth_browser: Some(String::from(browser)),
+      ..context
+    }
+  }
+
   pub fn with_auth(auth: Authority) -> Context {
+    with_config(load_config().unwrap(), auth)
+  }
+
+  pub fn with_config(config: Configuration, auth: Authority) -> Context {
     block_on(async {
-      let config = load_config().unwrap();
       let session = Arc::new(SessionStore::open(&config).await.unwrap());
       let records = Arc::new(RecordStore::open(&config).await.unwrap());
       let jobs = Arc::new(JobStore::open(&config).await.unwrap());
@@ -278,11 +473,94 @@ pub mod test_helpers {
 
 #[cfg(test)]
 mod test {
-  use super::test_helpers::with_auth;
-  use crate::Authority;
+  use super::test_helpers::{with_auth, with_user_by_name};
+  use super::Context;
+  use crate::{session, Authority, JobStore, RecordStore, SessionStore};
+  use async_std::sync::Arc;
+  use async_std::task::block_on;
+  use elaine::recognize;
+
+  async fn authority_for(context: &Context, request: String, cookies: bool) -> Authority {
+    let mut config = context.config().clone();
+    config.krumi.session_cookie = cookies;
+    let head = recognize(&mut request.as_bytes()).await.unwrap();
+
+    Context::builder()
+      .configuration(&config)
+      .session(Arc::new(SessionStore::open(&config).await.unwrap()))
+      .records(Arc::new(RecordStore::open(&config).await.unwrap()))
+      .jobs(Arc::new(JobStore::open(&config).await.unwrap()))
+      .for_request(&head)
+      .await
+      .unwrap()
+      .authority()
+      .clone()
+  }
 
   #[test]
   fn test_none_authority() {
     assert_eq!(with_auth(Authority::None).authority(), &Authority::None);
   }
+
+  #[test]
+  fn oauth_browser_cookie() {
+    block_on(async {
+      let config = with_auth(Authority::None).config().clone();
+      let request = "GET /auth/callback HTTP/1.1\r\nCookie: theme=dark; krumnet_oauth=abc\r\n\r\n";
+      let head = recognize(&mut request.as_bytes()).await.unwrap();
+
+      let context = Context::builder()
+        .configuration(&config)
+        .session(Arc::new(SessionStore::open(&config).await.unwrap()))
+        .records(Arc::new(RecordStore::open(&config).await.unwrap()))
+        .jobs(Arc::new(JobStore::open(&config).await.unwrap()))
+        .for_request(&head)
+        .await
+        .unwrap();
+
+      assert_eq!(context.oauth_browser(), Some("abc"));
+      assert_eq!(with_auth(Authority::None).oauth_browser(), None);
+    });
+  }
+
+  #[test]
+  fn bearer_and_cookie_sessions() {
+    block_on(async {
+      let (context, uid) = with_user_by_name("context.bearer_and_cookie_sessions").await;
+      let tokens = context.session().create(&uid, None).await.unwrap();
+      let expected = Authority::user(uid.clone(), tokens.token.clone());
+      let csrf = session::csrf_token(&tokens.token);
+
+      let bearer = format!(
+        "GET /auth/identify HTTP/1.1\r\nAuthorization: Bearer {}\r\n\r\n",
+        tokens.token
+      );
+      assert_eq!(authority_for(&context, bearer, false).await, expected);
+
+      let cookie = format!(
+        "GET /auth/identify HTTP/1.1\r\nCookie: krumnet_session={}\r\n\r\n",
+        tokens.token
+      );
+      assert_eq!(
+        authority_for(&context, cookie.clone(), false).await,
+        Authority::None
+      );
+      assert_eq!(authority_for(&context, cookie, true).await, expected);
+
+      let forged = format!(
+        "POST /lobbies HTTP/1.1\r\nCookie: krumnet_session={}\r\n\r\n",
+        tokens.token
+      );
+      assert_eq!(authority_for(&context, forged, true).await, Authority::None);
+
+      let protected = format!(
+        "POST /lobbies HTTP/1.1\r\nCookie: krumnet_session={}\r\nX-CSRF-Token: {}\r\n\r\n",
+        tokens.token, csrf
+      );
+      assert_eq!(authority_for(&context, protected, true).await, expected);
+
+      context.session().revoke_all(&uid).await.unwrap();
+      super::test_helpers::cleanup(&context).await;
+    });
+  }
 }
@@ -0,0 +1,8 @@
+update
+  krumnet.users
+set
+  avatar_url = $2
+where
+  id = $1
+and
+  avatar_url is null;
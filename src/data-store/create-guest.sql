@@ -0,0 +1,6 @@
+insert into krumnet.users
+  (default_email, name, guest)
+values
+  ($1, $2, true)
+returning
+  id;
@@ -0,0 +1,6 @@
+insert into krumnet.identities
+  (user_id, provider, subject, email, name)
+values
+  ($1, $2, $3, $4, $5)
+returning
+  id;
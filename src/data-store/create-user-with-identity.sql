@@ -0,0 +1,39 @@
+with available_name as (
+  select
+    candidates.name
+  from (
+    select
+      suffix,
+      case when suffix = 1 then $2 else $2 || ' ' || suffix end as name
+    from
+      generate_series(1, 100) as suffix
+  ) as candidates
+  where
+    not exists (
+      select
+        1
+      from
+        krumnet.users as users
+      where
+        lower(users.name) = lower(candidates.name)
+      and
+        users.deleted_at is null
+    )
+  order by
+    candidates.suffix
+  limit 1
+), new_user as (
+    insert into krumnet.users
+      (default_email, name, avatar_url)
+    select
+      $1, available_name.name, $5
+    from
+      available_name
+    returning id
+) insert into krumnet.identities
+    (email, name, provider, subject, user_id)
+  select
+    $1, $2, $3, $4, new_user.id
+  from new_user
+returning
+  user_id;
@@ -0,0 +1,17 @@
+select
+  keys.id      as key_id,
+  keys.user_id as user_id,
+  keys.scopes  as scopes
+from
+  krumnet.api_keys as keys
+inner join
+  krumnet.users as users
+on
+  users.id = keys.user_id
+where
+  keys.key_hash = $1
+and
+  keys.revoked_at is null
+and
+  users.expired_at is null
+limit 1;
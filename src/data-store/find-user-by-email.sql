@@ -0,0 +1,7 @@
+select
+  users.id as user_id
+from
+  krumnet.users as users
+where
+  lower(users.default_email) = lower($1)
+limit 1;
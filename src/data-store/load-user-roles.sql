@@ -0,0 +1,6 @@
+select
+  roles.role as role
+from
+  krumnet.user_roles as roles
+where
+  roles.user_id = $1;
@@ -0,0 +1,7 @@
+update
+  krumnet.api_keys
+set
+  use_count    = use_count + 1,
+  last_used_at = now()
+where
+  id = $1;
@@ -0,0 +1,8 @@
+update
+  krumnet.users
+set
+  last_seen_at = now()
+where
+  id = $1
+and
+  guest = true;
@@ -0,0 +1,11 @@
+update
+  krumnet.users
+set
+  guest = false,
+  default_email = coalesce($2, default_email)
+where
+  id = $1
+and
+  guest = true
+returning
+  id;
@@ -0,0 +1,163 @@
+use crate::configuration::{EntryConfiguration, EntryFilterMode};
+
+const EMPTY: &str = "errors.entries.empty";
+const TOO_SHORT: &str = "errors.entries.too_short";
+const TOO_LONG: &str = "errors.entries.too_long";
+const FILTERED: &str = "errors.entries.filtered";
+
+#[derive(Debug, PartialEq)]
+pub enum EntryError {
+  Empty,
+  TooShort,
+  TooLong,
+  Filtered,
+}
+
+impl EntryError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      EntryError::Empty => EMPTY,
+      EntryError::TooShort => TOO_SHORT,
+      EntryError::TooLong => TOO_LONG,
+      EntryError::Filtered => FILTERED,
+    }
+  }
+}
+
+impl std::fmt::Display for EntryError {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(formatter, "{}", self.code())
+  }
+}
+
+// Collapses every run of whitespace (including newlines) into a single space and trims the ends.
+fn normalize_whitespace(entry: &str) -> String {
+  entry.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn is_filtered(words: &[String], word: &str) -> bool {
+  let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+  !bare.is_empty()
+    && words
+      .iter()
+      .any(|w| w.to_lowercase() == bare.to_lowercase())
+}
+
+fn mask(word: &str) -> String {
+  let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+  word.replacen(bare, &"*".repeat(bare.chars().count()), 1)
+}
+
+// Applies the configured word list to an already-normalized entry. Matching is done on whole
+// words, ignoring case and any surrounding punctuation.
+fn apply_filter(config: &EntryConfiguration, entry: String) -> Result<String, EntryError> {
+  let words = &config.filter.words;
+
+  if !entry.split(' ').any(|word| is_filtered(words, word)) {
+    return Ok(entry);
+  }
+
+  match config.filter.mode {
+    EntryFilterMode::Reject => Err(EntryError::Filtered),
+    EntryFilterMode::Mask => Ok(
+      entry
+        .split(' ')
+        .map(|word| match is_filtered(words, word) {
+          true => mask(word),
+          false => String::from(word),
+        })
+        .collect::<Vec<String>>()
+        .join(" "),
+    ),
+  }
+}
+
+// Prepares a round entry submitted by a player for storage, returning the value that should be
+// persisted or the reason it was refused.
+pub fn normalize(config: &EntryConfiguration, entry: &str) -> Result<String, EntryError> {
+  let normalized = normalize_whitespace(entry);
+  let length = normalized.chars().count();
+
+  if length == 0 {
+    return Err(EntryError::Empty);
+  }
+
+  if length < config.min_length {
+    return Err(EntryError::TooShort);
+  }
+
+  if length > config.max_length {
+    return Err(EntryError::TooLong);
+  }
+
+  apply_filter(config, normalized)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{normalize, EntryError};
+  use crate::configuration::{EntryConfiguration, EntryFilterConfiguration, EntryFilterMode};
+
+  fn config_with_words(mode: EntryFilterMode) -> EntryConfiguration {
+    EntryConfiguration {
+      filter: EntryFilterConfiguration {
+        words: vec![String::from("darn")],
+        mode,
+      },
+      ..EntryConfiguration::default()
+    }
+  }
+
+  #[test]
+  fn collapses_whitespace() {
+    let config = EntryConfiguration::default();
+    let result = normalize(&config, "  a \n\n very\tgood   entry ");
+    assert_eq!(result, Ok(String::from("a very good entry")));
+  }
+
+  #[test]
+  fn rejects_empty() {
+    let config = EntryConfiguration::default();
+    assert_eq!(normalize(&config, " \n\t "), Err(EntryError::Empty));
+  }
+
+  #[test]
+  fn rejects_by_length() {
+    let config = EntryConfiguration {
+      min_length: 3,
+      max_length: 5,
+      ..EntryConfiguration::default()
+    };
+    assert_eq!(normalize(&config, "ab"), Err(EntryError::TooShort));
+    assert_eq!(normalize(&config, "abcdef"), Err(EntryError::TooLong));
+    assert_eq!(normalize(&config, " abc  "), Ok(String::from("abc")));
+  }
+
+  #[test]
+  fn rejects_filtered_words() {
+    let config = config_with_words(EntryFilterMode::Reject);
+    assert_eq!(normalize(&config, "well, DARN!"), Err(EntryError::Filtered));
+    assert_eq!(
+      normalize(&config, "darnation"),
+      Ok(String::from("darnation"))
+    );
+  }
+
+  #[test]
+  fn masks_filtered_words() {
+    let config = config_with_words(EntryFilterMode::Mask);
+    assert_eq!(
+      normalize(&config, "well,  Darn!"),
+      Ok(String::from("well, ****!"))
+    );
+  }
+
+  #[test]
+  fn error_codes() {
+    assert_eq!(format!("{}", EntryError::Empty), "errors.entries.empty");
+    assert_eq!(
+      format!("{}", EntryError::TooLong),
+      "errors.entries.too_long"
+    );
+  }
+}
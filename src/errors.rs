@@ -1,9 +1,183 @@
+use http::StatusCode;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::any::Any;
 use std::io::{Error, ErrorKind};
 
-pub fn humanize_error<E: std::error::Error>(e: E) -> Error {
-  Error::new(ErrorKind::Other, format!("{}", e))
+pub const BAD_REQUEST: &str = "errors.bad_request";
+pub const UNAUTHORIZED: &str = "errors.unauthorized";
+pub const NOT_FOUND: &str = "errors.not_found";
+pub const METHOD_NOT_ALLOWED: &str = "errors.method_not_allowed";
+pub const CONFLICT: &str = "errors.conflict";
+pub const INVALID_PAYLOAD: &str = "errors.invalid_payload";
+pub const PAYLOAD_TOO_LARGE: &str = "errors.payload_too_large";
+pub const INTERNAL: &str = "errors.internal";
+
+// Postgres reports unique constraint violations with this sqlstate.
+const UNIQUE_VIOLATION: &str = "23505";
+
+// Everything a handler can fail with. Each variant maps onto a single status code; the codes carried
+// by the variants are the stable `errors.*` identifiers clients switch on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiError {
+  BadRequest(String),
+  Unauthorized,
+  Forbidden(String),
+  NotFound,
+  MethodNotAllowed,
+  Conflict(String),
+  Unprocessable(String, Map<String, Value>),
+  Internal(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorBody<'a> {
+  pub code: &'a str,
+  pub message: &'a str,
+  pub details: &'a Map<String, Value>,
+}
+
+impl ApiError {
+  pub fn status(&self) -> StatusCode {
+    match self {
+      ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+      ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+      ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+      ApiError::NotFound => StatusCode::NOT_FOUND,
+      ApiError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+      ApiError::Conflict(_) => StatusCode::CONFLICT,
+      ApiError::Unprocessable(_, _) => StatusCode::UNPROCESSABLE_ENTITY,
+      ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+  }
+
+  pub fn code(&self) -> &str {
+    match self {
+      ApiError::BadRequest(code) => code,
+      ApiError::Unauthorized => UNAUTHORIZED,
+      ApiError::Forbidden(code) => code,
+      ApiError::NotFound => NOT_FOUND,
+      ApiError::MethodNotAllowed => METHOD_NOT_ALLOWED,
+      ApiError::Conflict(code) => code,
+      ApiError::Unprocessable(code, _) => code,
+      ApiError::Internal(_) => INTERNAL,
+    }
+  }
+
+  // Internal failures are logged in full but never described to the client.
+  pub fn message(&self) -> &'static str {
+    match self {
+      ApiError::BadRequest(_) => "The request was invalid",
+      ApiError::Unauthorized => "Authentication is required",
+      ApiError::Forbidden(_) => "The request is not allowed",
+      ApiError::NotFound => "Not found",
+      ApiError::MethodNotAllowed => "The method is not supported for this path",
+      ApiError::Conflict(_) => "The request conflicts with existing data",
+      ApiError::Unprocessable(_, _) => "The request payload could not be processed",
+      ApiError::Internal(_) => "Something went wrong",
+    }
+  }
+
+  pub fn body(&self) -> serde_json::Result<String> {
+    let empty = Map::new();
+    let details = match self {
+      ApiError::Unprocessable(_, details) => details,
+      _ => &empty,
+    };
+
+    serde_json::to_string(&ErrorBody {
+      code: self.code(),
+      message: self.message(),
+      details,
+    })
+  }
+}
+
+impl std::fmt::Display for ApiError {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ApiError::Internal(reason) => write!(formatter, "{}", reason),
+      other => write!(formatter, "{} ({})", other.message(), other.code()),
+    }
+  }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ApiError> for Error {
+  fn from(error: ApiError) -> Self {
+    Error::new(ErrorKind::Other, error)
+  }
+}
+
+// Recovers the typed error from anything that has been passed around as an `io::Error`. Errors that
+// did not start out as an `ApiError` are either payloads that failed to parse or internal failures.
+impl From<&Error> for ApiError {
+  fn from(error: &Error) -> Self {
+    if let Some(api) = error.get_ref().and_then(|e| e.downcast_ref::<ApiError>()) {
+      return api.clone();
+    }
+
+    match error.kind() {
+      ErrorKind::InvalidData | ErrorKind::UnexpectedEof => {
+        let mut details = Map::new();
+        details.insert(String::from("reason"), Value::from(format!("{}", error)));
+        ApiError::Unprocessable(String::from(INVALID_PAYLOAD), details)
+      }
+      ErrorKind::TimedOut => ApiError::BadRequest(String::from(BAD_REQUEST)),
+      _ => ApiError::Internal(format!("{}", error)),
+    }
+  }
+}
+
+pub fn humanize_error<E: std::error::Error + 'static>(e: E) -> Error {
+  let unique = (&e as &dyn Any)
+    .downcast_ref::<sqlx::Error>()
+    .and_then(|e| match e {
+      sqlx::Error::Database(db) => db.code(),
+      _ => None,
+    })
+    .map(|code| code == UNIQUE_VIOLATION)
+    .unwrap_or_default();
+
+  match unique {
+    true => ApiError::Conflict(String::from(CONFLICT)).into(),
+    false => ApiError::Internal(format!("{}", e)).into(),
+  }
+}
+
+// Handlers that can only hit one unique constraint use this to report it with a specific code.
+pub fn is_conflict(error: &Error) -> bool {
+  matches!(ApiError::from(error), ApiError::Conflict(_))
 }
 
 pub fn e<S: std::fmt::Display>(s: S) -> Error {
-  Error::new(ErrorKind::Other, format!("{}", s))
+  ApiError::Internal(format!("{}", s)).into()
+}
+
+#[cfg(test)]
+mod test {
+  use super::{e, ApiError, INTERNAL, INVALID_PAYLOAD};
+  use std::io::Error;
+
+  #[test]
+  fn recovers_typed_errors() {
+    let error: Error = ApiError::Conflict(String::from("errors.users.name_taken")).into();
+    assert_eq!(
+      ApiError::from(&error),
+      ApiError::Conflict(String::from("errors.users.name_taken"))
+    );
+
+    let internal = ApiError::from(&e("connection refused"));
+    assert_eq!(internal.code(), INTERNAL);
+    assert_eq!(
+      internal.body().unwrap(),
+      r#"{"code":"errors.internal","message":"Something went wrong","details":{}}"#
+    );
+
+    let invalid = serde_json::from_slice::<String>(b"{").unwrap_err();
+    let parsed = ApiError::from(&Error::from(invalid));
+    assert_eq!(parsed.code(), INVALID_PAYLOAD);
+    assert_eq!(parsed.status().as_u16(), 422);
+  }
 }
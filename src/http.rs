@@ -2,18 +2,20 @@ extern crate http;
 
 use async_std::io::{timeout, Read};
 use async_std::prelude::*;
+use elaine::{Head, RequestMethod, RequestVersion};
 use http::header::{
-  HeaderName, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
-  ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS, CONTENT_LENGTH, CONTENT_TYPE,
-  LOCATION,
+  HeaderName, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+  ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS, ALLOW,
+  CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, LOCATION, SET_COOKIE,
 };
 use log::{debug, info};
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 use std::marker::Unpin;
 use std::time::Duration;
 
-use crate::constants::MAX_FILE_SIZE;
-pub use http::header::AUTHORIZATION;
+use crate::constants::{CSRF_HEADER_NAME, DEFAULT_PAGE_SIZE, MAX_FILE_SIZE, MAX_PAGE_SIZE};
+use crate::errors::{ApiError, PAYLOAD_TOO_LARGE};
+pub use http::header::{AUTHORIZATION, COOKIE};
 pub use http::{header, Method, Request, StatusCode, Uri};
 pub use url::form_urlencoded as query;
 pub use url::Url;
@@ -33,14 +35,126 @@ pub fn query_values<S: std::fmt::Display>(uri: &Uri, key: S) -> Vec<String> {
     .collect::<Vec<String>>()
 }
 
+// Accepts both the standard `Bearer <token>` form of the authorization header and the bare token
+// that clients have historically sent.
+pub fn bearer_token(value: &str) -> &str {
+  let value = value.trim();
+
+  let mut parts = value.splitn(2, ' ');
+
+  match (parts.next(), parts.next()) {
+    (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => token.trim(),
+    _ => value,
+  }
+}
+
+// Finds the value of a single cookie in the contents of a `Cookie` request header.
+pub fn cookie_value(header: &str, name: &str) -> Option<String> {
+  header
+    .split(';')
+    .filter_map(|pair| {
+      let mut parts = pair.trim().splitn(2, '=');
+      match (parts.next(), parts.next()) {
+        (Some(key), Some(value)) if key == name => Some(String::from(value)),
+        _ => None,
+      }
+    })
+    .next()
+}
+
+// HTTP/1.1 connections are persistent unless the client says otherwise; HTTP/1.0 clients have to ask.
+pub fn keep_alive_requested(head: &Head) -> bool {
+  let connection = head
+    .find_header(CONNECTION)
+    .unwrap_or_default()
+    .to_ascii_lowercase();
+  let mut options = connection.split(',').map(|option| option.trim());
+
+  match head.version() {
+    Some(RequestVersion::RFC2616) => !options.any(|option| option == "close"),
+    Some(RequestVersion::RFC1945) => options.any(|option| option == "keep-alive"),
+    None => false,
+  }
+}
+
+// Requests that can not change anything; these never need protection from cross site forgery.
+pub fn is_safe_method(method: &RequestMethod) -> bool {
+  matches!(
+    method,
+    RequestMethod::GET | RequestMethod::HEAD | RequestMethod::OPTIONS
+  )
+}
+
+#[derive(Debug, Default)]
+pub struct Cookie {
+  pub name: String,
+  pub value: String,
+  pub max_age: u64,
+  pub http_only: bool,
+  pub secure: bool,
+}
+
+impl std::fmt::Display for Cookie {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(
+      formatter,
+      "{}={}; Max-Age={}; Path=/; SameSite=Lax",
+      self.name, self.value, self.max_age
+    )?;
+
+    if self.http_only {
+      write!(formatter, "; HttpOnly")?;
+    }
+
+    if self.secure {
+      write!(formatter, "; Secure")?;
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Page {
+  pub number: i64,
+  pub size: i64,
+}
+
+impl Page {
+  // Pages far past the end saturate rather than overflow; they are simply empty.
+  pub fn offset(&self) -> i64 {
+    (self.number - 1).saturating_mul(self.size)
+  }
+}
+
+// Reads the 1-based `page` and `per_page` values from the query string, falling back to the first
+// page of the default size when either is missing or invalid.
+pub fn page(uri: &Uri) -> Page {
+  let number = query_values(uri, "page")
+    .into_iter()
+    .next()
+    .and_then(|value| value.parse::<i64>().ok())
+    .filter(|number| *number > 0)
+    .unwrap_or(1);
+
+  let size = query_values(uri, "per_page")
+    .into_iter()
+    .next()
+    .and_then(|value| value.parse::<i64>().ok())
+    .filter(|size| *size > 0)
+    .map(|size| size.min(MAX_PAGE_SIZE))
+    .unwrap_or(DEFAULT_PAGE_SIZE);
+
+  Page { number, size }
+}
+
 pub async fn read_size_async<R>(reader: &mut R, size: usize) -> Result<Vec<u8>>
 where
   R: Read + Unpin,
 {
   if size > MAX_FILE_SIZE {
-    let m = format!("requested read too large - {}", size);
-    let e = Error::new(ErrorKind::Other, m);
-    return Err(e);
+    info!("requested read too large - {}", size);
+    return Err(ApiError::BadRequest(String::from(PAYLOAD_TOO_LARGE)).into());
   }
   timeout(Duration::from_millis(300), async {
     let mut contents: Vec<u8> = Vec::with_capacity(size);
@@ -100,34 +214,61 @@ impl Response {
     Ok(Response(StatusCode::OK, header_map, Payload::String(vec)))
   }
 
-  pub fn bad_request<S: std::fmt::Display>(reason: S) -> Self {
+  pub fn ok_content<S: std::fmt::Display>(content_type: &str, body: S) -> Self {
     let mut header_map = HeaderMap::default();
-    header_map.push((CONTENT_TYPE, "text/plain; charset=utf-8".to_string()));
+    header_map.push((CONTENT_TYPE, content_type.to_string()));
     Response(
-      StatusCode::BAD_REQUEST,
+      StatusCode::OK,
       header_map,
-      Payload::String(format!("{}", reason)),
+      Payload::String(format!("{}", body)),
     )
   }
 
+  pub fn ok_bytes(content_type: &str, body: Vec<u8>) -> Self {
+    let header_map = vec![(CONTENT_TYPE, content_type.to_string())];
+    Response(StatusCode::OK, header_map, Payload::Bytes(body))
+  }
+
+  // Every failure is sent as json in the same shape, with a status that matches the error.
+  pub fn error(error: ApiError) -> Self {
+    let body = error.body().unwrap_or_default();
+    let header_map = vec![(CONTENT_TYPE, "application/json; charset=utf-8".to_string())];
+    Response(error.status(), header_map, Payload::String(body))
+  }
+
+  pub fn bad_request<S: std::fmt::Display>(reason: S) -> Self {
+    Response::error(ApiError::BadRequest(format!("{}", reason)))
+  }
+
+  pub fn conflict<S: std::fmt::Display>(reason: S) -> Self {
+    Response::error(ApiError::Conflict(format!("{}", reason)))
+  }
+
+  pub fn forbidden<S: std::fmt::Display>(reason: S) -> Self {
+    Response::error(ApiError::Forbidden(format!("{}", reason)))
+  }
+
   pub fn failed() -> Self {
-    Response(
-      StatusCode::BAD_REQUEST,
-      HeaderMap::default(),
-      Payload::Empty,
-    )
+    Response::error(ApiError::Internal(String::new()))
   }
 
   pub fn unauthorized() -> Self {
-    Response(
-      StatusCode::UNAUTHORIZED,
-      HeaderMap::default(),
-      Payload::Empty,
-    )
+    Response::error(ApiError::Unauthorized)
   }
 
   pub fn not_found() -> Self {
-    Response(StatusCode::NOT_FOUND, HeaderMap::default(), Payload::Empty)
+    Response::error(ApiError::NotFound)
+  }
+
+  pub fn method_not_allowed(allowed: &[RequestMethod]) -> Self {
+    let Response(code, mut header_map, body) = Response::error(ApiError::MethodNotAllowed);
+    let allowed = allowed
+      .iter()
+      .map(|method| format!("{:?}", method))
+      .collect::<Vec<String>>()
+      .join(", ");
+    header_map.push((ALLOW, allowed));
+    Response(code, header_map, body)
   }
 
   pub fn redirect<S: std::fmt::Display>(destination: &S) -> Self {
@@ -136,51 +277,186 @@ impl Response {
     Response(StatusCode::TEMPORARY_REDIRECT, header_map, Payload::Empty)
   }
 
+  pub fn cookie(self, cookie: Cookie) -> Self {
+    let Response(code, mut header_map, body) = self;
+    header_map.push((SET_COOKIE, format!("{}", cookie)));
+    Response(code, header_map, body)
+  }
+
+  // Responses are sent with `connection: close` unless marked as leaving the connection open for
+  // up to `remaining` more requests.
+  pub fn keep_alive(self, idle_timeout: u64, remaining: usize) -> Self {
+    let Response(code, mut header_map, body) = self;
+    header_map.push((CONNECTION, "keep-alive".to_string()));
+    header_map.push((
+      HeaderName::from_static("keep-alive"),
+      format!("timeout={}, max={}", idle_timeout, remaining),
+    ));
+    Response(code, header_map, body)
+  }
+
   pub fn cors(self, origin: String) -> Self {
     let Response(code, mut header_map, body) = self;
 
     debug!("adding cors headers");
     header_map.push((ACCESS_CONTROL_ALLOW_ORIGIN, origin));
+    header_map.push((ACCESS_CONTROL_ALLOW_CREDENTIALS, "true".to_string()));
     header_map.push((
       ACCESS_CONTROL_ALLOW_HEADERS,
-      format!("{}, {}", AUTHORIZATION, CONTENT_TYPE),
+      format!("{}, {}, {}", AUTHORIZATION, CONTENT_TYPE, CSRF_HEADER_NAME),
     ));
     header_map.push((ACCESS_CONTROL_REQUEST_HEADERS, CONTENT_TYPE.to_string()));
     header_map.push((
       ACCESS_CONTROL_ALLOW_METHODS,
-      "POST, GET, PUT, DELETE".to_string(),
+      "POST, GET, PUT, PATCH, DELETE".to_string(),
     ));
 
     Response(code, header_map, body)
   }
 }
 
-impl std::fmt::Display for Response {
-  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl Response {
+  fn head(&self) -> String {
     let Response(code, header_map, body) = self;
     let lenh = body.len().map(|b| (CONTENT_LENGTH, format!("{}", b)));
+    let close = match header_map.iter().any(|(name, _)| name == CONNECTION) {
+      true => None,
+      false => Some((CONNECTION, "close".to_string())),
+    };
 
     let headers = header_map
       .iter()
       .chain(lenh.iter())
-      .chain(Some((header::CONNECTION, "close".to_string())).iter())
+      .chain(close.iter())
       .map(|(v, k)| format!("{}: {}\r\n", v, k))
       .collect::<String>();
 
-    write!(formatter, "HTTP/1.1 {}\r\n{}\r\n{}", code, headers, body)
+    format!("HTTP/1.1 {}\r\n{}\r\n", code, headers)
+  }
+
+  // The raw bytes written to the connection; unlike the `Display` implementation, binary payloads
+  // are written untouched.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = self.head().into_bytes();
+
+    match &self.2 {
+      Payload::Bytes(b) => bytes.extend_from_slice(b),
+      Payload::String(s) => bytes.extend_from_slice(s.as_bytes()),
+      Payload::Empty => (),
+    }
+
+    bytes
+  }
+}
+
+impl std::fmt::Display for Response {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(formatter, "{}{}", self.head(), self.2)
   }
 }
 
 #[cfg(test)]
 mod test {
-  use super::Response;
+  use super::{
+    bearer_token, cookie_value, keep_alive_requested, page, Cookie, Page, Response, Uri,
+  };
+  use async_std::task::block_on;
+  use elaine::recognize;
+
+  #[test]
+  fn page_defaults() {
+    let uri = "/leaderboards?page=bogus".parse::<Uri>().unwrap();
+    assert_eq!(
+      page(&uri),
+      Page {
+        number: 1,
+        size: 25
+      }
+    );
+    assert_eq!(page(&uri).offset(), 0);
+  }
+
+  #[test]
+  fn page_from_query() {
+    let uri = "/leaderboards?page=3&per_page=1000".parse::<Uri>().unwrap();
+    assert_eq!(
+      page(&uri),
+      Page {
+        number: 3,
+        size: 100
+      }
+    );
+    assert_eq!(page(&uri).offset(), 200);
+  }
+
+  #[test]
+  fn page_offset_saturates() {
+    let uri = format!("/leaderboards?page={}&per_page=100", i64::MAX);
+    let uri = uri.parse::<Uri>().unwrap();
+    assert_eq!(page(&uri).number, i64::MAX);
+    assert_eq!(page(&uri).offset(), i64::MAX);
+  }
+
+  #[test]
+  fn bearer_tokens() {
+    assert_eq!(bearer_token("Bearer abc.def"), "abc.def");
+    assert_eq!(bearer_token("bearer  abc.def "), "abc.def");
+    assert_eq!(bearer_token("abc.def"), "abc.def");
+  }
+
+  #[test]
+  fn keep_alive() {
+    let requested = |request: &str| {
+      let head = block_on(recognize(&mut request.as_bytes())).unwrap();
+      keep_alive_requested(&head)
+    };
+    assert!(requested("GET / HTTP/1.1\r\n\r\n"));
+    assert!(!requested("GET / HTTP/1.1\r\nConnection: Close\r\n\r\n"));
+    assert!(!requested("GET / HTTP/1.0\r\n\r\n"));
+    assert!(requested(
+      "GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n"
+    ));
+
+    let response = format!("{}", Response::default().keep_alive(5, 99));
+    assert!(response.contains("connection: keep-alive\r\nkeep-alive: timeout=5, max=99\r\n"));
+    assert!(!response.contains("connection: close"));
+  }
+
+  #[test]
+  fn cookies() {
+    let header = "theme=dark; krumnet_session=abc.def;other=1";
+    assert_eq!(
+      cookie_value(header, "krumnet_session"),
+      Some(String::from("abc.def"))
+    );
+    assert_eq!(cookie_value(header, "other"), Some(String::from("1")));
+    assert_eq!(cookie_value(header, "missing"), None);
+
+    let cookie = Cookie {
+      name: String::from("krumnet_session"),
+      value: String::from("abc"),
+      max_age: 60,
+      http_only: true,
+      secure: true,
+    };
+    assert_eq!(
+      format!("{}", cookie),
+      "krumnet_session=abc; Max-Age=60; Path=/; SameSite=Lax; HttpOnly; Secure"
+    );
+  }
+
+  #[test]
+  fn binary_payload() {
+    let res = Response::ok_bytes("image/png", vec![0x89, 0x50, 0xff]);
+    assert!(res.to_bytes().ends_with(&[b'\n', 0x89, 0x50, 0xff]));
+  }
 
   #[test]
   fn not_found() {
     let res = Response::not_found();
     assert_eq!(
       format!("{}", res),
-      "HTTP/1.1 404 Not Found\r\nconnection: close\r\n\r\n"
+      "HTTP/1.1 404 Not Found\r\ncontent-type: application/json; charset=utf-8\r\ncontent-length: 62\r\nconnection: close\r\n\r\n{\"code\":\"errors.not_found\",\"message\":\"Not found\",\"details\":{}}"
     );
   }
 }
@@ -1,5 +1,6 @@
 use crate::interchange::jobs;
 use crate::interchange::jobs::{Job, QueuedJob};
+use crate::router::Access;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 pub use sqlx::FromRow;
@@ -23,6 +24,9 @@ pub struct GameRoundEntry {
   pub created: DateTime<Utc>,
   pub user_id: String,
   pub user_name: String,
+  pub user_avatar_url: Option<String>,
+  pub team_id: Option<String>,
+  pub hidden: bool,
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -55,6 +59,12 @@ pub struct GameRoundDetails {
   pub fulfilled: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameRoundList {
+  pub rounds: Vec<GameRoundDetails>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct LobbyListLobby {
@@ -78,6 +88,8 @@ pub struct GameMember {
   pub member_id: String,
   pub user_id: String,
   pub name: String,
+  pub avatar_url: Option<String>,
+  pub team_id: Option<String>,
   #[serde(with = "chrono::serde::ts_milliseconds")]
   pub joined: DateTime<Utc>,
 }
@@ -108,6 +120,23 @@ pub struct GameDetailPlacement {
   pub vote_count: i32,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameTeam {
+  pub id: String,
+  pub name: String,
+  pub position: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameTeamPlacement {
+  pub team_id: String,
+  pub team_name: String,
+  pub place: i32,
+  pub vote_count: i32,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct GameDetails {
@@ -120,6 +149,8 @@ pub struct GameDetails {
   pub members: Vec<GameMember>,
   pub rounds: Vec<GameRound>,
   pub placements: Vec<GameDetailPlacement>,
+  pub teams: Vec<GameTeam>,
+  pub team_placements: Vec<GameTeamPlacement>,
 }
 
 #[derive(Debug, Serialize)]
@@ -128,6 +159,7 @@ pub struct LobbyMember {
   pub member_id: String,
   pub user_id: String,
   pub name: String,
+  pub avatar_url: Option<String>,
   pub invited_by: Option<String>,
   #[serde(with = "chrono::serde::ts_milliseconds_option")]
   pub joined_at: Option<DateTime<Utc>>,
@@ -135,6 +167,12 @@ pub struct LobbyMember {
   pub left_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LobbyMemberList {
+  pub members: Vec<LobbyMember>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct GameRoundPlacement {
@@ -216,21 +254,411 @@ impl From<QueuedJob> for JobHandle {
       Job::CheckRoundFulfillment { .. }
       | Job::CleanupLobbyMembership { .. }
       | Job::CheckRoundCompletion(_)
-      | Job::CleanupGameMembership { .. } => without_result(id),
+      | Job::CleanupGameMembership { .. }
+      | Job::UpdateRatings(_)
+      | Job::AwardAchievements(_)
+      | Job::CleanupGuests(_)
+      | Job::AnonymizeUser(_) => without_result(id),
     }
   }
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct NewReport {
+  pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+  pub id: String,
+  pub reason: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  pub reporter_id: String,
+  pub reporter_name: String,
+  pub user_id: Option<String>,
+  pub user_name: Option<String>,
+  pub entry_id: Option<String>,
+  pub entry: Option<String>,
+  pub hidden: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ReportList {
+  pub reports: Vec<Report>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ApiKey {
+  pub id: String,
+  pub name: String,
+  pub prefix: String,
+  pub scopes: Vec<String>,
+  pub use_count: i32,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub last_used: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ApiKeyList {
+  pub keys: Vec<ApiKey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct NewApiKey {
+  pub id: String,
+  pub name: String,
+  pub scopes: Vec<String>,
+  pub key: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminLobby {
+  pub id: String,
+  pub name: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub closed: Option<DateTime<Utc>>,
+  pub member_count: i64,
+  pub game_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminLobbyList {
+  pub page: i64,
+  pub per_page: i64,
+  pub lobbies: Vec<AdminLobby>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminLobbyDetails {
+  pub lobby: AdminLobby,
+  pub members: Vec<LobbyMember>,
+  pub games: Vec<LobbyGame>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminGame {
+  pub id: String,
+  pub name: String,
+  pub lobby_id: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub ended: Option<DateTime<Utc>>,
+  pub member_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminGameList {
+  pub page: i64,
+  pub per_page: i64,
+  pub games: Vec<AdminGame>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AdminGameDetails {
+  pub game: AdminGame,
+  pub members: Vec<GameMember>,
+  pub rounds: Vec<GameRound>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct SessionUserData {
   pub id: String,
   pub email: String,
   pub name: String,
+  pub guest: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct SessionTokenData {
+  pub token: String,
+  pub refresh_token: String,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct SessionData {
   pub user: SessionUserData,
+  pub achievements: Vec<UserAchievement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserBestRound {
+  pub round_id: String,
+  pub game_id: String,
+  pub prompt: Option<String>,
+  pub place: i32,
+  pub vote_count: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserStats {
+  pub user_id: String,
+  pub user_name: String,
+  pub games_played: i64,
+  pub wins: i64,
+  pub average_place: Option<f64>,
+  pub total_votes: i64,
+  pub rating: f64,
+  pub rated_games: i32,
+  pub best_round: Option<UserBestRound>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LeaderboardEntry {
+  pub rank: i64,
+  pub user_id: String,
+  pub user_name: String,
+  pub games_played: i64,
+  pub wins: i64,
+  pub total_votes: i64,
+  pub rating: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Leaderboard {
+  pub lobby_id: Option<String>,
+  pub sort: String,
+  pub page: i64,
+  pub per_page: i64,
+  pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserAchievement {
+  pub id: String,
+  pub kind: String,
+  pub title: String,
+  pub description: String,
+  pub game_id: Option<String>,
+  pub round_id: Option<String>,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub awarded: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserAchievementList {
+  pub achievements: Vec<UserAchievement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameList {
+  pub games: Vec<GameDetails>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameHistoryGame {
+  pub id: String,
+  pub name: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub ended: Option<DateTime<Utc>>,
+  pub member_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameHistory {
+  pub games: Vec<GameHistoryGame>,
+  pub next: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TranscriptEntry {
+  pub author: String,
+  pub entry: Option<String>,
+  pub place: Option<i32>,
+  pub voters: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TranscriptRound {
+  pub position: i32,
+  pub prompt: Option<String>,
+  pub entries: Vec<TranscriptEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameTranscript {
+  pub id: String,
+  pub name: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub ended: DateTime<Utc>,
+  pub rounds: Vec<TranscriptRound>,
+  pub placements: Vec<GameDetailPlacement>,
+  pub team_placements: Vec<GameTeamPlacement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct GameShareToken {
+  pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ActiveSession {
+  pub id: String,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub created: DateTime<Utc>,
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub last_seen: DateTime<Utc>,
+  pub user_agent: Option<String>,
+  pub current: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ActiveSessionList {
+  pub sessions: Vec<ActiveSession>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RevokedSessions {
+  pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserProfile {
+  pub id: String,
+  pub name: String,
+  pub email: String,
+  pub avatar_url: Option<String>,
+  pub guest: bool,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub created: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedLobby {
+  pub id: String,
+  pub name: String,
+  pub invited_by: Option<String>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub joined: Option<DateTime<Utc>>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub left: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedEntry {
+  pub id: String,
+  pub game_id: String,
+  pub round_id: String,
+  pub prompt: Option<String>,
+  pub entry: Option<String>,
+  pub auto: bool,
+  pub hidden: bool,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub created: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedVote {
+  pub id: String,
+  pub game_id: String,
+  pub round_id: String,
+  pub entry_id: String,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub created: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedPlacement {
+  pub game_id: String,
+  pub game_name: String,
+  pub place: i32,
+  pub vote_count: i32,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub created: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserDataExport {
+  #[serde(with = "chrono::serde::ts_milliseconds")]
+  pub exported: DateTime<Utc>,
+  pub profile: UserProfile,
+  pub identities: Vec<LinkedIdentity>,
+  pub achievements: Vec<UserAchievement>,
+  pub lobbies: Vec<ExportedLobby>,
+  pub entries: Vec<ExportedEntry>,
+  pub votes: Vec<ExportedVote>,
+  pub placements: Vec<ExportedPlacement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LinkedIdentity {
+  pub id: String,
+  pub provider: String,
+  pub email: Option<String>,
+  pub name: Option<String>,
+  #[serde(with = "chrono::serde::ts_milliseconds_option")]
+  pub created: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LinkedIdentityList {
+  pub identities: Vec<LinkedIdentity>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct IdentityLinkRedirect {
+  pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RouteDescription {
+  pub method: String,
+  pub path: String,
+  pub access: Access,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RouteListing {
+  pub routes: Vec<RouteDescription>,
 }
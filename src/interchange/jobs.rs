@@ -43,11 +43,15 @@ pub struct CheckRoundCompletion {
   pub result: Option<Result<CheckRoundCompletionResult, String>>,
 }
 
+// Queued when a user creates a game for their lobby. When `teams` is present, the members of the
+// new game are split as evenly as possible into that many teams.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct CreateGame {
   pub creator: String,
   pub lobby_id: String,
+  #[serde(default)]
+  pub teams: Option<u8>,
   pub result: Option<Result<String, String>>,
 }
 
@@ -66,6 +70,53 @@ pub struct CreateLobby {
   pub result: Option<Result<String, String>>,
 }
 
+// Queued once a game has ended and its placements have been created, jobs of this kind will adjust
+// the rating of every placed player. On success, the job's result will be populated with the ids
+// of the users whose rating changed.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateRatings {
+  pub game_id: String,
+  pub result: Option<Result<Vec<String>, String>>,
+}
+
+// Queued whenever a round is completed, jobs of this kind will award any achievements earned by
+// the members of the game so far. On success, the job's result will be populated with the ids of
+// the newly created awards.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct AwardAchievements {
+  pub game_id: String,
+  pub result: Option<Result<Vec<String>, String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CleanupGuestsResult {
+  pub deleted: Vec<String>,
+  pub expired: Vec<String>,
+}
+
+// Queued periodically by the worker, jobs of this kind remove guest accounts that have not been
+// seen for `idle_timeout` seconds. Guests that never joined a lobby are deleted; the rest are only
+// expired so the games they played in stay intact for everyone else.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CleanupGuests {
+  pub idle_timeout: u64,
+  pub result: Option<Result<CleanupGuestsResult, String>>,
+}
+
+// Queued when a user deletes their account, jobs of this kind replace the user's personal details
+// with placeholders and remove their identities, leaving the rows referenced by games in place so
+// they stay intact for the other players.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct AnonymizeUser {
+  pub user_id: String,
+  pub result: Option<Result<String, String>>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case", tag = "t", content = "c")]
 pub enum Job {
@@ -75,6 +126,10 @@ pub enum Job {
   CleanupLobbyMembership(CleanupLobbyMembership),
   CheckRoundCompletion(CheckRoundCompletion),
   CleanupGameMembership(CleanupGameMembership),
+  UpdateRatings(UpdateRatings),
+  AwardAchievements(AwardAchievements),
+  CleanupGuests(CleanupGuests),
+  AnonymizeUser(AnonymizeUser),
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -105,10 +160,14 @@ impl QueuedJob {
     match &self.job {
       Job::CreateLobby(CreateLobby { creator, .. }) => Some(creator.clone()),
       Job::CreateGame(CreateGame { creator, .. }) => Some(creator.clone()),
+      Job::AnonymizeUser(AnonymizeUser { user_id, .. }) => Some(user_id.clone()),
       Job::CheckRoundFulfillment { .. }
       | Job::CleanupLobbyMembership { .. }
       | Job::CheckRoundCompletion(_)
-      | Job::CleanupGameMembership(_) => None,
+      | Job::CleanupGameMembership(_)
+      | Job::UpdateRatings(_)
+      | Job::AwardAchievements(_)
+      | Job::CleanupGuests(_) => None,
     }
   }
 }
@@ -5,7 +5,7 @@ extern crate log;
 use std::io::Result;
 use std::marker::Unpin;
 
-use async_std::io::{Read as AsyncRead, Write as AsyncWrite};
+use async_std::io::{timeout, Read as AsyncRead, Write as AsyncWrite};
 use async_std::net::TcpListener;
 use async_std::prelude::*;
 use async_std::sync::Arc;
@@ -14,29 +14,36 @@ use chrono::{DateTime, Utc};
 use elaine::{recognize, Head, RequestMethod};
 use log::{debug, error as fatal, info, warn};
 use serde::Serialize;
+use std::time::Duration;
 
+pub mod achievements;
+pub mod api_keys;
 pub mod authority;
 pub mod bg;
 pub mod configuration;
 pub mod constants;
 pub mod context;
+pub mod entries;
 pub mod errors;
 pub mod http;
 pub mod interchange;
 pub mod jobs;
 pub mod names;
 pub mod oauth;
+pub mod ratings;
 pub mod records;
+pub mod router;
 pub mod routes;
 pub mod session;
 pub mod version;
 
-pub use crate::authority::Authority;
-pub use crate::configuration::{Configuration, GoogleCredentials};
+pub use crate::authority::{Authority, Role, Scope};
+pub use crate::configuration::{Configuration, GoogleCredentials, KeepAliveConfiguration};
 pub use crate::context::{Context, ContextBuilder};
 pub use crate::http::{read_size_async, Response, Uri};
 pub use crate::jobs::JobStore;
 pub use crate::records::{Connection as RecordConnection, RecordStore};
+pub use crate::router::{Access, Router};
 pub use crate::session::Session as SessionStore;
 
 #[derive(Serialize)]
@@ -66,12 +73,259 @@ async fn health_check(context: &Context) -> Result<Response> {
   Response::ok_json(HealthCheckData::default()).map(|r| r.cors(context.cors()))
 }
 
-// Called for each new connection to the server, this is where requests are routed.
-async fn route<T>(mut connection: T, builder: ContextBuilder) -> Result<()>
+// Every endpoint of the api. Literal paths are listed ahead of parameterized paths that overlap.
+pub fn router() -> Router {
+  use routes::*;
+  use RequestMethod::{DELETE, GET, PATCH, POST, PUT};
+
+  Router::new()
+    // Authentication routing
+    .route(GET, "/auth/redirect", Access::Public, |ctx, _| {
+      Box::pin(oauth::redirect(ctx, oauth::GOOGLE_PROVIDER))
+    })
+    .route(GET, "/auth/callback", Access::Public, |ctx, req| {
+      Box::pin(async move { oauth::callback(ctx, oauth::GOOGLE_PROVIDER, &req.uri).await })
+    })
+    .route(GET, "/auth/identify", Access::Public, |ctx, _| {
+      Box::pin(identify(ctx))
+    })
+    .route(POST, "/auth/refresh", Access::Public, |ctx, req| {
+      Box::pin(async move { refresh(ctx, &mut req.body()).await })
+    })
+    .route(POST, "/auth/guest", Access::Public, |ctx, req| {
+      Box::pin(async move { guest(ctx, &mut req.body()).await })
+    })
+    .route(GET, "/auth/dev", Access::Public, |ctx, req| {
+      Box::pin(async move { oauth::dev(ctx, &req.uri).await })
+    })
+    .route(GET, "/auth/destroy", Access::Public, |ctx, req| {
+      Box::pin(async move { destroy(ctx, &req.uri).await })
+    })
+    .route(
+      GET,
+      "/auth/{provider}/redirect",
+      Access::Public,
+      |ctx, req| Box::pin(async move { oauth::redirect(ctx, req.param("provider")).await }),
+    )
+    .route(
+      GET,
+      "/auth/{provider}/callback",
+      Access::Public,
+      |ctx, req| {
+        Box::pin(async move { oauth::callback(ctx, req.param("provider"), &req.uri).await })
+      },
+    )
+    .route(
+      POST,
+      "/auth/{provider}/link",
+      Access::Session,
+      |ctx, req| Box::pin(async move { oauth::link(ctx, req.param("provider")).await }),
+    )
+    // Identities
+    .route(GET, "/identities", Access::User, |ctx, _| {
+      Box::pin(identities::find(ctx))
+    })
+    .route(DELETE, "/identities/{id}", Access::Session, |ctx, req| {
+      Box::pin(async move { identities::destroy(ctx, req.param("id")).await })
+    })
+    // Api keys
+    .route(GET, "/api-keys", Access::Session, |ctx, _| {
+      Box::pin(api_keys::find(ctx))
+    })
+    .route(POST, "/api-keys", Access::Session, |ctx, req| {
+      Box::pin(async move { api_keys::create(ctx, &mut req.body()).await })
+    })
+    .route(DELETE, "/api-keys/{id}", Access::Session, |ctx, req| {
+      Box::pin(async move { api_keys::destroy(ctx, req.param("id")).await })
+    })
+    // Sessions
+    .route(GET, "/sessions", Access::User, |ctx, _| {
+      Box::pin(sessions::find(ctx))
+    })
+    .route(DELETE, "/sessions", Access::Session, |ctx, _| {
+      Box::pin(sessions::destroy_all(ctx))
+    })
+    .route(DELETE, "/sessions/{id}", Access::Session, |ctx, req| {
+      Box::pin(async move { sessions::destroy(ctx, req.param("id")).await })
+    })
+    // Basic health check for sanity, and a listing of everything here for docs
+    .route(GET, "/health-check", Access::Public, |ctx, _| {
+      Box::pin(health_check(ctx))
+    })
+    .route(GET, "/routes", Access::Public, |ctx, _| {
+      Box::pin(async move { Response::ok_json(router().listing()).map(|r| r.cors(ctx.cors())) })
+    })
+    // Jobs
+    .route(GET, "/jobs", Access::User, |ctx, req| {
+      Box::pin(async move { jobs::find(ctx, &req.uri).await })
+    })
+    // Lobbies
+    .route(GET, "/lobbies", Access::User, |ctx, req| {
+      Box::pin(async move { lobbies::find(ctx, &req.uri).await })
+    })
+    .route(POST, "/lobbies", Access::User, |ctx, req| {
+      Box::pin(async move { lobbies::create(ctx, &mut req.body()).await })
+    })
+    .route(GET, "/lobbies/{id}", Access::User, |ctx, req| {
+      Box::pin(async move { lobbies::details(ctx, &String::from(req.param("id"))).await })
+    })
+    .route(GET, "/lobbies/{id}/members", Access::User, |ctx, req| {
+      Box::pin(async move { lobbies::members(ctx, req.param("id")).await })
+    })
+    .route(POST, "/lobby-memberships", Access::User, |ctx, req| {
+      Box::pin(async move { lobby_memberships::create_membership(ctx, &mut req.body()).await })
+    })
+    .route(DELETE, "/lobby-memberships", Access::User, |ctx, req| {
+      Box::pin(async move { lobby_memberships::destroy_membership(ctx, &mut req.body()).await })
+    })
+    // Games
+    .route(POST, "/games", Access::User, |ctx, req| {
+      Box::pin(async move { games::create(ctx, &mut req.body()).await })
+    })
+    .route(GET, "/games", Access::User, |ctx, req| {
+      Box::pin(async move { games::find(ctx, &req.uri).await })
+    })
+    .route(GET, "/games/{id}", Access::User, |ctx, req| {
+      Box::pin(async move { games::find_one(ctx, req.param("id")).await })
+    })
+    .route(GET, "/games/{id}/export", Access::Public, |ctx, req| {
+      Box::pin(async move { games::export(ctx, req.param("id"), &req.uri).await })
+    })
+    .route(POST, "/games/{id}/share-token", Access::User, |ctx, req| {
+      Box::pin(async move { games::create_share_token(ctx, req.param("id")).await })
+    })
+    .route(
+      DELETE,
+      "/games/{id}/share-token",
+      Access::User,
+      |ctx, req| Box::pin(async move { games::destroy_share_token(ctx, req.param("id")).await }),
+    )
+    .route(GET, "/rounds", Access::User, |ctx, req| {
+      Box::pin(async move { rounds::find(ctx, &req.uri).await })
+    })
+    .route(GET, "/rounds/{id}", Access::User, |ctx, req| {
+      Box::pin(async move { rounds::find_one(ctx, req.param("id")).await })
+    })
+    .route(POST, "/round-entry-votes", Access::User, |ctx, req| {
+      Box::pin(async move { games::create_entry_vote(ctx, &mut req.body()).await })
+    })
+    .route(
+      DELETE,
+      "/round-entry-votes/{id}",
+      Access::User,
+      |ctx, req| Box::pin(async move { games::destroy_entry_vote(ctx, req.param("id")).await }),
+    )
+    .route(POST, "/round-entries", Access::User, |ctx, req| {
+      Box::pin(async move { games::create_entry(ctx, &mut req.body()).await })
+    })
+    // Profiles
+    .route(GET, "/users/me", Access::User, |ctx, _| {
+      Box::pin(users::me(ctx))
+    })
+    .route(PATCH, "/users/me", Access::User, |ctx, req| {
+      Box::pin(async move { users::update(ctx, &mut req.body()).await })
+    })
+    .route(DELETE, "/users/me", Access::Session, |ctx, _| {
+      Box::pin(users::destroy(ctx))
+    })
+    .route(GET, "/users/me/export", Access::Session, |ctx, _| {
+      Box::pin(users::export(ctx))
+    })
+    .route(PUT, "/users/me/avatar", Access::User, |ctx, req| {
+      Box::pin(async move { users::upload_avatar(ctx, &mut req.body()).await })
+    })
+    .route(GET, "/users/{id}/avatar", Access::Public, |ctx, req| {
+      Box::pin(async move { users::avatar(ctx, req.param("id")).await })
+    })
+    // Statistics
+    .route(GET, "/users/{id}/stats", Access::User, |ctx, req| {
+      Box::pin(async move { users::stats(ctx, req.param("id")).await })
+    })
+    .route(GET, "/users/{id}/achievements", Access::User, |ctx, req| {
+      Box::pin(async move { users::achievements(ctx, req.param("id")).await })
+    })
+    .route(GET, "/leaderboards", Access::User, |ctx, req| {
+      Box::pin(async move { leaderboards::find(ctx, &req.uri).await })
+    })
+    // Moderation
+    .route(POST, "/reports", Access::User, |ctx, req| {
+      Box::pin(async move { reports::create(ctx, &mut req.body()).await })
+    })
+    .route(GET, "/reports", Access::Admin, |ctx, _| {
+      Box::pin(reports::find(ctx))
+    })
+    .route(POST, "/hidden-entries", Access::Admin, |ctx, req| {
+      Box::pin(async move { reports::hide_entry(ctx, &mut req.body()).await })
+    })
+    .route(DELETE, "/hidden-entries", Access::Admin, |ctx, req| {
+      Box::pin(async move { reports::show_entry(ctx, &mut req.body()).await })
+    })
+    // Administration
+    .route(GET, "/admin/lobbies", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::find_lobbies(ctx, &req.uri).await })
+    })
+    .route(GET, "/admin/lobbies/{id}", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::find_lobby(ctx, &String::from(req.param("id"))).await })
+    })
+    .route(
+      POST,
+      "/admin/lobbies/{id}/close",
+      Access::Admin,
+      |ctx, req| {
+        Box::pin(async move { admin::close_lobby(ctx, &String::from(req.param("id"))).await })
+      },
+    )
+    .route(GET, "/admin/games", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::find_games(ctx, &req.uri).await })
+    })
+    .route(GET, "/admin/games/{id}", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::find_game(ctx, &String::from(req.param("id"))).await })
+    })
+    .route(POST, "/admin/games/{id}/end", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::end_game(ctx, &String::from(req.param("id"))).await })
+    })
+    .route(POST, "/admin/impersonate", Access::Admin, |ctx, req| {
+      Box::pin(async move { admin::impersonate(ctx, &mut req.body()).await })
+    })
+    .route(
+      POST,
+      "/admin/users/{id}/roles",
+      Access::Admin,
+      |ctx, req| {
+        Box::pin(async move {
+          let user_id = String::from(req.param("id"));
+          admin::grant_role(ctx, &user_id, &mut req.body()).await
+        })
+      },
+    )
+    .route(
+      DELETE,
+      "/admin/users/{id}/roles/{role}",
+      Access::Admin,
+      |ctx, req| {
+        Box::pin(async move {
+          let user_id = String::from(req.param("id"));
+          admin::revoke_role(ctx, &user_id, req.param("role")).await
+        })
+      },
+    )
+    .route(GET, "/admin/token-rejections", Access::Admin, |ctx, _| {
+      Box::pin(admin::token_rejections(ctx))
+    })
+}
+
+// Called for each request read from a connection, this is where requests are routed. Returns whether
+// the connection can be used for another request.
+async fn route<T>(
+  connection: &mut T,
+  head: Head,
+  builder: ContextBuilder,
+  router: &Router,
+  remaining: usize,
+) -> Result<bool>
 where
   T: AsyncRead + AsyncWrite + Unpin,
 {
-  let head = recognize(&mut connection).await?;
   debug!("recognized request - '{:?}'", head.path());
   let ctx = builder.for_request(&head).await?;
   let (method, path) = extract_parts(&head)?;
@@ -79,72 +333,80 @@ where
 
   info!("{:?} {}", method, uri);
 
-  let response = match (method, uri.path()) {
-    (RequestMethod::OPTIONS, _) => {
+  // A body that could not be read in full would leave the rest of it waiting on the connection.
+  let body = read_size_async(connection, ctx.pending()).await;
+  let keep_alive = remaining > 0 && body.is_ok() && http::keep_alive_requested(&head);
+
+  let response = match (method, body) {
+    (_, Err(e)) => Err(e),
+    (RequestMethod::OPTIONS, Ok(_)) => {
       debug!("cors preflight request");
       Ok(Response::default().cors(ctx.cors()))
     }
-    // Authentication routing
-    (RequestMethod::GET, "/auth/redirect") => {
-      debug!("initiating oauth flow");
-      oauth::redirect(&ctx)
-    }
-    (RequestMethod::GET, "/auth/identify") => routes::identify(&ctx).await,
-    (RequestMethod::GET, "/auth/destroy") => routes::destroy(&ctx, &uri).await,
-    (RequestMethod::GET, "/auth/callback") => {
-      debug!("oauth callback");
-      oauth::callback(&ctx, &uri).await
-    }
-    // Basic health check for sanity
-    (RequestMethod::GET, "/health-check") => {
-      info!("health-check - '{}'", path);
-      health_check(&ctx).await
-    }
-
-    // Jobs
-    (RequestMethod::GET, "/jobs") => routes::jobs::find(&ctx, &uri).await,
-
-    // Lobbies
-    (RequestMethod::GET, "/lobbies") => routes::lobbies::find(&ctx, &uri).await,
-    (RequestMethod::POST, "/lobbies") => routes::lobbies::create(&ctx, &mut connection).await,
+    (method, Ok(body)) => router.dispatch(&ctx, &method, uri, body).await,
+  }
+  .unwrap_or_else(|e| {
+    let error = errors::ApiError::from(&e);
 
-    (RequestMethod::POST, "/lobby-memberships") => {
-      routes::lobby_memberships::create_membership(&ctx, &mut connection).await
-    }
-    (RequestMethod::DELETE, "/lobby-memberships") => {
-      routes::lobby_memberships::destroy_membership(&ctx, &mut connection).await
+    match &error {
+      errors::ApiError::Internal(_) => fatal!("request handler failed - {}", e),
+      _ => warn!("request rejected - {}", e),
     }
 
-    (RequestMethod::POST, "/games") => routes::games::create(&ctx, &mut connection).await,
-    (RequestMethod::GET, "/games") => routes::games::find(&ctx, &uri).await,
+    Response::error(error).cors(ctx.cors())
+  });
 
-    (RequestMethod::GET, "/rounds") => routes::rounds::find(&ctx, &uri).await,
+  let response = match keep_alive {
+    true => response.keep_alive(ctx.config().keep_alive.idle_timeout, remaining),
+    false => response,
+  };
 
-    (RequestMethod::POST, "/round-entry-votes") => {
-      routes::games::create_entry_vote(&ctx, &mut connection).await
-    }
+  connection.write_all(&response.to_bytes()).await?;
+  Ok(keep_alive)
+}
 
-    (RequestMethod::POST, "/round-entries") => {
-      routes::games::create_entry(&ctx, &mut connection).await
-    }
+// Called for each new connection to the server. Requests are handled one after another until the
+// client closes the connection, it sits idle past the configured timeout or it has served the
+// maximum number of requests.
+async fn handle<T>(
+  mut connection: T,
+  builder: ContextBuilder,
+  router: &Router,
+  limits: &KeepAliveConfiguration,
+) -> Result<()>
+where
+  T: AsyncRead + AsyncWrite + Unpin,
+{
+  let idle_timeout = Duration::from_secs(limits.idle_timeout);
+  let max_requests = limits.max_requests.max(1);
+
+  for served in 1..=max_requests {
+    let head = match timeout(idle_timeout, recognize(&mut connection)).await {
+      Ok(head) => head,
+      Err(e) if served > 1 => {
+        debug!("closing connection after {} requests - {}", served - 1, e);
+        return Ok(());
+      }
+      Err(e) => return Err(e),
+    };
+
+    let remaining = max_requests - served;
 
-    _ => {
-      debug!("not-found - '{}'", path);
-      Ok(Response::not_found().cors(ctx.cors()))
+    if !route(&mut connection, head, builder.clone(), router, remaining).await? {
+      break;
     }
   }
-  .unwrap_or_else(|e| {
-    fatal!("request handler failed - {}", e);
-    Response::failed().cors(ctx.cors())
-  });
 
-  connection
-    .write(format!("{}", response).as_bytes())
-    .await
-    .map(|_| ())
+  Ok(())
 }
 
 pub async fn serve(configuration: Configuration) -> Result<()> {
+  configuration.validate()?;
+
+  if configuration.dev_login {
+    warn!("dev login is enabled; anyone can sign in as any user");
+  }
+
   let listener = TcpListener::bind(&configuration.addr).await?;
   let mut incoming = listener.incoming();
 
@@ -157,6 +419,8 @@ pub async fn serve(configuration: Configuration) -> Result<()> {
   info!("opening record store");
   let records = Arc::new(RecordStore::open(&configuration).await?);
 
+  let router = Arc::new(router());
+
   info!("accepting incoming tcp streams");
   while let Some(stream) = incoming.next().await {
     match stream {
@@ -166,9 +430,11 @@ pub async fn serve(configuration: Configuration) -> Result<()> {
           .jobs(jobs.clone())
           .session(session.clone())
           .records(records.clone());
+        let router = router.clone();
+        let limits = configuration.keep_alive.clone();
 
         task::spawn(async move {
-          let result = route(&mut connection, builder).await;
+          let result = handle(&mut connection, builder, &router, &limits).await;
 
           if let Err(e) = result {
             warn!("unable to handle connection: {:?}", e);
@@ -189,15 +455,70 @@ pub async fn serve(configuration: Configuration) -> Result<()> {
 
 #[cfg(test)]
 mod test_helpers {
-  use crate::Context;
+  use crate::{bg, Context, RecordStore};
   use sqlx::query;
 
+  pub struct GameFixture {
+    pub lobby_id: String,
+    pub game_id: String,
+  }
+
+  // Creates a lobby owned by the first user that every other user joins, along with a game for all
+  // of them, split into teams when requested.
+  pub async fn game_for_users(
+    records: &RecordStore,
+    owner: &String,
+    others: &[&String],
+    teams: Option<u8>,
+  ) -> GameFixture {
+    let job_id = format!("job-for-users-{}", owner);
+    let lobby_id = bg::handlers::lobbies::make_lobby(records, &job_id, owner)
+      .await
+      .expect("unable to create lobby");
+
+    let mut conn = records.acquire().await.expect("unable to connect");
+
+    for other in others {
+      query!(
+        "insert into krumnet.lobby_memberships (user_id, lobby_id, joined_at) values ($1, $2, now())",
+        *other,
+        lobby_id
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to join");
+    }
+
+    let game_id =
+      bg::handlers::lobbies::make_game_with_teams(records, &job_id, owner, &lobby_id, teams)
+        .await
+        .expect("unable to create game");
+
+    GameFixture { lobby_id, game_id }
+  }
+
   pub async fn cleanup_lobby(context: &Context, id: &String) {
     let mut conn = context
       .records_connection()
       .await
       .expect("unable to connect");
 
+    query!(
+      "delete from krumnet.game_share_tokens where game_id in (select id from krumnet.games where lobby_id = $1)",
+      id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
+
+    query!(
+      "delete from krumnet.user_achievements where game_id in (select id from krumnet.games where lobby_id = $1)",
+      id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
+
     query!(
       "delete from krumnet.game_member_round_placement_results where lobby_id = $1",
       id
@@ -230,6 +551,14 @@ mod test_helpers {
     .await
     .expect("unable to delete");
 
+    query!(
+      "delete from krumnet.reports where entry_id in (select id from krumnet.game_round_entries where lobby_id = $1)",
+      id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
+
     query!(
       "delete from krumnet.game_round_entries where lobby_id = $1",
       id
@@ -246,6 +575,11 @@ mod test_helpers {
     .await
     .expect("unable to delete");
 
+    query!("delete from krumnet.game_teams where lobby_id = $1", id)
+      .execute(&mut conn)
+      .await
+      .expect("unable to delete");
+
     query!("delete from krumnet.game_rounds where lobby_id = $1", id)
       .execute(&mut conn)
       .await
@@ -270,3 +604,105 @@ mod test_helpers {
       .expect("unable to delete");
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::{handle, router};
+  use crate::context::test_helpers::load_config;
+  use crate::{Context, JobStore, RecordStore, SessionStore};
+  use async_std::io::{Cursor, Read, Write};
+  use async_std::sync::Arc;
+  use async_std::task::block_on;
+  use std::pin::Pin;
+  use std::task::{Context as TaskContext, Poll};
+
+  // An in-memory connection; requests are read from `input` and responses collected in `output`.
+  struct Pipe {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>,
+  }
+
+  impl Read for Pipe {
+    fn poll_read(
+      mut self: Pin<&mut Self>,
+      cx: &mut TaskContext,
+      buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+      Pin::new(&mut self.input).poll_read(cx, buf)
+    }
+  }
+
+  impl Write for Pipe {
+    fn poll_write(
+      mut self: Pin<&mut Self>,
+      _: &mut TaskContext,
+      buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+      self.output.extend_from_slice(buf);
+      Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut TaskContext) -> Poll<std::io::Result<()>> {
+      Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut TaskContext) -> Poll<std::io::Result<()>> {
+      Poll::Ready(Ok(()))
+    }
+  }
+
+  fn responses(requests: &str, max_requests: usize) -> Vec<String> {
+    let mut config = load_config().unwrap();
+    config.keep_alive.max_requests = max_requests;
+
+    block_on(async {
+      let builder = Context::builder()
+        .configuration(&config)
+        .session(Arc::new(SessionStore::open(&config).await.unwrap()))
+        .records(Arc::new(RecordStore::open(&config).await.unwrap()))
+        .jobs(Arc::new(JobStore::open(&config).await.unwrap()));
+      let mut pipe = Pipe {
+        input: Cursor::new(requests.as_bytes().to_vec()),
+        output: Vec::new(),
+      };
+
+      handle(&mut pipe, builder, &router(), &config.keep_alive)
+        .await
+        .unwrap();
+
+      String::from_utf8(pipe.output)
+        .unwrap()
+        .split("HTTP/1.1 ")
+        .skip(1)
+        .map(String::from)
+        .collect()
+    })
+  }
+
+  #[test]
+  fn pipelined_requests() {
+    let requests = concat!(
+      "POST /routes HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}",
+      "GET /routes HTTP/1.1\r\n\r\n",
+      "GET /routes HTTP/1.1\r\nConnection: close\r\n\r\n",
+      "GET /routes HTTP/1.1\r\n\r\n",
+    );
+    let served = responses(requests, 100);
+
+    assert_eq!(served.len(), 3);
+    assert!(served[0].starts_with("405"));
+    assert!(served[0].contains("keep-alive: timeout=5, max=99"));
+    assert!(served[1].starts_with("200"));
+    assert!(served[1].contains("keep-alive: timeout=5, max=98"));
+    assert!(served[2].contains("connection: close"));
+  }
+
+  #[test]
+  fn max_requests_per_connection() {
+    let requests = "GET /routes HTTP/1.1\r\n\r\nGET /routes HTTP/1.1\r\n\r\n";
+    let served = responses(requests, 1);
+
+    assert_eq!(served.len(), 1);
+    assert!(served[0].contains("connection: close"));
+  }
+}
@@ -0,0 +1,305 @@
+use isahc::HttpClient;
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::io::Result;
+
+use crate::configuration::{
+  Configuration, GithubConfiguration, GoogleCredentials, OidcConfiguration, ProviderConfiguration,
+};
+use crate::constants::{
+  github_auth_url, github_emails_url, github_token_url, github_user_url, google_auth_url,
+  google_info_url, google_token_url, GOOGLE_AUTH_SCOPE_VALUE, OAUTH_CLIENT_ID_KEY,
+  OAUTH_CODE_CHALLENGE_KEY, OAUTH_CODE_CHALLENGE_METHOD_KEY, OAUTH_CODE_CHALLENGE_METHOD_VALUE,
+  OAUTH_REDIRECT_URI_KEY, OAUTH_RESPONSE_TYPE_KEY, OAUTH_RESPONSE_TYPE_VALUE, OAUTH_SCOPE_KEY,
+  OAUTH_STATE_KEY, OIDC_DISCOVERY_PATH,
+};
+use crate::errors;
+use crate::http::{header, query as qs, Method, Request, Url};
+use crate::session::OAuthState;
+
+pub const GOOGLE_PROVIDER: &str = "google";
+
+// Github rejects api requests that do not identify the client making them.
+const USER_AGENT: &str = "krumnet";
+
+// The response received from a provider's token endpoint after exchanging an authorization code.
+#[derive(Debug, PartialEq, Deserialize)]
+struct TokenExchangePayload {
+  access_token: String,
+}
+
+// The subset of the OpenID Connect discovery document needed to complete a login.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Endpoints {
+  #[serde(rename = "authorization_endpoint")]
+  pub authorization: String,
+  #[serde(rename = "token_endpoint")]
+  pub token: String,
+  #[serde(rename = "userinfo_endpoint")]
+  pub userinfo: String,
+}
+
+// Standard claims returned from an OpenID Connect userinfo endpoint.
+#[derive(Debug, Deserialize)]
+struct OidcUserInfo {
+  sub: String,
+  name: Option<String>,
+  email: Option<String>,
+  #[serde(default)]
+  email_verified: bool,
+  picture: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+  id: u64,
+  login: String,
+  name: Option<String>,
+  avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+  email: String,
+  primary: bool,
+  verified: bool,
+}
+
+// The account details of a user that has logged in with a provider, normalized across providers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+  pub provider: String,
+  pub subject: String,
+  pub name: String,
+  pub email: String,
+  pub email_verified: bool,
+  pub picture: Option<String>,
+}
+
+pub enum Provider {
+  Google(GoogleCredentials),
+  Oidc(String, OidcConfiguration),
+  Github(String, GithubConfiguration),
+}
+
+fn fetch<T: DeserializeOwned>(request: Request<()>) -> Result<T> {
+  let client = HttpClient::new().map_err(errors::humanize_error)?;
+  let uri = request.uri().clone();
+
+  match client.send(request) {
+    Ok(mut response) if response.status().is_success() => {
+      serde_json::from_reader(response.body_mut()).map_err(errors::humanize_error)
+    }
+    Ok(response) => Err(errors::e(format!(
+      "bad response status from '{}': {}",
+      uri,
+      response.status()
+    ))),
+    Err(e) => Err(errors::e(format!("unable to reach '{}': {}", uri, e))),
+  }
+}
+
+fn authorized_get<T: DeserializeOwned>(uri: &str, access_token: &str) -> Result<T> {
+  let request = Request::builder()
+    .method(Method::GET)
+    .uri(uri)
+    .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+    .header(header::ACCEPT, "application/json")
+    .header(header::USER_AGENT, USER_AGENT)
+    .body(())
+    .map_err(errors::humanize_error)?;
+
+  fetch(request)
+}
+
+impl Provider {
+  // Finds the provider registered under a name. Google is always available, using the credentials
+  // from the top-level `google` configuration unless it has been explicitly overridden.
+  pub fn find(configuration: &Configuration, name: &str) -> Option<Self> {
+    match configuration.providers.get(name) {
+      Some(ProviderConfiguration::Oidc(oidc)) => Some(Provider::Oidc(name.into(), oidc.clone())),
+      Some(ProviderConfiguration::Github(github)) => {
+        Some(Provider::Github(name.into(), github.clone()))
+      }
+      None if name == GOOGLE_PROVIDER => Some(Provider::Google(configuration.google.clone())),
+      None => None,
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    match self {
+      Provider::Google(_) => GOOGLE_PROVIDER,
+      Provider::Oidc(name, _) | Provider::Github(name, _) => name,
+    }
+  }
+
+  fn credentials(&self) -> (&str, &str, &str) {
+    match self {
+      Provider::Google(google) => (
+        &google.client_id,
+        &google.client_secret,
+        &google.redirect_uri,
+      ),
+      Provider::Oidc(_, oidc) => (&oidc.client_id, &oidc.client_secret, &oidc.redirect_uri),
+      Provider::Github(_, github) => (
+        &github.client_id,
+        &github.client_secret,
+        &github.redirect_uri,
+      ),
+    }
+  }
+
+  fn scopes(&self) -> String {
+    match self {
+      Provider::Google(_) => GOOGLE_AUTH_SCOPE_VALUE.to_string(),
+      Provider::Oidc(_, oidc) => oidc.scopes.join(" "),
+      Provider::Github(_, github) => github.scopes.join(" "),
+    }
+  }
+
+  pub async fn endpoints(&self) -> Result<Endpoints> {
+    match self {
+      Provider::Google(_) => Ok(Endpoints {
+        authorization: google_auth_url(),
+        token: google_token_url(),
+        userinfo: google_info_url(),
+      }),
+      Provider::Github(_, _) => Ok(Endpoints {
+        authorization: github_auth_url(),
+        token: github_token_url(),
+        userinfo: github_user_url(),
+      }),
+      Provider::Oidc(name, oidc) => {
+        let discovery = format!(
+          "{}/{}",
+          oidc.issuer.trim_end_matches('/'),
+          OIDC_DISCOVERY_PATH
+        );
+        debug!("loading discovery document for '{}' - {}", name, discovery);
+
+        let request = Request::builder()
+          .method(Method::GET)
+          .uri(discovery)
+          .header(header::ACCEPT, "application/json")
+          .body(())
+          .map_err(errors::humanize_error)?;
+
+        fetch(request)
+      }
+    }
+  }
+
+  // Builds the url that users are sent to in order to start logging in with the provider.
+  pub fn authorization_url(&self, endpoints: &Endpoints, flow: &OAuthState) -> Result<Url> {
+    let (client_id, _, redirect_uri) = self.credentials();
+    let mut url = endpoints
+      .authorization
+      .parse::<Url>()
+      .map_err(errors::humanize_error)?;
+
+    url
+      .query_pairs_mut()
+      .clear()
+      .append_pair(OAUTH_RESPONSE_TYPE_KEY, OAUTH_RESPONSE_TYPE_VALUE)
+      .append_pair(OAUTH_CLIENT_ID_KEY, client_id)
+      .append_pair(OAUTH_REDIRECT_URI_KEY, redirect_uri)
+      .append_pair(OAUTH_SCOPE_KEY, &self.scopes())
+      .append_pair(OAUTH_STATE_KEY, &flow.state)
+      .append_pair(OAUTH_CODE_CHALLENGE_KEY, &flow.code_challenge)
+      .append_pair(
+        OAUTH_CODE_CHALLENGE_METHOD_KEY,
+        OAUTH_CODE_CHALLENGE_METHOD_VALUE,
+      );
+
+    Ok(url)
+  }
+
+  // Exchanges the authorization code received in the callback for an access token.
+  pub async fn exchange_code(
+    &self,
+    endpoints: &Endpoints,
+    code: &str,
+    verifier: &str,
+  ) -> Result<String> {
+    let (client_id, client_secret, redirect_uri) = self.credentials();
+
+    let encoded = qs::Serializer::new(String::new())
+      .append_pair("code", code)
+      .append_pair("client_id", client_id)
+      .append_pair("client_secret", client_secret)
+      .append_pair("redirect_uri", redirect_uri)
+      .append_pair("grant_type", "authorization_code")
+      .append_pair("code_verifier", verifier)
+      .finish();
+
+    let client = HttpClient::new().map_err(errors::humanize_error)?;
+    let request = Request::builder()
+      .method(Method::POST)
+      .uri(&endpoints.token)
+      .header(header::ACCEPT, "application/json")
+      .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+      .body(encoded)
+      .map_err(errors::humanize_error)?;
+
+    match client.send(request) {
+      Ok(mut response) if response.status().is_success() => {
+        serde_json::from_reader::<_, TokenExchangePayload>(response.body_mut())
+          .map(|payload| payload.access_token)
+          .map_err(|e| errors::e(format!("unable to parse response body: {:?}", e)))
+      }
+      Ok(response) => Err(errors::e(format!(
+        "bad response from '{}' token exchange: {:?}",
+        self.name(),
+        response.status()
+      ))),
+      Err(e) => Err(errors::e(format!(
+        "unable to send code to '{}': {:?}",
+        self.name(),
+        e
+      ))),
+    }
+  }
+
+  // Loads the profile of the user that the access token was issued for.
+  pub async fn profile(&self, endpoints: &Endpoints, access_token: &str) -> Result<Profile> {
+    match self {
+      Provider::Google(_) | Provider::Oidc(_, _) => {
+        let info = authorized_get::<OidcUserInfo>(&endpoints.userinfo, access_token)?;
+        let email = info
+          .email
+          .ok_or_else(|| errors::e(format!("no email in '{}' profile", self.name())))?;
+
+        Ok(Profile {
+          provider: self.name().to_string(),
+          name: info.name.unwrap_or_else(|| email.clone()),
+          subject: info.sub,
+          email,
+          email_verified: info.email_verified,
+          picture: info.picture.filter(|picture| !picture.is_empty()),
+        })
+      }
+      Provider::Github(_, _) => {
+        let user = authorized_get::<GithubUser>(&endpoints.userinfo, access_token)?;
+        let emails = authorized_get::<Vec<GithubEmail>>(&github_emails_url(), access_token)?;
+
+        let email = emails
+          .into_iter()
+          .find(|email| email.primary && email.verified)
+          .ok_or_else(|| {
+            warn!("github user '{}' has no verified primary email", user.login);
+            errors::e("no verified primary email in github profile")
+          })?;
+
+        Ok(Profile {
+          provider: self.name().to_string(),
+          subject: user.id.to_string(),
+          name: user.name.unwrap_or(user.login),
+          email: email.email,
+          email_verified: email.verified,
+          picture: user.avatar_url,
+        })
+      }
+    }
+  }
+}
@@ -0,0 +1,139 @@
+// Ratings are a multiplayer adaptation of Elo: every finished game is treated as a set of
+// head-to-head matches between each pair of players, where the better placed player wins. The
+// adjustment from each match is scaled down by the number of opponents so that the total change
+// for a single game stays within the same bounds as a two player match. In team games players are
+// only matched against members of other teams.
+
+pub const INITIAL_RATING: f64 = 1500.0;
+pub const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+  pub user_id: String,
+  pub place: i32,
+  pub rating: f64,
+  pub team_id: Option<String>,
+}
+
+impl Standing {
+  fn opposes(&self, other: &Standing) -> bool {
+    other.user_id != self.user_id && (self.team_id.is_none() || other.team_id != self.team_id)
+  }
+}
+
+// The probability that a player with `rating` would beat a player with `opponent`.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+  1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+fn actual_score(place: i32, opponent: i32) -> f64 {
+  match place.cmp(&opponent) {
+    std::cmp::Ordering::Less => 1.0,
+    std::cmp::Ordering::Equal => 0.5,
+    std::cmp::Ordering::Greater => 0.0,
+  }
+}
+
+// Returns the new rating of every player in the game, in the same order as the standings given.
+pub fn update(standings: &[Standing]) -> Vec<(String, f64)> {
+  standings
+    .iter()
+    .map(|standing| {
+      let opponents = standings
+        .iter()
+        .filter(|other| standing.opposes(other))
+        .count();
+
+      if opponents == 0 {
+        return (standing.user_id.clone(), standing.rating);
+      }
+
+      let delta = standings
+        .iter()
+        .filter(|other| standing.opposes(other))
+        .map(|other| {
+          actual_score(standing.place, other.place) - expected_score(standing.rating, other.rating)
+        })
+        .sum::<f64>();
+
+      let rating = standing.rating + (K_FACTOR / opponents as f64) * delta;
+      (standing.user_id.clone(), rating)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::{expected_score, update, Standing, INITIAL_RATING};
+
+  fn standing(user_id: &str, place: i32, rating: f64) -> Standing {
+    Standing {
+      user_id: String::from(user_id),
+      place,
+      rating,
+      team_id: None,
+    }
+  }
+
+  #[test]
+  fn even_expectation() {
+    assert!((expected_score(INITIAL_RATING, INITIAL_RATING) - 0.5).abs() < f64::EPSILON);
+    assert!(expected_score(1700.0, 1500.0) > 0.75);
+  }
+
+  #[test]
+  fn head_to_head() {
+    let ratings = update(&[
+      standing("a", 1, INITIAL_RATING),
+      standing("b", 2, INITIAL_RATING),
+    ]);
+    assert_eq!(
+      ratings,
+      vec![(String::from("a"), 1516.0), (String::from("b"), 1484.0)]
+    );
+  }
+
+  #[test]
+  fn multiplayer_is_zero_sum() {
+    let ratings = update(&[
+      standing("a", 2, 1600.0),
+      standing("b", 1, 1450.0),
+      standing("c", 3, 1500.0),
+      standing("d", 4, 1525.0),
+    ]);
+    let before = 1600.0 + 1450.0 + 1500.0 + 1525.0;
+    let after = ratings.iter().map(|(_, rating)| rating).sum::<f64>();
+    assert!((before - after).abs() < 1e-9);
+    assert!(ratings[1].1 > 1450.0);
+    assert!(ratings[3].1 < 1525.0);
+  }
+
+  #[test]
+  fn teammates_share_results() {
+    let on_team = |user_id: &str, place: i32, team_id: &str| Standing {
+      team_id: Some(String::from(team_id)),
+      ..standing(user_id, place, INITIAL_RATING)
+    };
+    let ratings = update(&[
+      on_team("a", 1, "red"),
+      on_team("b", 1, "red"),
+      on_team("c", 2, "blue"),
+      on_team("d", 2, "blue"),
+    ]);
+    assert_eq!(
+      ratings,
+      vec![
+        (String::from("a"), 1516.0),
+        (String::from("b"), 1516.0),
+        (String::from("c"), 1484.0),
+        (String::from("d"), 1484.0),
+      ]
+    );
+  }
+
+  #[test]
+  fn single_player_unchanged() {
+    let ratings = update(&[standing("a", 1, INITIAL_RATING)]);
+    assert_eq!(ratings, vec![(String::from("a"), INITIAL_RATING)]);
+  }
+}
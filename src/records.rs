@@ -8,7 +8,7 @@ use sqlx::Postgres;
 
 use crate::{errors, Configuration};
 
-fn warn_and_return<E: std::error::Error>(error: E) -> Error {
+fn warn_and_return<E: std::error::Error + 'static>(error: E) -> Error {
   warn!("record store failure - {}", error);
   errors::humanize_error(error)
 }
@@ -18,6 +18,7 @@ pub struct RecordStore {
 }
 
 pub type Connection = PoolConnection<Postgres>;
+pub type Transaction = sqlx::Transaction<'static, Postgres>;
 
 impl RecordStore {
   pub async fn open(configuration: &Configuration) -> Result<Self> {
@@ -33,4 +34,9 @@ impl RecordStore {
   pub async fn acquire(&self) -> Result<Connection> {
     self._pg.acquire().await.map_err(warn_and_return)
   }
+
+  // Statements run against the returned transaction are rolled back unless it is committed.
+  pub async fn begin(&self) -> Result<Transaction> {
+    self._pg.begin().await.map_err(warn_and_return)
+  }
 }
@@ -0,0 +1,309 @@
+use elaine::RequestMethod;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Result;
+use std::pin::Pin;
+
+use crate::interchange::http::{RouteDescription, RouteListing};
+use crate::{Authority, Context, Response, Role, Uri};
+
+// Who may reach a route. Checked before the handler runs, so handlers for `User` routes can rely on
+// there being a user. `Session` routes manage the account itself and refuse api keys, so a leaked
+// key cannot be used to take it over. Admin routes pretend not to exist for everyone else.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+  Public,
+  User,
+  Session,
+  Admin,
+}
+
+// Everything a handler receives besides the context: the full uri, any `{name}` segments captured
+// from the path and the request body, which is read before routing.
+#[derive(Debug)]
+pub struct Request {
+  pub uri: Uri,
+  pub params: HashMap<String, String>,
+  pub body: Vec<u8>,
+}
+
+impl Request {
+  pub fn param(&self, name: &str) -> &str {
+    self
+      .params
+      .get(name)
+      .map(|value| value.as_str())
+      .unwrap_or_default()
+  }
+
+  pub fn body(&self) -> &[u8] {
+    &self.body
+  }
+}
+
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<Response>> + Send + 'a>>;
+
+pub type Handler = for<'a> fn(&'a Context, Request) -> HandlerFuture<'a>;
+
+pub struct Route {
+  pub method: RequestMethod,
+  pub path: &'static str,
+  pub access: Access,
+  handler: Handler,
+}
+
+#[derive(Default)]
+pub struct Router {
+  _routes: Vec<Route>,
+}
+
+pub enum Recognized<'a> {
+  Found(&'a Route, HashMap<String, String>),
+  MethodNotAllowed(Vec<RequestMethod>),
+  NotFound,
+}
+
+// Matches a path against a pattern segment by segment, capturing `{name}` segments.
+fn capture(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+  let mut expected = pattern.trim_end_matches('/').split('/');
+  let mut actual = path.trim_end_matches('/').split('/');
+  let mut params = HashMap::new();
+
+  loop {
+    match (expected.next(), actual.next()) {
+      (None, None) => return Some(params),
+      (Some(name), Some(value)) if name.starts_with('{') && name.ends_with('}') => {
+        if value.is_empty() {
+          return None;
+        }
+
+        let name = name.trim_start_matches('{').trim_end_matches('}');
+        params.insert(String::from(name), String::from(value));
+      }
+      (Some(literal), Some(value)) if literal == value => continue,
+      _ => return None,
+    }
+  }
+}
+
+impl Router {
+  pub fn new() -> Self {
+    Router::default()
+  }
+
+  pub fn route(
+    mut self,
+    method: RequestMethod,
+    path: &'static str,
+    access: Access,
+    handler: Handler,
+  ) -> Self {
+    self._routes.push(Route {
+      method,
+      path,
+      access,
+      handler,
+    });
+    self
+  }
+
+  pub fn routes(&self) -> &[Route] {
+    &self._routes
+  }
+
+  // Routes are tried in the order they were added, so literal paths should be added before any
+  // parameterized path they overlap with.
+  pub fn recognize(&self, method: &RequestMethod, path: &str) -> Recognized<'_> {
+    let mut allowed = Vec::new();
+
+    for route in &self._routes {
+      if let Some(params) = capture(route.path, path) {
+        if &route.method == method {
+          return Recognized::Found(route, params);
+        }
+
+        allowed.push(route.method.clone());
+      }
+    }
+
+    match allowed.is_empty() {
+      true => Recognized::NotFound,
+      false => Recognized::MethodNotAllowed(allowed),
+    }
+  }
+
+  pub fn listing(&self) -> RouteListing {
+    let routes = self
+      ._routes
+      .iter()
+      .map(|route| RouteDescription {
+        method: format!("{:?}", route.method),
+        path: String::from(route.path),
+        access: route.access,
+      })
+      .collect();
+
+    RouteListing { routes }
+  }
+
+  pub async fn dispatch(
+    &self,
+    context: &Context,
+    method: &RequestMethod,
+    uri: Uri,
+    body: Vec<u8>,
+  ) -> Result<Response> {
+    let (route, params) = match self.recognize(method, uri.path()) {
+      Recognized::Found(route, params) => (route, params),
+      Recognized::MethodNotAllowed(allowed) => {
+        return Ok(Response::method_not_allowed(&allowed).cors(context.cors()))
+      }
+      Recognized::NotFound => return Ok(Response::not_found().cors(context.cors())),
+    };
+
+    match (route.access, context.authority()) {
+      (Access::User, Authority::None)
+      | (Access::Session, Authority::None)
+      | (Access::Session, Authority::User { key: Some(_), .. }) => {
+        return Ok(Response::unauthorized().cors(context.cors()));
+      }
+      (Access::Admin, authority) if !authority.has_role(Role::Admin) => {
+        return Ok(Response::not_found().cors(context.cors()));
+      }
+      _ => (),
+    }
+
+    (route.handler)(context, Request { uri, params, body }).await
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{capture, Access, Recognized, Router};
+  use crate::context::test_helpers::with_auth;
+  use crate::{Authority, Response, Uri};
+  use async_std::task::block_on;
+  use elaine::RequestMethod;
+
+  fn router() -> Router {
+    Router::new()
+      .route(RequestMethod::GET, "/games", Access::User, |_, _| {
+        Box::pin(async { Ok(Response::ok_content("text/plain", "games")) })
+      })
+      .route(RequestMethod::GET, "/games/{id}", Access::User, |_, req| {
+        Box::pin(async move { Ok(Response::ok_content("text/plain", req.param("id"))) })
+      })
+      .route(
+        RequestMethod::DELETE,
+        "/games/{id}",
+        Access::Admin,
+        |_, _| Box::pin(async { Ok(Response::default()) }),
+      )
+      .route(
+        RequestMethod::DELETE,
+        "/sessions",
+        Access::Session,
+        |_, _| Box::pin(async { Ok(Response::default()) }),
+      )
+      .route(
+        RequestMethod::GET,
+        "/health-check",
+        Access::Public,
+        |_, _| Box::pin(async { Ok(Response::default()) }),
+      )
+  }
+
+  #[test]
+  fn captures_params() {
+    let params = capture("/lobbies/{id}/members", "/lobbies/abc/members").unwrap();
+    assert_eq!(params.get("id").map(|id| id.as_str()), Some("abc"));
+    assert!(capture("/lobbies/{id}/members", "/lobbies//members").is_none());
+    assert!(capture("/lobbies/{id}", "/lobbies/abc/members").is_none());
+    assert!(capture("/lobbies", "/lobbies/").is_some());
+  }
+
+  #[test]
+  fn recognizes_routes() {
+    let router = router();
+
+    match router.recognize(&RequestMethod::GET, "/games/abc") {
+      Recognized::Found(route, params) => {
+        assert_eq!(route.path, "/games/{id}");
+        assert_eq!(params.get("id").map(|id| id.as_str()), Some("abc"));
+      }
+      _ => panic!("expected to find route"),
+    }
+
+    match router.recognize(&RequestMethod::POST, "/games/abc") {
+      Recognized::MethodNotAllowed(allowed) => {
+        assert_eq!(allowed, vec![RequestMethod::GET, RequestMethod::DELETE])
+      }
+      _ => panic!("expected method not allowed"),
+    }
+
+    assert!(matches!(
+      router.recognize(&RequestMethod::GET, "/lobbies"),
+      Recognized::NotFound
+    ));
+
+    assert_eq!(router.listing().routes.len(), 5);
+  }
+
+  #[test]
+  fn checks_access() {
+    let router = router();
+    let anonymous = with_auth(Authority::None);
+    let player = with_auth(Authority::user(String::from("player"), String::new()));
+    let uri = |path: &str| path.parse::<Uri>().unwrap();
+
+    block_on(async {
+      let get = RequestMethod::GET;
+      let response = router.dispatch(&anonymous, &get, uri("/health-check"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).starts_with("HTTP/1.1 200"));
+
+      let response = router.dispatch(&anonymous, &get, uri("/games/abc"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).starts_with("HTTP/1.1 401"));
+
+      let response = router.dispatch(&player, &get, uri("/games/abc"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).ends_with("abc"));
+
+      let delete = RequestMethod::DELETE;
+      let response = router.dispatch(&player, &delete, uri("/games/abc"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).starts_with("HTTP/1.1 404"));
+
+      let post = RequestMethod::POST;
+      let response = router.dispatch(&player, &post, uri("/games/abc"), vec![]);
+      let response = format!("{}", response.await.unwrap());
+      assert!(response.starts_with("HTTP/1.1 405"));
+      assert!(response.contains("allow: GET, DELETE"));
+    });
+  }
+
+  #[test]
+  fn sessions_only() {
+    let router = router();
+    let player = with_auth(Authority::user(String::from("player"), String::new()));
+    let keyed = with_auth(Authority::User {
+      id: String::from("player"),
+      token: String::new(),
+      roles: vec![],
+      key: Some(String::from("key")),
+    });
+    let uri = |path: &str| path.parse::<Uri>().unwrap();
+
+    block_on(async {
+      let delete = RequestMethod::DELETE;
+      let response = router.dispatch(&player, &delete, uri("/sessions"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).starts_with("HTTP/1.1 200"));
+
+      let response = router.dispatch(&keyed, &delete, uri("/sessions"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).starts_with("HTTP/1.1 401"));
+
+      let get = RequestMethod::GET;
+      let response = router.dispatch(&keyed, &get, uri("/games/abc"), vec![]);
+      assert!(format!("{}", response.await.unwrap()).ends_with("abc"));
+    });
+  }
+}
@@ -0,0 +1,22 @@
+with ended_games as (
+  update
+    krumnet.games
+  set
+    ended_at = now()
+  where
+    lobby_id = $1
+  and
+    ended_at is null
+  returning
+    id
+)
+update
+  krumnet.lobbies
+set
+  closed_at = now()
+where
+  id = $1
+and
+  closed_at is null
+returning
+  id;
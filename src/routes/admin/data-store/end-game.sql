@@ -0,0 +1,10 @@
+update
+  krumnet.games
+set
+  ended_at = now()
+where
+  id = $1
+and
+  ended_at is null
+returning
+  id;
@@ -0,0 +1,7 @@
+insert into krumnet.user_roles
+  (user_id, role, granted_by)
+values
+  ($1, $2, $3)
+on conflict (user_id, role) do nothing
+returning
+  id;
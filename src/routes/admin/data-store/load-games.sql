@@ -0,0 +1,22 @@
+select
+  games.id         as game_id,
+  games.name       as game_name,
+  games.lobby_id   as lobby_id,
+  games.created_at as created_at,
+  games.ended_at   as ended_at,
+  (
+    select
+      count(*)
+    from
+      krumnet.game_memberships as members
+    where
+      members.game_id = games.id
+  )                as member_count
+from
+  krumnet.games as games
+where
+  (cast($1 as varchar) is null or games.id = $1)
+order by
+  games.created_at desc
+limit $2
+offset $3;
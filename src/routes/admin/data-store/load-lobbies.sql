@@ -0,0 +1,31 @@
+select
+  lobbies.id         as lobby_id,
+  lobbies.name       as lobby_name,
+  lobbies.created_at as created_at,
+  lobbies.closed_at  as closed_at,
+  (
+    select
+      count(*)
+    from
+      krumnet.lobby_memberships as members
+    where
+      members.lobby_id = lobbies.id
+    and
+      members.left_at is null
+  )                  as member_count,
+  (
+    select
+      count(*)
+    from
+      krumnet.games as games
+    where
+      games.lobby_id = lobbies.id
+  )                  as game_count
+from
+  krumnet.lobbies as lobbies
+where
+  (cast($1 as varchar) is null or lobbies.id = $1)
+order by
+  lobbies.created_at desc
+limit $2
+offset $3;
@@ -0,0 +1,8 @@
+delete from
+  krumnet.user_roles
+where
+  user_id = $1
+and
+  role = $2
+returning
+  id;
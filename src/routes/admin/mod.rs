@@ -0,0 +1,473 @@
+use async_std::io::Read as AsyncRead;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::from_slice as deserialize;
+use sqlx::query_file;
+use std::io::Result;
+use std::marker::Unpin;
+
+use crate::http::{page, Page, Uri};
+use crate::interchange::http::{
+  AdminGame, AdminGameDetails, AdminGameList, AdminLobby, AdminLobbyDetails, AdminLobbyList,
+  SessionTokenData,
+};
+use crate::routes::{games, lobbies};
+use crate::{errors, read_size_async, Authority, Context, Response, Role};
+
+const UNKNOWN_ROLE: &str = "errors.roles.unknown";
+
+#[derive(Debug, Deserialize)]
+struct ImpersonatePayload {
+  pub user_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RolePayload {
+  pub role: String,
+}
+
+// Anyone without the admin role is treated as though the admin routes do not exist at all.
+pub fn admin_id(context: &Context) -> Option<&String> {
+  match context.authority() {
+    Authority::User { id, .. } if context.authority().has_role(Role::Admin) => Some(id),
+    _ => None,
+  }
+}
+
+async fn load_lobbies(
+  context: &Context,
+  id: Option<&String>,
+  page: &Page,
+) -> Result<Vec<AdminLobby>> {
+  let mut conn = context.records_connection().await?;
+
+  query_file!(
+    "src/routes/admin/data-store/load-lobbies.sql",
+    id,
+    page.size,
+    page.offset()
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .map(|row| {
+    Ok(AdminLobby {
+      id: row.lobby_id,
+      name: row.lobby_name,
+      created: row
+        .created_at
+        .ok_or_else(|| errors::e("Unable to parse lobby created timestamp"))?,
+      closed: row.closed_at,
+      member_count: row.member_count.unwrap_or_default(),
+      game_count: row.game_count.unwrap_or_default(),
+    })
+  })
+  .collect()
+}
+
+async fn load_games(context: &Context, id: Option<&String>, page: &Page) -> Result<Vec<AdminGame>> {
+  let mut conn = context.records_connection().await?;
+
+  query_file!(
+    "src/routes/admin/data-store/load-games.sql",
+    id,
+    page.size,
+    page.offset()
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .map(|row| {
+    Ok(AdminGame {
+      id: row.game_id,
+      name: row.game_name,
+      lobby_id: row.lobby_id,
+      created: row
+        .created_at
+        .ok_or_else(|| errors::e("Unable to parse game created timestamp"))?,
+      ended: row.ended_at,
+      member_count: row.member_count.unwrap_or_default(),
+    })
+  })
+  .collect()
+}
+
+fn single() -> Page {
+  Page { number: 1, size: 1 }
+}
+
+// Route
+// GET /admin/lobbies
+//
+// Lists every lobby, newest first, regardless of whether the admin is a member.
+pub async fn find_lobbies(context: &Context, uri: &Uri) -> Result<Response> {
+  if admin_id(context).is_none() {
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  let page = page(uri);
+  let lobbies = load_lobbies(context, None, &page).await?;
+
+  Response::ok_json(AdminLobbyList {
+    page: page.number,
+    per_page: page.size,
+    lobbies,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+// Route
+// GET /admin/lobbies/{id}
+pub async fn find_lobby(context: &Context, id: &String) -> Result<Response> {
+  if admin_id(context).is_none() {
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  let lobby = match load_lobbies(context, Some(id), &single()).await?.pop() {
+    Some(lobby) => lobby,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let members = lobbies::load_members(context, id).await?;
+  let games = lobbies::load_games(context, id).await?;
+
+  Response::ok_json(AdminLobbyDetails {
+    lobby,
+    members,
+    games,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+// Route
+// POST /admin/lobbies/{id}/close
+//
+// Closes the lobby and ends any of its games that are still being played.
+pub async fn close_lobby(context: &Context, id: &String) -> Result<Response> {
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let closed = query_file!("src/routes/admin/data-store/close-lobby.sql", id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+  match closed {
+    Some(row) => {
+      info!("admin '{}' closed lobby '{}'", uid, row.id);
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// GET /admin/games
+pub async fn find_games(context: &Context, uri: &Uri) -> Result<Response> {
+  if admin_id(context).is_none() {
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  let page = page(uri);
+  let games = load_games(context, None, &page).await?;
+
+  Response::ok_json(AdminGameList {
+    page: page.number,
+    per_page: page.size,
+    games,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+// Route
+// GET /admin/games/{id}
+pub async fn find_game(context: &Context, id: &String) -> Result<Response> {
+  if admin_id(context).is_none() {
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  let game = match load_games(context, Some(id), &single()).await?.pop() {
+    Some(game) => game,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let members = games::members_for_game(context, id).await?;
+  let rounds = games::rounds_for_game(context, id).await?;
+
+  Response::ok_json(AdminGameDetails {
+    game,
+    members,
+    rounds,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+// Route
+// POST /admin/games/{id}/end
+//
+// Marks the game as ended; rounds that were still in progress are left as they were.
+pub async fn end_game(context: &Context, id: &String) -> Result<Response> {
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let ended = query_file!("src/routes/admin/data-store/end-game.sql", id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+  match ended {
+    Some(row) => {
+      info!("admin '{}' ended game '{}'", uid, row.id);
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// POST /admin/impersonate
+//
+// Creates a session for another user so support can see exactly what they see. The session is
+// labelled with the admin's id and shows up in the user's own session list.
+pub async fn impersonate<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<ImpersonatePayload>(&contents)?;
+  let mut conn = context.records_connection().await?;
+
+  let user = query_file!("src/data-store/user-for-session.sql", payload.user_id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+  let user_id = match user {
+    Some(row) => row.user_id,
+    None => {
+      warn!("admin '{}' unable to impersonate unknown user", uid);
+      return Ok(Response::not_found().cors(context.cors()));
+    }
+  };
+
+  let agent = format!("impersonated by {}", uid);
+  let tokens = context.session().create(&user_id, Some(&agent)).await?;
+  info!("admin '{}' impersonating user '{}'", uid, user_id);
+
+  Response::ok_json(SessionTokenData {
+    token: tokens.token,
+    refresh_token: tokens.refresh_token,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+// Route
+// POST /admin/users/{id}/roles
+//
+// Grants a role to the user; granting a role the user already holds is a no-op.
+pub async fn grant_role<R>(context: &Context, user_id: &String, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<RolePayload>(&contents)?;
+
+  let role = match Role::from_name(&payload.role) {
+    Some(role) => role,
+    None => return Ok(Response::bad_request(UNKNOWN_ROLE).cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  query_file!(
+    "src/routes/admin/data-store/grant-role.sql",
+    user_id,
+    role.name(),
+    uid
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?;
+
+  info!("admin '{}' granted '{}' to '{}'", uid, role.name(), user_id);
+  Ok(Response::default().cors(context.cors()))
+}
+
+// Route
+// DELETE /admin/users/{id}/roles/{role}
+pub async fn revoke_role(context: &Context, user_id: &String, role: &str) -> Result<Response> {
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let role = match Role::from_name(role) {
+    Some(role) => role,
+    None => return Ok(Response::bad_request(UNKNOWN_ROLE).cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let revoked = query_file!(
+    "src/routes/admin/data-store/revoke-role.sql",
+    user_id,
+    role.name()
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next();
+
+  match revoked {
+    Some(_) => {
+      info!(
+        "admin '{}' revoked '{}' from '{}'",
+        uid,
+        role.name(),
+        user_id
+      );
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => {
+      debug!("user '{}' did not have '{}'", user_id, role.name());
+      Ok(Response::not_found().cors(context.cors()))
+    }
+  }
+}
+
+// Route
+// GET /admin/token-rejections
+//
+// The number of session tokens turned away for each reason since this process started.
+pub async fn token_rejections(context: &Context) -> Result<Response> {
+  if admin_id(context).is_none() {
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  Response::ok_json(context.session().rejections()).map(|response| response.cors(context.cors()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::{close_lobby, find_lobby, revoke_role, token_rejections};
+  use crate::context::{load_authorization, test_helpers as context_helpers};
+  use crate::{bg, test_helpers::cleanup_lobby, Authority, Role};
+  use async_std::task::block_on;
+  use sqlx::{query, query_file};
+
+  fn admin(id: &str) -> Authority {
+    Authority::User {
+      id: String::from(id),
+      token: String::new(),
+      roles: vec![Role::Admin],
+      key: None,
+    }
+  }
+
+  #[test]
+  fn roles_loaded_for_sessions() {
+    block_on(async {
+      let admin_id = context_helpers::make_user("routes.admin.roles.admin").await;
+      let user_id = context_helpers::make_user("routes.admin.roles.user").await;
+      let context = context_helpers::with_auth(admin(&admin_id));
+      let mut conn = context.records_connection().await.unwrap();
+
+      query_file!(
+        "src/routes/admin/data-store/grant-role.sql",
+        user_id,
+        Role::Admin.name(),
+        admin_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .unwrap();
+
+      let tokens = context.session().create(&user_id, None).await.unwrap();
+      let authority =
+        load_authorization(tokens.token.clone(), context.session(), context.records())
+          .await
+          .unwrap();
+      assert!(authority.has_role(Role::Admin));
+
+      let player = context_helpers::with_auth(Authority::user(admin_id.clone(), String::new()));
+      let response = revoke_role(&player, &user_id, "admin").await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+      let response = token_rejections(&player).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+      let response = format!("{}", token_rejections(&context).await.unwrap());
+      assert!(response.starts_with("HTTP/1.1 200"));
+      assert!(response.contains("\"malformed\""));
+
+      let response = revoke_role(&context, &user_id, "admin").await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 200"));
+
+      let authority =
+        load_authorization(tokens.token.clone(), context.session(), context.records())
+          .await
+          .unwrap();
+      assert!(!authority.has_role(Role::Admin));
+
+      context.session().revoke_all(&user_id).await.unwrap();
+      context_helpers::cleanup_user(&user_id).await;
+      context_helpers::cleanup_user(&admin_id).await;
+    });
+  }
+
+  #[test]
+  fn close_lobby_once() {
+    block_on(async {
+      let (context, uid) = context_helpers::with_user_by_name("routes.admin.close_lobby").await;
+      let lobby_id = bg::handlers::lobbies::make_lobby(context.records(), &uid, &uid)
+        .await
+        .expect("unable to create");
+
+      let response = find_lobby(&context, &lobby_id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      let admin_context = context_helpers::with_auth(admin(&uid));
+      let response = close_lobby(&admin_context, &lobby_id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 200"));
+      let response = close_lobby(&admin_context, &lobby_id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      let mut conn = context.records_connection().await.unwrap();
+      let closed = query!(
+        "select closed_at from krumnet.lobbies where id = $1",
+        lobby_id
+      )
+      .fetch_one(&mut conn)
+      .await
+      .unwrap();
+      assert!(closed.closed_at.is_some());
+
+      let response = find_lobby(&admin_context, &lobby_id).await.unwrap();
+      assert!(format!("{}", response).contains("\"closed\":"));
+
+      cleanup_lobby(&context, &lobby_id).await;
+      context_helpers::cleanup(&context).await;
+    });
+  }
+}
@@ -0,0 +1,21 @@
+insert into krumnet.api_keys
+  (user_id, name, prefix, key_hash, scopes)
+select
+  cast($1 as varchar),
+  cast($2 as varchar),
+  cast($3 as varchar),
+  cast($4 as varchar),
+  cast($5 as varchar[])
+where
+  (
+    select
+      count(*)
+    from
+      krumnet.api_keys as keys
+    where
+      keys.user_id = $1
+    and
+      keys.revoked_at is null
+  ) < $6
+returning
+  id as key_id;
@@ -0,0 +1,16 @@
+select
+  keys.id           as key_id,
+  keys.name         as name,
+  keys.prefix       as prefix,
+  keys.scopes       as scopes,
+  keys.use_count    as use_count,
+  keys.created_at   as created_at,
+  keys.last_used_at as last_used_at
+from
+  krumnet.api_keys as keys
+where
+  keys.user_id = $1
+and
+  keys.revoked_at is null
+order by
+  keys.created_at desc;
@@ -0,0 +1,12 @@
+update
+  krumnet.api_keys
+set
+  revoked_at = now()
+where
+  id = $1
+and
+  user_id = $2
+and
+  revoked_at is null
+returning
+  id;
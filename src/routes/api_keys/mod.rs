@@ -0,0 +1,250 @@
+use async_std::io::Read as AsyncRead;
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::from_slice as deserialize;
+use sqlx::query_file;
+use std::io::Result;
+use std::marker::Unpin;
+
+use crate::constants::{MAX_API_KEYS, MAX_API_KEY_NAME_LENGTH};
+use crate::interchange::http::{ApiKey, ApiKeyList, NewApiKey};
+use crate::{api_keys, errors, read_size_async, Authority, Context, Response, Scope};
+
+const INVALID_NAME: &str = "errors.api_keys.invalid_name";
+const INVALID_SCOPES: &str = "errors.api_keys.invalid_scopes";
+const TOO_MANY_KEYS: &str = "errors.api_keys.too_many";
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyPayload {
+  pub name: String,
+  pub scopes: Vec<String>,
+}
+
+// Keys are managed from a logged in session only; a leaked key should not be able to mint more.
+fn session_user(context: &Context) -> Option<&String> {
+  match context.authority() {
+    Authority::User { id, key: None, .. } => Some(id),
+    _ => None,
+  }
+}
+
+fn valid_scopes(names: &[String]) -> Option<Vec<Scope>> {
+  let scopes = names
+    .iter()
+    .map(|name| Scope::from_name(name))
+    .collect::<Option<Vec<Scope>>>()?;
+
+  match scopes.is_empty() {
+    true => None,
+    false => Some(scopes),
+  }
+}
+
+pub async fn keys_for_user(context: &Context, uid: &str) -> Result<Vec<ApiKey>> {
+  let mut conn = context.records_connection().await?;
+
+  query_file!("src/routes/api_keys/data-store/load-api-keys.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| {
+      Ok(ApiKey {
+        id: row.key_id,
+        name: row.name,
+        prefix: row.prefix,
+        scopes: row.scopes,
+        use_count: row.use_count,
+        created: row
+          .created_at
+          .ok_or_else(|| errors::e("Unable to parse api key created timestamp"))?,
+        last_used: row.last_used_at,
+      })
+    })
+    .collect()
+}
+
+// Stores the digest of a new key, returning nothing if the user is already at their limit.
+async fn create_key(
+  context: &Context,
+  uid: &str,
+  name: &str,
+  scopes: Vec<String>,
+) -> Result<Option<NewApiKey>> {
+  let key = api_keys::generate();
+  let mut conn = context.records_connection().await?;
+  let created = query_file!(
+    "src/routes/api_keys/data-store/create-api-key.sql",
+    uid,
+    name,
+    api_keys::display_prefix(&key),
+    api_keys::digest(&key),
+    &scopes,
+    MAX_API_KEYS
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next();
+
+  Ok(created.map(|row| {
+    info!("user '{}' created api key '{}'", uid, row.key_id);
+    NewApiKey {
+      id: row.key_id,
+      name: String::from(name),
+      scopes,
+      key,
+    }
+  }))
+}
+
+// Route
+// GET /api-keys
+//
+// Lists the current user's active api keys. The keys themselves are never returned after creation.
+pub async fn find(context: &Context) -> Result<Response> {
+  let uid = match session_user(context) {
+    Some(id) => id,
+    None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let keys = keys_for_user(context, uid).await?;
+  Response::ok_json(ApiKeyList { keys }).map(|r| r.cors(context.cors()))
+}
+
+// Route
+// POST /api-keys
+//
+// Creates a key that can be sent in the `Authorization` header in place of a session token. The
+// response is the only time the key is available; clients that lose it must create a new one.
+pub async fn create<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match session_user(context) {
+    Some(id) => id,
+    None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<ApiKeyPayload>(&contents)?;
+  let name = payload.name.trim();
+
+  if name.is_empty() || name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+    return Ok(Response::bad_request(INVALID_NAME).cors(context.cors()));
+  }
+
+  let scopes = match valid_scopes(&payload.scopes) {
+    Some(scopes) => scopes
+      .iter()
+      .map(|scope| String::from(scope.name()))
+      .collect::<Vec<String>>(),
+    None => return Ok(Response::bad_request(INVALID_SCOPES).cors(context.cors())),
+  };
+
+  match create_key(context, uid, name, scopes).await? {
+    Some(created) => Response::ok_json(created).map(|r| r.cors(context.cors())),
+    None => {
+      warn!("user '{}' already has the maximum number of api keys", uid);
+      Ok(Response::bad_request(TOO_MANY_KEYS).cors(context.cors()))
+    }
+  }
+}
+
+// Route
+// DELETE /api-keys/{id}
+pub async fn destroy(context: &Context, key_id: &str) -> Result<Response> {
+  let uid = match session_user(context) {
+    Some(id) => id,
+    None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let revoked = query_file!(
+    "src/routes/api_keys/data-store/revoke-api-key.sql",
+    key_id,
+    uid
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next();
+
+  match revoked {
+    Some(row) => {
+      info!("user '{}' revoked api key '{}'", uid, row.id);
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{create_key, destroy, find, keys_for_user};
+  use crate::context::{load_key_authorization, test_helpers as context_helpers};
+  use crate::Authority;
+  use async_std::task::block_on;
+  use elaine::RequestMethod;
+
+  #[test]
+  fn keys_authorize_within_scope() {
+    block_on(async {
+      let (context, uid) = context_helpers::with_user_by_name("api_keys.within_scope").await;
+      let created = create_key(&context, &uid, "bot", vec![String::from("read")])
+        .await
+        .unwrap()
+        .unwrap();
+
+      let authority = load_key_authorization(&created.key, &RequestMethod::GET, context.records())
+        .await
+        .unwrap();
+      assert_eq!(
+        authority,
+        Authority::User {
+          id: uid.clone(),
+          token: String::new(),
+          roles: Vec::new(),
+          key: Some(created.id.clone()),
+        }
+      );
+
+      let authority = load_key_authorization(&created.key, &RequestMethod::POST, context.records())
+        .await
+        .unwrap();
+      assert_eq!(authority, Authority::None);
+
+      let keys = keys_for_user(&context, &uid).await.unwrap();
+      assert_eq!(keys.len(), 1);
+      assert_eq!(keys[0].use_count, 1);
+      assert!(keys[0].last_used.is_some());
+      assert!(created.key.starts_with(&keys[0].prefix));
+
+      // Keys can not be used to manage keys.
+      let bot = context_helpers::with_auth(authority_for_key(&uid, &created.id));
+      let response = find(&bot).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 401"));
+
+      let response = destroy(&context, &created.id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 200"));
+
+      let authority = load_key_authorization(&created.key, &RequestMethod::GET, context.records())
+        .await
+        .unwrap();
+      assert_eq!(authority, Authority::None);
+
+      context_helpers::cleanup(&context).await;
+    });
+  }
+
+  fn authority_for_key(uid: &str, key_id: &str) -> Authority {
+    Authority::User {
+      id: String::from(uid),
+      token: String::new(),
+      roles: Vec::new(),
+      key: Some(String::from(key_id)),
+    }
+  }
+}
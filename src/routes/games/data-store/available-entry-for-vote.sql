@@ -2,7 +2,15 @@ select
   entries.id as id
 from
   krumnet.game_round_entries as entries
+inner join
+  krumnet.game_memberships as voters
+on
+  voters.game_id = entries.game_id
+and
+  voters.user_id = $2
 where
   entries.id = $1
 and 
-  entries.user_id <> $2;
+  entries.user_id <> $2
+and
+  (voters.team_id is null or entries.team_id <> voters.team_id);
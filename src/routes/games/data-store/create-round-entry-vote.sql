@@ -1,5 +1,5 @@
 insert into
-  krumnet.game_round_entry_votes
+  krumnet.game_round_entry_votes as votes
   (round_id, lobby_id, game_id, member_id, user_id, entry_id)
 select
   entries.round_id,
@@ -10,7 +10,18 @@ select
   entries.id
 from
   krumnet.game_round_entries as entries
+inner join
+  krumnet.game_rounds as rounds
+on
+  rounds.id = entries.round_id
 where
   entries.id = $1
+and
+  rounds.completed_at is null
+on conflict
+  (round_id, member_id)
+do update set
+  entry_id   = excluded.entry_id,
+  created_at = now()
 returning
   id;
@@ -1,9 +1,10 @@
 with new_entry as (
   insert into
     krumnet.game_round_entries
-    (round_id, member_id, entry, game_id, lobby_id, user_id)
+    (round_id, member_id, entry, game_id, lobby_id, user_id, team_id)
   values
-    ($1, $2, $3, $4, $5, $6)
+    ($1, $2, $3, $4, $5, $6, $7)
+  on conflict on constraint single_team_entry do nothing
   returning *
 ) select
   id       as entry_id,
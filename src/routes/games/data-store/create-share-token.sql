@@ -0,0 +1,23 @@
+insert into
+  krumnet.game_share_tokens as tokens
+  (game_id, created_by, token)
+select
+  games.id, cast($2 as varchar), $3
+from
+  krumnet.games as games
+inner join
+  krumnet.game_memberships as members
+on
+  members.game_id = games.id
+where
+  games.id = $1
+and
+  members.user_id = cast($2 as varchar)
+and
+  games.ended_at is not null
+on conflict on constraint
+  single_game_share_token
+do update set
+  game_id = excluded.game_id
+returning
+  tokens.token as token;
@@ -0,0 +1,15 @@
+delete from
+  krumnet.game_round_entry_votes as votes
+using
+  krumnet.game_rounds as rounds
+where
+  rounds.id = votes.round_id
+and
+  votes.id = $1
+and
+  votes.user_id = $2
+and
+  rounds.completed_at is null
+returning
+  votes.id       as id,
+  votes.round_id as round_id;
@@ -0,0 +1,12 @@
+delete from
+  krumnet.game_share_tokens as tokens
+using
+  krumnet.game_memberships as members
+where
+  tokens.game_id = $1
+and
+  members.game_id = tokens.game_id
+and
+  members.user_id = $2
+returning
+  tokens.id as id;
@@ -0,0 +1,6 @@
+select
+  games.id as game_id
+from
+  krumnet.games as games
+where
+  games.id = $1;
@@ -3,7 +3,8 @@ select
   games.id            as game_id,
   rounds.id           as round_id,
   memberships.id      as member_id,
-  memberships.user_id as user_id
+  memberships.user_id as user_id,
+  memberships.team_id as team_id
 from
   krumnet.game_memberships as memberships
 inner join
@@ -11,7 +11,7 @@ inner join
 on
   member.game_id = game.id
 where
-  game.id = $1
+  game.id = any(cast($1 as varchar[]))
 and
   member.user_id = $2
 group by
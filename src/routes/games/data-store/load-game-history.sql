@@ -0,0 +1,27 @@
+select
+  games.id          as game_id,
+  games.name        as game_name,
+  games.created_at  as created_at,
+  games.ended_at    as ended_at,
+  count(members.id) as member_count
+from
+  krumnet.games as games
+left join
+  krumnet.game_memberships as members
+on
+  members.game_id = games.id
+where
+  games.lobby_id = $1
+and
+  (cast($2 as boolean) is null or (games.ended_at is not null) = $2)
+and
+  (
+    cast($3 as timestamptz) is null
+    or (date_trunc('milliseconds', games.created_at), games.id) < (date_trunc('milliseconds', $3), cast($4 as varchar))
+  )
+group by
+  games.id
+order by
+  date_trunc('milliseconds', games.created_at) desc, games.id desc
+limit
+  $5;
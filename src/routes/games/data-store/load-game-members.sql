@@ -1,14 +1,17 @@
 select
-  members.id          as member_id,
+  members.id          as "member_id!",
   members.created_at  as created_at,
-  users.id            as user_id,
+  members.game_id     as "game_id!",
+  members.team_id     as team_id,
+  users.id            as "user_id!",
   users.default_email as user_email,
-  users.name          as user_name
+  users.name          as "user_name!",
+  users.avatar_url    as user_avatar_url
 from
   krumnet.game_memberships as members
-right join
+inner join
   krumnet.users as users
 on
   users.id = members.user_id
 where
-  members.game_id = $1;
+  members.game_id = any(cast($1 as varchar[]));
@@ -0,0 +1,11 @@
+select
+  teams.id       as id,
+  teams.game_id  as game_id,
+  teams.name     as name,
+  teams.position as position
+from
+  krumnet.game_teams as teams
+where
+  teams.game_id = any(cast($1 as varchar[]))
+order by
+  teams.position asc;
@@ -1,5 +1,6 @@
 select
   placements.id         as id,
+  placements.game_id    as game_id,
   placements.place      as placement,
   placements.vote_count as vote_count,
   users.name            as user_name,
@@ -11,6 +12,6 @@ left join
 on
   users.id = placements.user_id
 where
-  placements.game_id = $1
+  placements.game_id = any(cast($1 as varchar[]))
 order by
   placements.place asc;
@@ -1,5 +1,6 @@
 select
   rounds.id           as id,
+  rounds.game_id      as game_id,
   rounds.position     as pos,
   rounds.prompt       as prompt,
   rounds.created_at   as created_at,
@@ -9,4 +10,6 @@ select
 from
   krumnet.game_rounds as rounds
 where
-  rounds.game_id = $1;
+  rounds.game_id = any(cast($1 as varchar[]))
+order by
+  rounds.position asc;
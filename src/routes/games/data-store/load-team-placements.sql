@@ -0,0 +1,42 @@
+with team_rounds as (
+  select
+    placements.team_id         as team_id,
+    placements.round_id        as round_id,
+    min(placements.place)      as place,
+    max(placements.vote_count) as vote_count
+  from
+    krumnet.game_member_round_placement_results as placements
+  where
+    placements.game_id = any(cast($1 as varchar[]))
+  and
+    placements.team_id is not null
+  group by
+    placements.team_id,
+    placements.round_id
+)
+select
+  teams.id                                as team_id,
+  teams.game_id                           as game_id,
+  teams.name                              as team_name,
+  cast(dense_rank() over (
+    partition by teams.game_id order by sum(team_rounds.place) asc
+  ) as int)                               as placement,
+  cast(sum(team_rounds.vote_count) as int) as vote_count
+from
+  krumnet.game_teams as teams
+inner join
+  krumnet.games as games
+on
+  games.id = teams.game_id
+inner join
+  team_rounds
+on
+  team_rounds.team_id = teams.id
+where
+  teams.game_id = any(cast($1 as varchar[]))
+and
+  games.ended_at is not null
+group by
+  teams.id
+order by
+  teams.game_id, sum(team_rounds.place) asc;
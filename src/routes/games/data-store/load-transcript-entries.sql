@@ -0,0 +1,49 @@
+select
+  rounds.position    as position,
+  rounds.prompt      as prompt,
+  entries.id         as entry_id,
+  users.name         as author,
+  case
+    when entries.hidden_at is null then entries.entry
+    else null
+  end                as entry,
+  results.place      as "place?",
+  coalesce(
+    array_agg(voters.name order by voters.name) filter (where voters.id is not null),
+    '{}'
+  )                  as voters
+from
+  krumnet.game_rounds as rounds
+inner join
+  krumnet.game_round_entries as entries
+on
+  entries.round_id = rounds.id
+inner join
+  krumnet.users as users
+on
+  users.id = entries.user_id
+left join
+  krumnet.game_member_round_placement_results as results
+on
+  results.round_id = entries.round_id
+and
+  results.member_id = entries.member_id
+left join
+  krumnet.game_round_entry_votes as votes
+on
+  votes.entry_id = entries.id
+left join
+  krumnet.users as voters
+on
+  voters.id = votes.user_id
+where
+  rounds.game_id = $1
+group by
+  rounds.id,
+  entries.id,
+  users.id,
+  results.id
+order by
+  rounds.position asc,
+  results.place asc nulls last,
+  entries.created_at asc;
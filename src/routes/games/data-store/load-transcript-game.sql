@@ -0,0 +1,35 @@
+select
+  games.id         as game_id,
+  games.name       as game_name,
+  games.created_at as created_at,
+  games.ended_at   as ended_at
+from
+  krumnet.games as games
+where
+  games.id = $1
+and
+  games.ended_at is not null
+and
+  (
+    exists (
+      select
+        1
+      from
+        krumnet.game_memberships as members
+      where
+        members.game_id = games.id
+      and
+        members.user_id = $2
+    )
+  or
+    exists (
+      select
+        1
+      from
+        krumnet.game_share_tokens as tokens
+      where
+        tokens.game_id = games.id
+      and
+        tokens.token = $3
+    )
+  );
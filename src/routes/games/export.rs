@@ -0,0 +1,285 @@
+use std::fmt::Write;
+
+use crate::interchange::http::{GameTranscript, TranscriptEntry};
+
+pub const MARKDOWN_CONTENT_TYPE: &str = "text/markdown; charset=utf-8";
+pub const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+#[derive(Debug, PartialEq)]
+pub enum Format {
+  Json,
+  Markdown,
+  Html,
+}
+
+impl Format {
+  // Missing formats default to json, unknown formats are rejected.
+  pub fn parse(value: Option<&String>) -> Option<Self> {
+    match value.map(|v| v.as_str()) {
+      None | Some("json") => Some(Format::Json),
+      Some("markdown") | Some("md") => Some(Format::Markdown),
+      Some("html") => Some(Format::Html),
+      Some(_) => None,
+    }
+  }
+}
+
+fn escape_html(input: &str) -> String {
+  let mut output = String::with_capacity(input.len());
+
+  for c in input.chars() {
+    match c {
+      '&' => output.push_str("&amp;"),
+      '<' => output.push_str("&lt;"),
+      '>' => output.push_str("&gt;"),
+      '"' => output.push_str("&quot;"),
+      '\'' => output.push_str("&#39;"),
+      other => output.push(other),
+    }
+  }
+
+  output
+}
+
+fn entry_text(entry: &TranscriptEntry) -> &str {
+  entry.entry.as_deref().unwrap_or("(hidden)")
+}
+
+fn place_text(place: Option<i32>) -> String {
+  place
+    .map(|p| format!("#{}", p))
+    .unwrap_or_else(|| "-".into())
+}
+
+pub fn markdown(transcript: &GameTranscript) -> String {
+  let mut out = String::new();
+
+  writeln!(out, "# {}\n", transcript.name).ok();
+  writeln!(
+    out,
+    "Played {} - ended {}\n",
+    transcript.created.to_rfc3339(),
+    transcript.ended.to_rfc3339()
+  )
+  .ok();
+
+  for round in &transcript.rounds {
+    writeln!(
+      out,
+      "## Round {}: {}\n",
+      round.position + 1,
+      round.prompt.as_deref().unwrap_or("")
+    )
+    .ok();
+
+    for entry in &round.entries {
+      writeln!(
+        out,
+        "- {} **{}**: {} ({} votes{})",
+        place_text(entry.place),
+        entry.author,
+        entry_text(entry),
+        entry.voters.len(),
+        match entry.voters.is_empty() {
+          true => String::new(),
+          false => format!(": {}", entry.voters.join(", ")),
+        }
+      )
+      .ok();
+    }
+
+    out.push('\n');
+  }
+
+  writeln!(out, "## Final placements\n").ok();
+
+  for placement in &transcript.placements {
+    writeln!(
+      out,
+      "{}. {} ({} votes)",
+      placement.place, placement.user_name, placement.vote_count
+    )
+    .ok();
+  }
+
+  if !transcript.team_placements.is_empty() {
+    writeln!(out, "\n## Team placements\n").ok();
+
+    for placement in &transcript.team_placements {
+      writeln!(
+        out,
+        "{}. {} ({} votes)",
+        placement.place, placement.team_name, placement.vote_count
+      )
+      .ok();
+    }
+  }
+
+  out
+}
+
+// The html document is fully self-contained (no external styles or scripts) so it can be saved
+// and opened on its own.
+pub fn html(transcript: &GameTranscript) -> String {
+  let mut out = String::new();
+  let name = escape_html(&transcript.name);
+
+  writeln!(out, "<!DOCTYPE html>").ok();
+  writeln!(out, "<html><head><meta charset=\"utf-8\">").ok();
+  writeln!(out, "<title>{}</title></head><body>", name).ok();
+  writeln!(out, "<h1>{}</h1>", name).ok();
+  writeln!(
+    out,
+    "<p>Played {} - ended {}</p>",
+    transcript.created.to_rfc3339(),
+    transcript.ended.to_rfc3339()
+  )
+  .ok();
+
+  for round in &transcript.rounds {
+    writeln!(
+      out,
+      "<h2>Round {}: {}</h2>\n<ul>",
+      round.position + 1,
+      escape_html(round.prompt.as_deref().unwrap_or(""))
+    )
+    .ok();
+
+    for entry in &round.entries {
+      let voters = entry
+        .voters
+        .iter()
+        .map(|v| escape_html(v))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+      writeln!(
+        out,
+        "<li>{} <strong>{}</strong>: {} <small>({} votes{})</small></li>",
+        place_text(entry.place),
+        escape_html(&entry.author),
+        escape_html(entry_text(entry)),
+        entry.voters.len(),
+        match voters.is_empty() {
+          true => String::new(),
+          false => format!(": {}", voters),
+        }
+      )
+      .ok();
+    }
+
+    writeln!(out, "</ul>").ok();
+  }
+
+  writeln!(out, "<h2>Final placements</h2>\n<ol>").ok();
+
+  for placement in &transcript.placements {
+    writeln!(
+      out,
+      "<li>{} ({} votes)</li>",
+      escape_html(&placement.user_name),
+      placement.vote_count
+    )
+    .ok();
+  }
+
+  writeln!(out, "</ol>").ok();
+
+  if !transcript.team_placements.is_empty() {
+    writeln!(out, "<h2>Team placements</h2>\n<ol>").ok();
+
+    for placement in &transcript.team_placements {
+      writeln!(
+        out,
+        "<li>{} ({} votes)</li>",
+        escape_html(&placement.team_name),
+        placement.vote_count
+      )
+      .ok();
+    }
+
+    writeln!(out, "</ol>").ok();
+  }
+
+  writeln!(out, "</body></html>").ok();
+  out
+}
+
+#[cfg(test)]
+mod test {
+  use super::{escape_html, html, markdown, Format};
+  use crate::interchange::http::{
+    GameDetailPlacement, GameTranscript, TranscriptEntry, TranscriptRound,
+  };
+  use chrono::{TimeZone, Utc};
+
+  fn transcript() -> GameTranscript {
+    GameTranscript {
+      id: "game-1".into(),
+      name: "<fun> game".into(),
+      created: Utc.timestamp_millis(0),
+      ended: Utc.timestamp_millis(1000),
+      rounds: vec![TranscriptRound {
+        position: 0,
+        prompt: Some("first".into()),
+        entries: vec![
+          TranscriptEntry {
+            author: "alice".into(),
+            entry: Some("<b>hi</b>".into()),
+            place: Some(1),
+            voters: vec!["bob".into()],
+          },
+          TranscriptEntry {
+            author: "bob".into(),
+            entry: None,
+            place: Some(2),
+            voters: vec![],
+          },
+        ],
+      }],
+      placements: vec![GameDetailPlacement {
+        id: "p-1".into(),
+        user_id: "u-1".into(),
+        user_name: "alice".into(),
+        place: 1,
+        vote_count: 1,
+      }],
+      team_placements: vec![],
+    }
+  }
+
+  #[test]
+  fn parse_formats() {
+    assert_eq!(Format::parse(None), Some(Format::Json));
+    assert_eq!(Format::parse(Some(&"md".into())), Some(Format::Markdown));
+    assert_eq!(Format::parse(Some(&"html".into())), Some(Format::Html));
+    assert_eq!(Format::parse(Some(&"pdf".into())), None);
+  }
+
+  #[test]
+  fn escape_markup() {
+    assert_eq!(
+      escape_html("<a href=\"x\">&</a>"),
+      "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+    );
+  }
+
+  #[test]
+  fn markdown_transcript() {
+    let output = markdown(&transcript());
+    assert!(output.starts_with("# <fun> game"));
+    assert!(output.contains("## Round 1: first"));
+    assert!(output.contains("- #1 **alice**: <b>hi</b> (1 votes: bob)"));
+    assert!(output.contains("- #2 **bob**: (hidden) (0 votes)"));
+    assert!(output.contains("1. alice (1 votes)"));
+    assert!(!output.contains("Team placements"));
+  }
+
+  #[test]
+  fn html_transcript_escapes() {
+    let output = html(&transcript());
+    assert!(output.contains("<h1>&lt;fun&gt; game</h1>"));
+    assert!(output.contains("&lt;b&gt;hi&lt;/b&gt;"));
+    assert!(!output.contains("<b>hi</b>"));
+  }
+}
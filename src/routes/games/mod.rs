@@ -1,20 +1,35 @@
 use async_std::io::Read as AsyncRead;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use log::{debug, info, warn};
 use serde::Deserialize;
 use serde_json::from_slice as deserialize;
 use sqlx::query_file;
+use std::collections::HashMap;
 use std::io::Result;
 use std::marker::Unpin;
 
 use crate::{
-  errors,
+  constants::{DEFAULT_PAGE_SIZE, MAX_BATCH_IDS, MAX_PAGE_SIZE},
+  entries, errors,
   http::{query_values, Uri},
-  interchange, read_size_async, Authority, Context, Response,
+  interchange, read_size_async,
+  routes::lobbies,
+  session::random_token,
+  Authority, Context, Response,
 };
 
+mod export;
+
 const NOT_ENOUGH_MEMBERS: &'static str = "errors.games.not_enough_members";
 const INVALID_LOBBY: &'static str = "errors.games.invalid_lobby";
+const INVALID_TEAM_COUNT: &str = "errors.games.invalid_team_count";
+const ENTRY_EXISTS: &str = "errors.entries.already_submitted";
+const TEAM_ENTRY_EXISTS: &str = "errors.entries.team_already_submitted";
+const TOO_MANY_IDS: &str = "errors.games.too_many_ids";
+const INVALID_STATUS: &str = "errors.games.invalid_status";
+const INVALID_CURSOR: &str = "errors.games.invalid_cursor";
+const INVALID_FORMAT: &str = "errors.games.invalid_export_format";
+const NOT_MEMBER: &str = "errors.games.not_member";
 
 #[derive(Debug, Deserialize)]
 struct EntryVotePayload {
@@ -29,6 +44,7 @@ struct RoundAuthority {
   member_id: String,
   user_id: String,
   round_id: String,
+  team_id: Option<String>,
 }
 
 async fn available_entry_for_vote(
@@ -70,8 +86,51 @@ async fn create_vote_for_entry(
   Ok(query_result.into_iter().nth(0).map(|row| row.id))
 }
 
+async fn delete_vote(context: &Context, vote_id: &str, user_id: &String) -> Result<Option<String>> {
+  let mut conn = context.records_connection().await?;
+
+  let query_result = query_file!(
+    "src/routes/games/data-store/delete-round-entry-vote.sql",
+    vote_id,
+    user_id
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?;
+
+  Ok(query_result.into_iter().next().map(|row| row.round_id))
+}
+
+// Route
+// DELETE /round-entry-votes/{id}
+//
+// Votes can be retracted (or changed via another POST) up until the round has been completed.
+pub async fn destroy_entry_vote(context: &Context, vote_id: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+    Authority::User { id, .. } => id,
+  };
+
+  match delete_vote(context, vote_id, uid).await? {
+    Some(round_id) => {
+      info!(
+        "user '{}' retracted vote '{}' in '{}'",
+        uid, vote_id, round_id
+      );
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => {
+      warn!("user '{}' unable to retract vote '{}'", uid, vote_id);
+      Ok(Response::not_found().cors(context.cors()))
+    }
+  }
+}
+
 // Route
 // POST /round-entry-votes
+//
+// Submitting a vote when the user has already voted in the round will replace the previous vote,
+// as long as the round has not been completed.
 pub async fn create_entry_vote<R: AsyncRead + Unpin>(
   context: &Context,
   reader: &mut R,
@@ -87,7 +146,7 @@ pub async fn create_entry_vote<R: AsyncRead + Unpin>(
     Some(auth) => auth,
     None => {
       warn!("unauthorized vote by user '{}'", uid);
-      return Ok(Response::unauthorized().cors(context.cors()));
+      return Ok(Response::forbidden(NOT_MEMBER).cors(context.cors()));
     }
   };
 
@@ -147,6 +206,7 @@ async fn authority_for_round(
     member_id: row.member_id,
     user_id: row.user_id,
     round_id: row.round_id,
+    team_id: row.team_id,
   });
   Ok(possible)
 }
@@ -159,6 +219,9 @@ struct EntryPayload {
 
 // Route
 // POST /round-entries
+//
+// In team games the first entry submitted by any member of a team is used for the whole team;
+// subsequent submissions from teammates are rejected.
 pub async fn create_entry<R: AsyncRead + Unpin>(
   context: &Context,
   reader: &mut R,
@@ -175,32 +238,24 @@ pub async fn create_entry<R: AsyncRead + Unpin>(
     Some(auth) => auth,
     None => {
       warn!("unauthorized attempt to create entry by user '{}'", uid);
-      return Ok(Response::unauthorized().cors(context.cors()));
+      return Ok(Response::forbidden(NOT_MEMBER).cors(context.cors()));
     }
   };
 
-  let mut conn = context.records_connection().await?;
-  let created = query_file!(
-    "src/routes/games/data-store/create-round-entry.sql",
-    authority.round_id,
-    authority.member_id,
-    payload.entry,
-    authority.game_id,
-    authority.lobby_id,
-    authority.user_id
-  )
-  .fetch_all(&mut conn)
-  .await
-  .map_err(errors::humanize_error)?
-  .into_iter()
-  .nth(0)
-  .and_then(|row| Some((row.entry_id, row.entry, row.round_id)));
-
-  debug!("creating round entry for user '{}' - {:?}", uid, created);
+  let entry = match entries::normalize(&context.config().entries, &payload.entry) {
+    Ok(entry) => entry,
+    Err(e) => {
+      warn!("rejected entry from user '{}' - {}", uid, e);
+      return Ok(Response::bad_request(e).cors(context.cors()));
+    }
+  };
 
-  match created {
-    Some((_entry_id, entry, round_id)) => {
-      debug!("successfully created entry - {:?}", entry);
+  match insert_entry(context, &authority, &entry).await? {
+    Ok(round_id) => {
+      debug!(
+        "successfully created entry for user '{}' - {:?}",
+        uid, entry
+      );
 
       context
         .jobs()
@@ -217,16 +272,55 @@ pub async fn create_entry<R: AsyncRead + Unpin>(
           Ok(Response::default().cors(context.cors()))
         })
     }
-    None => {
-      warn!("round entry creation did not return information from inserted entry");
-      return Ok(Response::default().cors(context.cors()));
+    Err(code) => {
+      warn!(
+        "user '{}' already has an entry in round '{}' - {}",
+        uid, authority.round_id, code
+      );
+      Ok(Response::conflict(code).cors(context.cors()))
     }
   }
 }
 
+// Inserts the entry, returning the round id or the code explaining why nothing was inserted. Only
+// the team constraint is handled by the query; a member's own duplicate surfaces as a conflict.
+async fn insert_entry(
+  context: &Context,
+  authority: &RoundAuthority,
+  entry: &String,
+) -> Result<std::result::Result<String, &'static str>> {
+  let mut conn = context.records_connection().await?;
+  let created = query_file!(
+    "src/routes/games/data-store/create-round-entry.sql",
+    authority.round_id,
+    authority.member_id,
+    entry,
+    authority.game_id,
+    authority.lobby_id,
+    authority.user_id,
+    authority.team_id
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error);
+
+  match created {
+    Ok(rows) => Ok(
+      rows
+        .into_iter()
+        .next()
+        .map(|row| row.round_id)
+        .ok_or(TEAM_ENTRY_EXISTS),
+    ),
+    Err(e) if errors::is_conflict(&e) => Ok(Err(ENTRY_EXISTS)),
+    Err(e) => Err(e),
+  }
+}
+
 #[derive(Deserialize)]
 pub struct CreatePayload {
   pub lobby_id: String,
+  pub teams: Option<u8>,
 }
 
 fn log_err<E: std::error::Error>(error: E) -> E {
@@ -234,42 +328,65 @@ fn log_err<E: std::error::Error>(error: E) -> E {
   error
 }
 
-async fn members_for_game(
+// Groups rows that were loaded for several games at once by the game they belong to.
+fn by_game<T>(rows: Vec<(String, T)>) -> HashMap<String, Vec<T>> {
+  let mut grouped: HashMap<String, Vec<T>> = HashMap::new();
+
+  for (game_id, row) in rows {
+    grouped.entry(game_id).or_default().push(row);
+  }
+
+  grouped
+}
+
+async fn members_for_games(
   context: &Context,
-  id: &String,
-) -> Result<Vec<interchange::http::GameMember>> {
+  ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameMember>>> {
   let mut conn = context.records_connection().await?;
 
-  query_file!("src/routes/games/data-store/load-game-members.sql", id)
+  query_file!("src/routes/games/data-store/load-game-members.sql", ids)
     .fetch_all(&mut conn)
     .await
     .map_err(errors::humanize_error)?
     .into_iter()
     .map(|row| {
-      Ok(interchange::http::GameMember {
+      let member = interchange::http::GameMember {
         member_id: row.member_id,
         user_id: row.user_id,
         name: row.user_name,
+        avatar_url: row.user_avatar_url,
+        team_id: row.team_id,
         joined: row
           .created_at
           .ok_or_else(|| errors::e(format!("Unable to parse game member created timestamp")))?,
-      })
+      };
+      Ok((row.game_id, member))
     })
-    .collect()
+    .collect::<Result<Vec<_>>>()
+    .map(by_game)
 }
 
-async fn rounds_for_game(
+pub async fn members_for_game(
   context: &Context,
   id: &String,
-) -> Result<Vec<interchange::http::GameRound>> {
+) -> Result<Vec<interchange::http::GameMember>> {
+  let mut members = members_for_games(context, std::slice::from_ref(id)).await?;
+  Ok(members.remove(id).unwrap_or_default())
+}
+
+async fn rounds_for_games(
+  context: &Context,
+  ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameRound>>> {
   let mut conn = context.records_connection().await?;
-  query_file!("src/routes/games/data-store/load-rounds.sql", id)
+  query_file!("src/routes/games/data-store/load-rounds.sql", ids)
     .fetch_all(&mut conn)
     .await
     .map_err(errors::humanize_error)?
     .into_iter()
     .map(|row| {
-      Ok(interchange::http::GameRound {
+      let round = interchange::http::GameRound {
         id: row.id,
         position: row.pos,
         prompt: row.prompt,
@@ -279,9 +396,19 @@ async fn rounds_for_game(
         started: row.started_at,
         fulfilled: row.fulfilled_at,
         completed: row.completed_at,
-      })
+      };
+      Ok((row.game_id, round))
     })
-    .collect()
+    .collect::<Result<Vec<_>>>()
+    .map(by_game)
+}
+
+pub async fn rounds_for_game(
+  context: &Context,
+  id: &String,
+) -> Result<Vec<interchange::http::GameRound>> {
+  let mut rounds = rounds_for_games(context, std::slice::from_ref(id)).await?;
+  Ok(rounds.remove(id).unwrap_or_default())
 }
 
 struct GameDetails {
@@ -291,85 +418,351 @@ struct GameDetails {
   pub ended_at: Option<DateTime<Utc>>,
 }
 
-async fn placements_for_game(
+async fn placements_for_games(
   context: &Context,
-  game_id: &String,
-) -> Result<Vec<interchange::http::GameDetailPlacement>> {
+  ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameDetailPlacement>>> {
   let mut conn = context.records_connection().await?;
-  query_file!("src/routes/games/data-store/load-placements.sql", game_id)
+  let rows = query_file!("src/routes/games/data-store/load-placements.sql", ids)
     .fetch_all(&mut conn)
     .await
     .map_err(errors::humanize_error)?
     .into_iter()
     .map(|row| {
-      Ok(interchange::http::GameDetailPlacement {
+      let placement = interchange::http::GameDetailPlacement {
         id: row.id,
         user_name: row.user_name,
         user_id: row.user_id,
         place: row.placement,
         vote_count: row.vote_count,
-      })
+      };
+      (row.game_id, placement)
     })
-    .collect()
+    .collect();
+
+  Ok(by_game(rows))
+}
+
+async fn placements_for_game(
+  context: &Context,
+  game_id: &String,
+) -> Result<Vec<interchange::http::GameDetailPlacement>> {
+  let mut placements = placements_for_games(context, std::slice::from_ref(game_id)).await?;
+  Ok(placements.remove(game_id).unwrap_or_default())
 }
 
-async fn find_game(context: &Context, uid: &String, gid: &String) -> Result<Response> {
+async fn teams_for_games(
+  context: &Context,
+  ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameTeam>>> {
+  let mut conn = context.records_connection().await?;
+  let rows = query_file!("src/routes/games/data-store/load-game-teams.sql", ids)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| {
+      let team = interchange::http::GameTeam {
+        id: row.id,
+        name: row.name,
+        position: row.position,
+      };
+      (row.game_id, team)
+    })
+    .collect();
+
+  Ok(by_game(rows))
+}
+
+// Team placements are derived from the round placements of each team's entries once the game has
+// ended; free-for-all games will always have an empty list.
+async fn team_placements_for_games(
+  context: &Context,
+  ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameTeamPlacement>>> {
+  let mut conn = context.records_connection().await?;
+  query_file!("src/routes/games/data-store/load-team-placements.sql", ids)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| {
+      let placement = interchange::http::GameTeamPlacement {
+        team_id: row.team_id,
+        team_name: row.team_name,
+        place: row
+          .placement
+          .ok_or_else(|| errors::e("Unable to load team placement"))?,
+        vote_count: row
+          .vote_count
+          .ok_or_else(|| errors::e("Unable to load team vote count"))?,
+      };
+      Ok((row.game_id, placement))
+    })
+    .collect::<Result<Vec<_>>>()
+    .map(by_game)
+}
+
+async fn team_placements_for_game(
+  context: &Context,
+  game_id: &String,
+) -> Result<Vec<interchange::http::GameTeamPlacement>> {
+  let mut placements = team_placements_for_games(context, std::slice::from_ref(game_id)).await?;
+  Ok(placements.remove(game_id).unwrap_or_default())
+}
+
+// Loads the complete details of every game in `ids` that the user is a member of, with a fixed
+// number of queries regardless of how many ids are requested. Games are returned in the order of
+// `ids`; unknown games and games the user is not a member of are left out.
+async fn load_games(
+  context: &Context,
+  uid: &String,
+  ids: &[String],
+) -> Result<Vec<interchange::http::GameDetails>> {
   let mut conn = context.records_connection().await?;
-  let details = query_file!(
+  let found = query_file!(
     "src/routes/games/data-store/load-game-details.sql",
-    gid,
+    ids,
     uid
   )
   .fetch_all(&mut conn)
   .await
   .map_err(errors::humanize_error)?
   .into_iter()
-  .nth(0)
   .map(|row| {
     Ok(GameDetails {
-      game_id: row.game_id,
       created_at: row.created_at.ok_or_else(|| {
         errors::e(format!(
           "Unable to parse created timestamp for game '{}'",
-          gid
+          row.game_id
         ))
       })?,
+      game_id: row.game_id,
       name: row.game_name,
       ended_at: row.ended_at,
     })
   })
-  .unwrap_or_else(|| Err(errors::e(format!("Unable to find game '{}'", gid))))?;
+  .collect::<Result<Vec<GameDetails>>>()?;
+
+  if found.is_empty() {
+    return Ok(Vec::new());
+  }
 
-  debug!(
-    "found game '{}', created '{:?}'",
-    details.game_id, details.created_at
-  );
+  let found_ids = found
+    .iter()
+    .map(|details| details.game_id.clone())
+    .collect::<Vec<String>>();
+  debug!("found games {:?}, loading details", found_ids);
 
-  let rounds = rounds_for_game(context, &details.game_id)
+  let mut rounds = rounds_for_games(context, &found_ids)
+    .await
+    .map_err(log_err)?;
+  let mut members = members_for_games(context, &found_ids)
+    .await
+    .map_err(log_err)?;
+  let mut placements = placements_for_games(context, &found_ids)
     .await
     .map_err(log_err)?;
-  let members = members_for_game(context, &details.game_id)
+  let mut teams = teams_for_games(context, &found_ids)
     .await
     .map_err(log_err)?;
-  let placements = placements_for_game(context, &details.game_id)
+  let mut team_placements = team_placements_for_games(context, &found_ids)
     .await
     .map_err(log_err)?;
 
-  let result = interchange::http::GameDetails {
-    id: details.game_id.clone(),
-    created: details.created_at.clone(),
-    name: details.name.clone(),
-    ended: details.ended_at.clone(),
-    members,
-    rounds,
-    placements,
+  let mut games = found
+    .into_iter()
+    .map(|details| {
+      let id = details.game_id;
+      let game = interchange::http::GameDetails {
+        members: members.remove(&id).unwrap_or_default(),
+        rounds: rounds.remove(&id).unwrap_or_default(),
+        placements: placements.remove(&id).unwrap_or_default(),
+        teams: teams.remove(&id).unwrap_or_default(),
+        team_placements: team_placements.remove(&id).unwrap_or_default(),
+        created: details.created_at,
+        name: details.name,
+        ended: details.ended_at,
+        id: id.clone(),
+      };
+      (id, game)
+    })
+    .collect::<HashMap<String, interchange::http::GameDetails>>();
+
+  Ok(ids.iter().filter_map(|id| games.remove(id)).collect())
+}
+
+// Responds to a signed in user that is not a member of the game; games that exist are forbidden
+// while anything else is not found.
+async fn denied(context: &Context, gid: &str) -> Result<Response> {
+  let mut conn = context.records_connection().await?;
+  let exists = query_file!("src/routes/games/data-store/game-exists.sql", gid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .is_some();
+
+  match exists {
+    true => Ok(Response::forbidden(NOT_MEMBER).cors(context.cors())),
+    false => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Loads the complete details of a game, returning `None` if the game does not exist or the user is
+// not a member of it.
+async fn load_game(
+  context: &Context,
+  uid: &String,
+  gid: &String,
+) -> Result<Option<interchange::http::GameDetails>> {
+  let mut games = load_games(context, uid, std::slice::from_ref(gid)).await?;
+  Ok(games.pop())
+}
+
+// History cursors are the creation timestamp (in milliseconds) and id of the last game on a page;
+// the id breaks ties between games created in the same millisecond.
+fn history_cursor(game: &interchange::http::GameHistoryGame) -> String {
+  format!("{}.{}", game.created.timestamp_millis(), game.id)
+}
+
+fn parse_history_cursor(cursor: &str) -> Option<(DateTime<Utc>, String)> {
+  let mut parts = cursor.splitn(2, '.');
+  let (millis, id) = (parts.next()?, parts.next()?);
+  let created = Utc
+    .timestamp_millis_opt(millis.parse::<i64>().ok()?)
+    .single()?;
+
+  match id.is_empty() {
+    true => None,
+    false => Some((created, String::from(id))),
+  }
+}
+
+// Loads a page of the games played in a lobby, newest first, starting after the `before` cursor.
+async fn game_history(
+  context: &Context,
+  lobby_id: &String,
+  ended: Option<bool>,
+  before: Option<(DateTime<Utc>, String)>,
+  limit: i64,
+) -> Result<Vec<interchange::http::GameHistoryGame>> {
+  let mut conn = context.records_connection().await?;
+  let (before_created, before_id) = match before {
+    Some((created, id)) => (Some(created), Some(id)),
+    None => (None, None),
   };
 
-  Response::ok_json(&result).map(|r| r.cors(context.cors()))
+  query_file!(
+    "src/routes/games/data-store/load-game-history.sql",
+    lobby_id,
+    ended,
+    before_created,
+    before_id,
+    limit
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .map(|row| {
+    Ok(interchange::http::GameHistoryGame {
+      id: row.game_id,
+      name: row.game_name,
+      created: row
+        .created_at
+        .ok_or_else(|| errors::e("Unable to parse game created timestamp"))?,
+      ended: row.ended_at,
+      member_count: row.member_count.unwrap_or_default(),
+    })
+  })
+  .collect()
+}
+
+// Route
+// GET /games?lobby_id={id}&status={ended|active}&before={cursor}&limit={limit}
+async fn find_history(
+  context: &Context,
+  uid: &String,
+  lobby_id: &String,
+  uri: &Uri,
+) -> Result<Response> {
+  let ended = match query_values(uri, "status").into_iter().next().as_deref() {
+    None => None,
+    Some("ended") => Some(true),
+    Some("active") => Some(false),
+    Some(other) => {
+      warn!("invalid game status filter '{}'", other);
+      return Ok(Response::bad_request(INVALID_STATUS).cors(context.cors()));
+    }
+  };
+
+  let before = match query_values(uri, "before").into_iter().next() {
+    None => None,
+    Some(cursor) => match parse_history_cursor(&cursor) {
+      Some(before) => Some(before),
+      None => {
+        warn!("invalid game history cursor '{}'", cursor);
+        return Ok(Response::bad_request(INVALID_CURSOR).cors(context.cors()));
+      }
+    },
+  };
+
+  let limit = query_values(uri, "limit")
+    .into_iter()
+    .next()
+    .and_then(|value| value.parse::<i64>().ok())
+    .filter(|limit| *limit > 0)
+    .map(|limit| limit.min(MAX_PAGE_SIZE))
+    .unwrap_or(DEFAULT_PAGE_SIZE);
+
+  let mut conn = context.records_connection().await?;
+  let membership = query_file!(
+    "src/routes/lobbies/data-store/load-lobby-detail.sql",
+    lobby_id,
+    uid
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next();
+
+  if membership.is_none() {
+    warn!("user '{}' not a member of lobby '{}'", uid, lobby_id);
+    return lobbies::denied(context, lobby_id).await;
+  }
+
+  let games = game_history(context, lobby_id, ended, before, limit).await?;
+  let next = match games.len() as i64 == limit {
+    true => games.last().map(history_cursor),
+    false => None,
+  };
+
+  Response::ok_json(interchange::http::GameHistory { games, next }).map(|r| r.cors(context.cors()))
+}
+
+// Route
+// GET /games/{id}
+pub async fn find_one(context: &Context, gid: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  match load_game(context, uid, &String::from(gid)).await? {
+    Some(details) => Response::ok_json(&details).map(|r| r.cors(context.cors())),
+    None => denied(context, gid).await,
+  }
 }
 
 // Route
 // GET /games
+//
+// Responds with a list of every game in `ids[]` the user is able to see. A single id responds with
+// the game itself, as this route did before it accepted several. Without ids, a `lobby_id` lists
+// the history of games played in the lobby.
 pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
   let uid = match context.authority() {
     Authority::User { id, .. } => id,
@@ -378,14 +771,211 @@ pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
 
   let ids = query_values(uri, "ids[]");
 
-  if ids.len() != 1 {
-    debug!("find all games not implemented yet");
+  if ids.is_empty() {
+    if let Some(lobby_id) = query_values(uri, "lobby_id").into_iter().next() {
+      debug!("loading game history for user '{}'", uid);
+      return find_history(context, uid, &lobby_id, uri).await;
+    }
+
+    debug!("no game ids provided");
     return Ok(Response::not_found().cors(context.cors()));
   }
 
-  let gid = ids.iter().nth(0).ok_or(errors::e("Invalid id"))?;
-  debug!("attempting to find game from single id - {:?}", gid);
-  find_game(context, uid, gid).await
+  if ids.len() > MAX_BATCH_IDS {
+    warn!("user '{}' requested {} games", uid, ids.len());
+    return Ok(Response::bad_request(TOO_MANY_IDS).cors(context.cors()));
+  }
+
+  if let [gid] = ids.as_slice() {
+    return find_one(context, gid).await;
+  }
+
+  let games = load_games(context, uid, &ids).await?;
+  Response::ok_json(interchange::http::GameList { games }).map(|r| r.cors(context.cors()))
+}
+
+// Loads the transcript of an ended game that is visible to either a member of the game or anyone
+// holding the game's share token.
+async fn load_transcript(
+  context: &Context,
+  gid: &str,
+  uid: Option<&String>,
+  token: Option<&String>,
+) -> Result<Option<interchange::http::GameTranscript>> {
+  let mut conn = context.records_connection().await?;
+  let row = match query_file!(
+    "src/routes/games/data-store/load-transcript-game.sql",
+    gid,
+    uid,
+    token
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  {
+    Some(row) => row,
+    None => return Ok(None),
+  };
+
+  let created = row
+    .created_at
+    .ok_or_else(|| errors::e("Unable to parse game created timestamp"))?;
+  let ended = row
+    .ended_at
+    .ok_or_else(|| errors::e("Unable to parse game ended timestamp"))?;
+
+  let mut rounds: Vec<interchange::http::TranscriptRound> = Vec::new();
+
+  for entry in query_file!(
+    "src/routes/games/data-store/load-transcript-entries.sql",
+    row.game_id
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  {
+    let transcript_entry = interchange::http::TranscriptEntry {
+      author: entry.author,
+      entry: entry.entry,
+      place: entry.place,
+      voters: entry.voters.unwrap_or_default(),
+    };
+
+    match rounds.last_mut() {
+      Some(round) if round.position == entry.position => round.entries.push(transcript_entry),
+      _ => rounds.push(interchange::http::TranscriptRound {
+        position: entry.position,
+        prompt: entry.prompt,
+        entries: vec![transcript_entry],
+      }),
+    }
+  }
+
+  let placements = placements_for_game(context, &row.game_id)
+    .await
+    .map_err(log_err)?;
+  let team_placements = team_placements_for_game(context, &row.game_id)
+    .await
+    .map_err(log_err)?;
+
+  Ok(Some(interchange::http::GameTranscript {
+    id: row.game_id,
+    name: row.game_name,
+    created,
+    ended,
+    rounds,
+    placements,
+    team_placements,
+  }))
+}
+
+// Route
+// GET /games/{id}/export?format={json|markdown|html}&token={token}
+//
+// Members of an ended game can always export it; a `token` allows anyone to view the transcript
+// read-only once a member has shared it.
+pub async fn export(context: &Context, gid: &str, uri: &Uri) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => Some(id),
+    Authority::None => None,
+  };
+  let token = query_values(uri, "token").into_iter().next();
+
+  if uid.is_none() && token.is_none() {
+    return Ok(Response::unauthorized().cors(context.cors()));
+  }
+
+  let format = match export::Format::parse(query_values(uri, "format").first()) {
+    Some(format) => format,
+    None => return Ok(Response::bad_request(INVALID_FORMAT).cors(context.cors())),
+  };
+
+  let transcript = match load_transcript(context, gid, uid, token.as_ref()).await? {
+    Some(transcript) => transcript,
+    None => {
+      warn!("unable to export game '{}' ({:?})", gid, uid);
+      return Ok(Response::not_found().cors(context.cors()));
+    }
+  };
+
+  let response = match format {
+    export::Format::Json => Response::ok_json(&transcript)?,
+    export::Format::Markdown => {
+      Response::ok_content(export::MARKDOWN_CONTENT_TYPE, export::markdown(&transcript))
+    }
+    export::Format::Html => {
+      Response::ok_content(export::HTML_CONTENT_TYPE, export::html(&transcript))
+    }
+  };
+
+  Ok(response.cors(context.cors()))
+}
+
+// Route
+// POST /games/{id}/share-token
+//
+// Games have at most one share token; subsequent requests respond with the existing token.
+pub async fn create_share_token(context: &Context, gid: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let token = query_file!(
+    "src/routes/games/data-store/create-share-token.sql",
+    gid,
+    uid,
+    random_token()
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  .map(|row| row.token);
+
+  match token {
+    Some(token) => {
+      info!("user '{}' shared game '{}'", uid, gid);
+      Response::ok_json(interchange::http::GameShareToken { token }).map(|r| r.cors(context.cors()))
+    }
+    None => {
+      warn!("user '{}' unable to share game '{}'", uid, gid);
+      Ok(Response::not_found().cors(context.cors()))
+    }
+  }
+}
+
+// Route
+// DELETE /games/{id}/share-token
+pub async fn destroy_share_token(context: &Context, gid: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+  let deleted = query_file!(
+    "src/routes/games/data-store/delete-share-token.sql",
+    gid,
+    uid
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next();
+
+  match deleted {
+    Some(_) => {
+      info!("user '{}' revoked share token for game '{}'", uid, gid);
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
 }
 
 // Route
@@ -402,7 +992,7 @@ where
   debug!("creating new game for user - {}", uid);
 
   let contents = read_size_async(reader, context.pending()).await?;
-  let CreatePayload { lobby_id } = deserialize::<CreatePayload>(&contents)?;
+  let CreatePayload { lobby_id, teams } = deserialize::<CreatePayload>(&contents)?;
 
   let mut conn = context.records_connection().await?;
   let maybe_lobby = query_file!(
@@ -438,11 +1028,21 @@ where
     return Ok(Response::bad_request(NOT_ENOUGH_MEMBERS).cors(context.cors()));
   }
 
+  // Every team needs at least one member, and there must be more than one team for members to
+  // have something to vote for.
+  if let Some(count) = teams {
+    if count < 2 || i64::from(count) > member_count {
+      warn!("invalid team count {} for '{}'", count, lobby_id);
+      return Ok(Response::bad_request(INVALID_TEAM_COUNT).cors(context.cors()));
+    }
+  }
+
   info!("queuing new game job for lobby '{}'", lobby_id);
 
   let details = interchange::jobs::CreateGame {
     creator: uid.clone(),
     lobby_id: lobby_id.clone(),
+    teams,
     result: None,
   };
 
@@ -460,32 +1060,116 @@ where
 
 #[cfg(test)]
 mod test {
-  use super::authority_for_round;
+  use super::{
+    authority_for_round, available_entry_for_vote, create_vote_for_entry, delete_vote, find,
+    find_history, find_one, game_history, history_cursor, insert_entry, load_game, load_games,
+    load_transcript, parse_history_cursor, ENTRY_EXISTS, TEAM_ENTRY_EXISTS,
+  };
   use crate::{
     bg,
     context::{test_helpers as context_helpers, Context},
-    test_helpers::cleanup_lobby,
+    http::Uri,
+    test_helpers::{cleanup_lobby, game_for_users},
   };
   use async_std::task::block_on;
   use sqlx::query;
 
-  struct GameContext {
-    lobby_id: String,
-    game_id: String,
+  async fn make_entry(context: &Context, round_id: &String, user_id: &String) -> String {
+    let mut conn = context
+      .records_connection()
+      .await
+      .expect("unable to connect");
+
+    query!(
+      "
+      insert into krumnet.game_round_entries (user_id, member_id, round_id, game_id, lobby_id, team_id, entry)
+      select members.user_id, members.id, rounds.id, rounds.game_id, rounds.lobby_id, members.team_id, 'entry'
+      from krumnet.game_rounds as rounds
+      inner join krumnet.game_memberships as members on members.game_id = rounds.game_id
+      where rounds.id = $1 and members.user_id = $2
+      returning id
+      ",
+      round_id,
+      user_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to insert")
+    .into_iter()
+    .next()
+    .map(|row| row.id)
+    .expect("unable to get id")
   }
 
-  async fn game_for_user(context: &Context, user_id: &String) -> GameContext {
-    let job_id = format!("job-for-user-{}", user_id);
+  // Returns the user ids of the teammates and opponents of the user, in that order.
+  async fn teams_for_user(
+    context: &Context,
+    game_id: &String,
+    user_id: &String,
+  ) -> (Vec<String>, Vec<String>) {
+    let mut conn = context
+      .records_connection()
+      .await
+      .expect("unable to connect");
+
+    query!(
+      "
+      select others.user_id, others.team_id = members.team_id as teammate
+      from krumnet.game_memberships as members
+      inner join krumnet.game_memberships as others on others.game_id = members.game_id
+      where members.game_id = $1 and members.user_id = $2 and others.user_id <> $2
+      ",
+      game_id,
+      user_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to query")
+    .into_iter()
+    .fold((vec![], vec![]), |(mut teammates, mut opponents), row| {
+      match row.teammate {
+        Some(true) => teammates.push(row.user_id),
+        _ => opponents.push(row.user_id),
+      }
+      (teammates, opponents)
+    })
+  }
 
-    let lobby_id = bg::handlers::lobbies::make_lobby(context.records(), &job_id, user_id)
+  async fn votes_for_round(context: &Context, round_id: &String) -> Vec<(String, String)> {
+    let mut conn = context
+      .records_connection()
       .await
-      .expect("unable to create");
+      .expect("unable to connect");
+
+    query!(
+      "select id, entry_id from krumnet.game_round_entry_votes where round_id = $1",
+      round_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to query")
+    .into_iter()
+    .map(|row| (row.id, row.entry_id))
+    .collect()
+  }
 
-    let game_id = bg::handlers::lobbies::make_game(context.records(), &job_id, user_id, &lobby_id)
+  async fn complete_round(context: &Context, round_id: &String) {
+    let mut conn = context
+      .records_connection()
       .await
-      .expect("unable to crete");
+      .expect("unable to connect");
 
-    GameContext { lobby_id, game_id }
+    query!(
+      "
+      update krumnet.game_rounds
+      set fulfilled_at = now() + interval '1 second', completed_at = now() + interval '2 seconds'
+      where id = $1
+      ",
+      round_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to complete");
   }
 
   async fn get_round_id(context: &Context, game_id: &String, position: i32) -> String {
@@ -529,7 +1213,7 @@ mod test {
       let other =
         context_helpers::make_user("routes.games.no_authority_for_non_member.other").await;
 
-      let game_context = game_for_user(&ctx, &other).await;
+      let game_context = game_for_users(ctx.records(), &other, &[], None).await;
       let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
 
       let authority = authority_for_round(&ctx, &round_id, &user_id).await;
@@ -548,7 +1232,7 @@ mod test {
     block_on(async {
       let (ctx, user_id) =
         context_helpers::with_user_by_name("routes.games.authority_for_member").await;
-      let game_context = game_for_user(&ctx, &user_id).await;
+      let game_context = game_for_users(ctx.records(), &user_id, &[], None).await;
       let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
       let authority = authority_for_round(&ctx, &round_id, &user_id).await;
       assert_eq!(authority.is_ok(), true);
@@ -557,4 +1241,413 @@ mod test {
       context_helpers::cleanup(&ctx).await;
     });
   }
+
+  #[test]
+  fn change_vote_before_completion() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.change_vote_before_completion").await;
+      let first = context_helpers::make_user("routes.games.change_vote_before_completion.1").await;
+      let second = context_helpers::make_user("routes.games.change_vote_before_completion.2").await;
+
+      let game_context = game_for_users(ctx.records(), &user_id, &[&first, &second], None).await;
+      let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
+      let first_entry = make_entry(&ctx, &round_id, &first).await;
+      let second_entry = make_entry(&ctx, &round_id, &second).await;
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      let original = create_vote_for_entry(&ctx, &authority, &first_entry)
+        .await
+        .expect("unable to vote");
+      let changed = create_vote_for_entry(&ctx, &authority, &second_entry)
+        .await
+        .expect("unable to vote");
+
+      assert!(original.is_some());
+      assert_eq!(original, changed);
+      assert_eq!(
+        votes_for_round(&ctx, &round_id).await,
+        vec![(changed.unwrap(), second_entry)]
+      );
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&first).await;
+      context_helpers::cleanup_user(&second).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn retract_vote_before_completion() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.retract_vote_before_completion").await;
+      let other = context_helpers::make_user("routes.games.retract_vote_before_completion.1").await;
+
+      let game_context = game_for_users(ctx.records(), &user_id, &[&other], None).await;
+      let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
+      let entry_id = make_entry(&ctx, &round_id, &other).await;
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      let vote_id = create_vote_for_entry(&ctx, &authority, &entry_id)
+        .await
+        .expect("unable to vote")
+        .expect("missing vote");
+
+      let retracted = delete_vote(&ctx, &vote_id, &other).await;
+      assert_eq!(retracted.unwrap(), None);
+
+      let retracted = delete_vote(&ctx, &vote_id, &user_id).await;
+      assert_eq!(retracted.unwrap(), Some(round_id.clone()));
+      assert_eq!(votes_for_round(&ctx, &round_id).await.len(), 0);
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&other).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn votes_final_after_completion() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.votes_final_after_completion").await;
+      let first = context_helpers::make_user("routes.games.votes_final_after_completion.1").await;
+      let second = context_helpers::make_user("routes.games.votes_final_after_completion.2").await;
+
+      let game_context = game_for_users(ctx.records(), &user_id, &[&first, &second], None).await;
+      let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
+      let first_entry = make_entry(&ctx, &round_id, &first).await;
+      let second_entry = make_entry(&ctx, &round_id, &second).await;
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      let vote_id = create_vote_for_entry(&ctx, &authority, &first_entry)
+        .await
+        .expect("unable to vote")
+        .expect("missing vote");
+
+      complete_round(&ctx, &round_id).await;
+
+      let changed = create_vote_for_entry(&ctx, &authority, &second_entry).await;
+      assert_eq!(changed.unwrap(), None);
+
+      let retracted = delete_vote(&ctx, &vote_id, &user_id).await;
+      assert_eq!(retracted.unwrap(), None);
+
+      assert_eq!(
+        votes_for_round(&ctx, &round_id).await,
+        vec![(vote_id, first_entry)]
+      );
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&first).await;
+      context_helpers::cleanup_user(&second).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn no_vote_for_teammate() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.no_vote_for_teammate").await;
+      let first = context_helpers::make_user("routes.games.no_vote_for_teammate.1").await;
+      let second = context_helpers::make_user("routes.games.no_vote_for_teammate.2").await;
+      let third = context_helpers::make_user("routes.games.no_vote_for_teammate.3").await;
+
+      let others = [&first, &second, &third];
+      let game_context = game_for_users(ctx.records(), &user_id, &others, Some(2)).await;
+
+      let (teammates, opponents) = teams_for_user(&ctx, &game_context.game_id, &user_id).await;
+      assert_eq!((teammates.len(), opponents.len()), (1, 2));
+
+      let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
+      let teammate_entry = make_entry(&ctx, &round_id, &teammates[0]).await;
+      let opponent_entry = make_entry(&ctx, &round_id, &opponents[0]).await;
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      assert!(authority.team_id.is_some());
+
+      let available = available_entry_for_vote(&ctx, &authority, &teammate_entry).await;
+      assert_eq!(available.unwrap(), None);
+
+      let available = available_entry_for_vote(&ctx, &authority, &opponent_entry).await;
+      assert_eq!(available.unwrap(), Some(opponent_entry));
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&first).await;
+      context_helpers::cleanup_user(&second).await;
+      context_helpers::cleanup_user(&third).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn duplicate_entries_rejected() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.duplicate_entries_rejected").await;
+      let first = context_helpers::make_user("routes.games.duplicate_entries_rejected.1").await;
+      let second = context_helpers::make_user("routes.games.duplicate_entries_rejected.2").await;
+      let third = context_helpers::make_user("routes.games.duplicate_entries_rejected.3").await;
+
+      let game_context =
+        game_for_users(ctx.records(), &user_id, &[&first, &second, &third], None).await;
+      let round_id = get_round_id(&ctx, &game_context.game_id, 0).await;
+      let entry = String::from("entry");
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      let created = insert_entry(&ctx, &authority, &entry).await;
+      assert_eq!(created.unwrap(), Ok(round_id.clone()));
+      let created = insert_entry(&ctx, &authority, &entry).await;
+      assert_eq!(created.unwrap(), Err(ENTRY_EXISTS));
+
+      bg::handlers::lobbies::assign_teams(
+        ctx.records(),
+        &game_context.game_id,
+        &game_context.lobby_id,
+        2,
+      )
+      .await
+      .expect("unable to assign teams");
+
+      let (teammates, _) = teams_for_user(&ctx, &game_context.game_id, &user_id).await;
+      let round_id = get_round_id(&ctx, &game_context.game_id, 1).await;
+      make_entry(&ctx, &round_id, &teammates[0]).await;
+
+      let authority = authority_for_round(&ctx, &round_id, &user_id)
+        .await
+        .expect("unable to load authority")
+        .expect("missing authority");
+
+      let created = insert_entry(&ctx, &authority, &entry).await;
+      assert_eq!(created.unwrap(), Err(TEAM_ENTRY_EXISTS));
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&first).await;
+      context_helpers::cleanup_user(&second).await;
+      context_helpers::cleanup_user(&third).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn load_game_for_member_only() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.load_game_for_member_only").await;
+      let (other_ctx, other) =
+        context_helpers::with_user_by_name("routes.games.load_game_for_member_only.1").await;
+      let game_context = game_for_users(ctx.records(), &user_id, &[], None).await;
+
+      let details = load_game(&ctx, &user_id, &game_context.game_id).await;
+      assert_eq!(
+        details.unwrap().map(|game| game.id),
+        Some(game_context.game_id.clone())
+      );
+
+      let details = load_game(&ctx, &other, &game_context.game_id).await;
+      assert!(details.unwrap().is_none());
+
+      // Signed in users that aren't members are forbidden, while unknown games are not found.
+      let response = format!(
+        "{}",
+        find_one(&other_ctx, &game_context.game_id).await.unwrap()
+      );
+      assert!(response.starts_with("HTTP/1.1 403"));
+      assert!(response.contains("errors.games.not_member"));
+      let response = find_one(&other_ctx, "missing").await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      let uri = "/games".parse::<Uri>().unwrap();
+      let response = find_history(&other_ctx, &other, &game_context.lobby_id, &uri)
+        .await
+        .unwrap();
+      let response = format!("{}", response);
+      assert!(response.starts_with("HTTP/1.1 403"));
+      assert!(response.contains("errors.lobbies.not_member"));
+      let missing = String::from("missing");
+      let response = find_history(&other_ctx, &other, &missing, &uri)
+        .await
+        .unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      let second = game_for_users(ctx.records(), &user_id, &[], None).await;
+      let ids = vec![
+        second.game_id.clone(),
+        String::from("missing"),
+        game_context.game_id.clone(),
+      ];
+      let games = load_games(&ctx, &user_id, &ids).await.unwrap();
+      assert_eq!(
+        games
+          .iter()
+          .map(|game| game.id.clone())
+          .collect::<Vec<String>>(),
+        vec![second.game_id.clone(), game_context.game_id.clone()]
+      );
+      assert!(games.iter().all(|game| game.members.len() == 1));
+
+      // A single id keeps the shape this route had before it accepted several.
+      let uri = format!("/games?ids[]={}", second.game_id);
+      let response = find(&ctx, &uri.parse::<Uri>().unwrap()).await.unwrap();
+      let response = format!("{}", response);
+      assert!(response.contains(&format!("\"id\":\"{}\"", second.game_id)));
+      assert!(!response.contains("\"games\""));
+      let uri = format!("/games?ids[]={}&ids[]=missing", second.game_id);
+      let response = find(&ctx, &uri.parse::<Uri>().unwrap()).await.unwrap();
+      assert!(format!("{}", response).contains("\"games\""));
+      assert!(load_games(&ctx, &other, &ids).await.unwrap().is_empty());
+
+      cleanup_lobby(&ctx, &second.lobby_id).await;
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&other).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn game_history_pages() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.game_history_pages").await;
+      let game_context = game_for_users(ctx.records(), &user_id, &[], None).await;
+      let newest = bg::handlers::lobbies::make_game(
+        ctx.records(),
+        &String::from("job-for-history"),
+        &user_id,
+        &game_context.lobby_id,
+      )
+      .await
+      .expect("unable to create");
+
+      let first = game_history(&ctx, &game_context.lobby_id, None, None, 1)
+        .await
+        .expect("unable to load");
+      assert_eq!(
+        first.iter().map(|g| g.id.clone()).collect::<Vec<String>>(),
+        vec![newest.clone()]
+      );
+
+      let cursor = first.last().map(|game| (game.created, game.id.clone()));
+      let second = game_history(&ctx, &game_context.lobby_id, None, cursor, 1)
+        .await
+        .expect("unable to load");
+      assert_eq!(
+        second.iter().map(|g| g.id.clone()).collect::<Vec<String>>(),
+        vec![game_context.game_id.clone()]
+      );
+
+      // Games created in the same millisecond are still paged through one at a time.
+      let mut conn = ctx.records_connection().await.expect("unable to connect");
+      query!(
+        "update krumnet.games set created_at = (select created_at from krumnet.games where id = $1) where id = $2",
+        game_context.game_id,
+        newest
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to update");
+
+      let mut seen = Vec::new();
+      let mut cursor = None;
+
+      for _ in 0..2 {
+        let page = game_history(&ctx, &game_context.lobby_id, None, cursor, 1)
+          .await
+          .expect("unable to load");
+        assert_eq!(page.len(), 1);
+        cursor = parse_history_cursor(&history_cursor(&page[0]));
+        seen.push(page[0].id.clone());
+      }
+
+      seen.sort();
+      let mut expected = vec![newest, game_context.game_id.clone()];
+      expected.sort();
+      assert_eq!(seen, expected);
+
+      assert!(parse_history_cursor("9223372036854775807.abc").is_none());
+      assert!(parse_history_cursor("1600000000000").is_none());
+
+      let ended = game_history(&ctx, &game_context.lobby_id, Some(true), None, 10)
+        .await
+        .expect("unable to load");
+      assert!(ended.is_empty());
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn transcript_for_member_or_token() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.games.transcript_for_member_or_token").await;
+      let other = context_helpers::make_user("routes.games.transcript_for_member_or_token.1").await;
+      let game_context = game_for_users(ctx.records(), &user_id, &[], None).await;
+      let gid = &game_context.game_id;
+      let token = String::from("transcript-for-member-or-token");
+
+      let transcript = load_transcript(&ctx, gid, Some(&user_id), None).await;
+      assert!(transcript.unwrap().is_none());
+
+      let mut conn = ctx.records_connection().await.expect("unable to connect");
+      query!(
+        "update krumnet.games set ended_at = now() where id = $1",
+        gid
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to end");
+      query!(
+        "insert into krumnet.game_share_tokens (game_id, created_by, token) values ($1, $2, $3)",
+        gid,
+        user_id,
+        token
+      )
+      .execute(&mut conn)
+      .await
+      .expect("unable to share");
+
+      let transcript = load_transcript(&ctx, gid, Some(&user_id), None).await;
+      assert_eq!(
+        transcript.unwrap().map(|t| t.id),
+        Some(game_context.game_id.clone())
+      );
+
+      let transcript = load_transcript(&ctx, gid, Some(&other), None).await;
+      assert!(transcript.unwrap().is_none());
+
+      let transcript = load_transcript(&ctx, gid, None, Some(&token)).await;
+      assert!(transcript.unwrap().is_some());
+
+      let transcript = load_transcript(&ctx, gid, None, Some(&String::from("nope"))).await;
+      assert!(transcript.unwrap().is_none());
+
+      cleanup_lobby(&ctx, &game_context.lobby_id).await;
+      context_helpers::cleanup_user(&other).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
 }
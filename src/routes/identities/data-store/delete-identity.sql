@@ -0,0 +1,8 @@
+delete from
+  krumnet.identities as identities
+where
+  identities.id = $1
+and
+  identities.user_id = $2
+returning
+  identities.id;
@@ -0,0 +1,12 @@
+select
+  identities.id         as identity_id,
+  identities.provider   as provider,
+  identities.email      as email,
+  identities.name       as name,
+  identities.created_at as created_at
+from
+  krumnet.identities as identities
+where
+  identities.user_id = $1
+order by
+  identities.created_at asc;
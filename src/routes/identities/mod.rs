@@ -0,0 +1,129 @@
+use log::{info, warn};
+use sqlx::query_file;
+use std::io::Result;
+
+use crate::{errors, interchange, Authority, Context, Response};
+
+// Users must always be able to log back in, so the last identity on an account can't be removed.
+const LAST_IDENTITY: &str = "errors.identities.last_identity";
+
+pub async fn identities_for_user(
+  context: &Context,
+  uid: &str,
+) -> Result<Vec<interchange::http::LinkedIdentity>> {
+  let mut conn = context.records_connection().await?;
+
+  let identities = query_file!("src/routes/identities/data-store/load-identities.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| interchange::http::LinkedIdentity {
+      id: row.identity_id,
+      provider: row.provider,
+      email: row.email,
+      name: row.name,
+      created: row.created_at,
+    })
+    .collect();
+
+  Ok(identities)
+}
+
+// Route
+// GET /identities
+//
+// Lists the provider identities that can be used to log in as the current user.
+pub async fn find(context: &Context) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let identities = identities_for_user(context, uid).await?;
+
+  Response::ok_json(interchange::http::LinkedIdentityList { identities })
+    .map(|r| r.cors(context.cors()))
+}
+
+// Route
+// DELETE /identities/{id}
+pub async fn destroy(context: &Context, identity_id: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let identities = identities_for_user(context, uid).await?;
+
+  if !identities.iter().any(|identity| identity.id == identity_id) {
+    warn!("user '{}' unable to unlink identity '{}'", uid, identity_id);
+    return Ok(Response::not_found().cors(context.cors()));
+  }
+
+  if identities.len() == 1 {
+    return Ok(Response::conflict(LAST_IDENTITY).cors(context.cors()));
+  }
+
+  let mut conn = context.records_connection().await?;
+  query_file!(
+    "src/routes/identities/data-store/delete-identity.sql",
+    identity_id,
+    uid
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?;
+
+  info!("user '{}' unlinked identity '{}'", uid, identity_id);
+  Ok(Response::default().cors(context.cors()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::{destroy, find, identities_for_user};
+  use crate::context::test_helpers as context_helpers;
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  #[test]
+  fn unlink_keeps_last_identity() {
+    block_on(async {
+      let (context, user_id) =
+        context_helpers::with_user_by_name("identities.unlink_keeps_last_identity").await;
+      let mut conn = context.records_connection().await.unwrap();
+
+      for provider in &["google", "github"] {
+        query!(
+          "insert into krumnet.identities (user_id, provider, subject) values ($1, $2, $1)",
+          user_id,
+          provider.to_string()
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+      }
+
+      let response = format!("{}", find(&context).await.unwrap());
+      assert!(response.contains("\"provider\":\"github\""));
+
+      let identities = identities_for_user(&context, &user_id).await.unwrap();
+      assert_eq!(identities.len(), 2);
+
+      let response = destroy(&context, "not-an-identity").await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      let response = destroy(&context, &identities[0].id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 200"));
+
+      let response = destroy(&context, &identities[1].id).await.unwrap();
+      assert!(format!("{}", response).contains("errors.identities.last_identity"));
+      assert_eq!(
+        identities_for_user(&context, &user_id).await.unwrap().len(),
+        1
+      );
+
+      context_helpers::cleanup(&context).await;
+    });
+  }
+}
@@ -9,7 +9,7 @@ use std::io::Result;
 
 fn with_access(auth: &Authority, job: QueuedJob) -> Option<QueuedJob> {
   match auth {
-    Authority::User { id, token: _ } => job.user().and_then(|job_user| {
+    Authority::User { id, .. } => job.user().and_then(|job_user| {
       if &job_user == id {
         debug!("job '{}' owned by '{}', we good", job.id, job_user);
         return Some(job);
@@ -24,7 +24,7 @@ fn with_access(auth: &Authority, job: QueuedJob) -> Option<QueuedJob> {
 
 pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
   let uid = match context.authority() {
-    Authority::User { id, token: _ } => id,
+    Authority::User { id, .. } => id,
     Authority::None => return Ok(Response::not_found().cors(context.cors())),
   };
 
@@ -96,10 +96,7 @@ mod test {
         result: None,
       }),
     };
-    let auth = Authority::User {
-      id: uid.clone(),
-      token: String::from(""),
-    };
+    let auth = Authority::user(uid.clone(), String::new());
     assert!(with_access(&auth, job).is_none());
   }
 
@@ -113,10 +110,7 @@ mod test {
         result: None,
       }),
     };
-    let auth = Authority::User {
-      id: uid.clone(),
-      token: String::from(""),
-    };
+    let auth = Authority::user(uid.clone(), String::new());
     assert!(with_access(&auth, job).is_some());
   }
 }
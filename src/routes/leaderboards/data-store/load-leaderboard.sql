@@ -0,0 +1,50 @@
+with standings as (
+  select
+    placements.user_id                                       as user_id,
+    count(placements.id)                                     as games_played,
+    count(placements.id) filter (where placements.place = 1) as wins,
+    sum(placements.vote_count)                               as total_votes
+  from
+    krumnet.game_member_placement_results as placements
+  where
+    (cast($1 as varchar) is null or placements.lobby_id = $1)
+  group by
+    placements.user_id
+), ratings as (
+  select
+    changes.user_id                                        as user_id,
+    $5 + sum(changes.rating_after - changes.rating_before) as rating
+  from
+    krumnet.game_rating_changes as changes
+  where
+    (cast($1 as varchar) is null or changes.lobby_id = $1)
+  group by
+    changes.user_id
+) select
+  users.id                                  as user_id,
+  users.name                                as user_name,
+  row_number() over (
+    order by
+      case when $4 then ratings.rating end desc nulls last,
+      standings.wins desc,
+      standings.total_votes desc,
+      users.id asc
+  )                                         as rank,
+  standings.games_played                    as games_played,
+  standings.wins                            as wins,
+  cast(standings.total_votes as bigint)     as total_votes,
+  coalesce(ratings.rating, $5)              as rating
+from
+  standings
+inner join
+  krumnet.users as users
+on
+  users.id = standings.user_id
+left join
+  ratings
+on
+  ratings.user_id = standings.user_id
+order by
+  rank asc
+limit $2
+offset $3;
@@ -0,0 +1,176 @@
+use log::{debug, warn};
+use sqlx::query_file;
+use std::io::Result;
+
+use crate::{
+  errors,
+  http::{page, query_values, Page, Uri},
+  interchange, ratings,
+  routes::lobbies,
+  Authority, Context, Response,
+};
+
+const SORT_WINS: &str = "wins";
+const SORT_RATING: &str = "rating";
+
+async fn load_leaderboard(
+  context: &Context,
+  lobby_id: Option<&String>,
+  page: &Page,
+  by_rating: bool,
+) -> Result<Vec<interchange::http::LeaderboardEntry>> {
+  let mut conn = context.records_connection().await?;
+
+  query_file!(
+    "src/routes/leaderboards/data-store/load-leaderboard.sql",
+    lobby_id,
+    page.size,
+    page.offset(),
+    by_rating,
+    ratings::INITIAL_RATING
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .map(|row| {
+    Ok(interchange::http::LeaderboardEntry {
+      rank: row
+        .rank
+        .ok_or_else(|| errors::e("Unable to parse leaderboard rank"))?,
+      user_id: row.user_id,
+      user_name: row.user_name,
+      games_played: row.games_played.unwrap_or_default(),
+      wins: row.wins.unwrap_or_default(),
+      total_votes: row.total_votes.unwrap_or_default(),
+      rating: row
+        .rating
+        .ok_or_else(|| errors::e("Unable to parse leaderboard rating"))?,
+    })
+  })
+  .collect()
+}
+
+// Route
+// GET /leaderboards
+//
+// Ranks players by the number of games won, then by the total votes received, or by rating when
+// `sort=rating` is given. When a `lobby_id` is provided, only games played in that lobby are
+// considered (including the rating changes from them) and the user must be a member of it.
+pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let lobby_id = query_values(uri, "lobby_id").into_iter().next();
+  let page = page(uri);
+  let sort = match query_values(uri, "sort").into_iter().next() {
+    Some(sort) if sort == SORT_RATING => SORT_RATING,
+    _ => SORT_WINS,
+  };
+
+  if let Some(lobby_id) = &lobby_id {
+    let mut conn = context.records_connection().await?;
+    let membership = query_file!(
+      "src/routes/lobbies/data-store/load-lobby-detail.sql",
+      lobby_id,
+      uid
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+    if membership.is_none() {
+      warn!("user '{}' not a member of lobby '{}'", uid, lobby_id);
+      return lobbies::denied(context, lobby_id).await;
+    }
+  }
+
+  debug!("loading leaderboard {:?} for lobby {:?}", page, lobby_id);
+  let entries = load_leaderboard(context, lobby_id.as_ref(), &page, sort == SORT_RATING).await?;
+
+  Response::ok_json(interchange::http::Leaderboard {
+    lobby_id,
+    sort: String::from(sort),
+    page: page.number,
+    per_page: page.size,
+    entries,
+  })
+  .map(|response| response.cors(context.cors()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::load_leaderboard;
+  use crate::{
+    context::{test_helpers as context_helpers, Context},
+    http::Page,
+    test_helpers::{cleanup_lobby, game_for_users, GameFixture},
+  };
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  // Creates a game in a new lobby for both users, recording the first as the winner.
+  async fn finished_game(context: &Context, winner: &String, loser: &String) -> String {
+    let GameFixture { lobby_id, game_id } =
+      game_for_users(context.records(), winner, &[loser], None).await;
+
+    let mut conn = context
+      .records_connection()
+      .await
+      .expect("unable to connect");
+
+    query!(
+      "
+      insert into krumnet.game_member_placement_results (user_id, lobby_id, member_id, game_id, place, vote_count)
+      select members.user_id, members.lobby_id, members.id, members.game_id,
+        case when members.user_id = $2 then 1 else 2 end,
+        case when members.user_id = $2 then 2 else 1 end
+      from krumnet.game_memberships as members where members.game_id = $1
+      ",
+      game_id,
+      winner
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to place");
+
+    lobby_id
+  }
+
+  #[test]
+  fn lobby_leaderboard() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.leaderboards.lobby_leaderboard").await;
+      let other = context_helpers::make_user("routes.leaderboards.lobby_leaderboard.1").await;
+      let lobby_id = finished_game(&ctx, &user_id, &other).await;
+
+      let first = Page { number: 1, size: 1 };
+      let entries = load_leaderboard(&ctx, Some(&lobby_id), &first, false)
+        .await
+        .expect("unable to load");
+
+      assert_eq!(entries.len(), 1);
+      assert_eq!(entries[0].user_id, user_id);
+      assert_eq!((entries[0].rank, entries[0].wins), (1, 1));
+      assert_eq!(entries[0].rating, 1500.0);
+
+      let second = Page { number: 2, size: 1 };
+      let entries = load_leaderboard(&ctx, Some(&lobby_id), &second, false)
+        .await
+        .expect("unable to load");
+
+      assert_eq!(entries.len(), 1);
+      assert_eq!(entries[0].user_id, other);
+      assert_eq!((entries[0].rank, entries[0].wins), (2, 0));
+
+      cleanup_lobby(&ctx, &lobby_id).await;
+      context_helpers::cleanup_user(&other).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+}
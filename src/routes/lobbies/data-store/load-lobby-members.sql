@@ -3,6 +3,7 @@ select
   users.id            as user_id,
   users.default_email as user_email,
   users.name          as user_name,
+  users.avatar_url    as user_avatar_url,
   members.invited_by  as invited_by,
   members.joined_at   as joined_at,
   members.left_at     as left_at
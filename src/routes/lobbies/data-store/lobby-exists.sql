@@ -0,0 +1,6 @@
+select
+  lobbies.id as lobby_id
+from
+  krumnet.lobbies as lobbies
+where
+  lobbies.id = $1;
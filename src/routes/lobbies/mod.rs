@@ -14,12 +14,17 @@ use crate::{
   interchange, read_size_async, Authority, Context, Response,
 };
 
+const NOT_MEMBER: &str = "errors.lobbies.not_member";
+
 #[derive(Deserialize, Debug)]
 pub struct Payload {
   kind: String,
 }
 
-async fn load_games(context: &Context, id: &String) -> Result<Vec<interchange::http::LobbyGame>> {
+pub async fn load_games(
+  context: &Context,
+  id: &String,
+) -> Result<Vec<interchange::http::LobbyGame>> {
   let mut conn = context.records_connection().await?;
 
   query_file!("src/routes/lobbies/data-store/load-lobby-games.sql", id)
@@ -43,6 +48,24 @@ async fn load_games(context: &Context, id: &String) -> Result<Vec<interchange::h
     .collect()
 }
 
+// Responds to a signed in user that is not a member of the lobby; lobbies that exist are forbidden
+// while anything else is not found.
+pub async fn denied(context: &Context, lobby_id: &str) -> Result<Response> {
+  let mut conn = context.records_connection().await?;
+  let exists = query_file!("src/routes/lobbies/data-store/lobby-exists.sql", lobby_id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .is_some();
+
+  match exists {
+    true => Ok(Response::forbidden(NOT_MEMBER).cors(context.cors())),
+    false => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
 struct LobbyDetailRow {
   pub id: String,
   pub name: String,
@@ -76,7 +99,7 @@ async fn lobby_details_for_user(
   Ok(details)
 }
 
-async fn load_members(
+pub async fn load_members(
   context: &Context,
   id: &String,
 ) -> Result<Vec<interchange::http::LobbyMember>> {
@@ -91,6 +114,7 @@ async fn load_members(
         member_id: row.member_id,
         user_id: row.user_id,
         name: row.user_name,
+        avatar_url: row.user_avatar_url,
         invited_by: row.invited_by,
         joined_at: row.joined_at,
         left_at: row.left_at,
@@ -99,16 +123,18 @@ async fn load_members(
     .collect()
 }
 
+// Route
+// GET /lobbies/{id}
 pub async fn details(context: &Context, id: &String) -> Result<Response> {
   let uid = match context.authority() {
-    Authority::User { id: s, token: _ } => s,
+    Authority::User { id: s, .. } => s,
     _ => return Ok(Response::unauthorized().cors(context.cors())),
   };
 
   debug!("looking for loby via '{}' for user '{}'", id, uid);
   let deets = match lobby_details_for_user(context, id, uid).await? {
     Some(details) => details,
-    None => return Ok(Response::not_found().cors(context.cors())),
+    None => return denied(context, id).await,
   };
 
   debug!("found lobby '{}' details, loading members", deets.name);
@@ -123,6 +149,24 @@ pub async fn details(context: &Context, id: &String) -> Result<Response> {
   Ok(Response::ok_json(&details)?.cors(context.cors()))
 }
 
+// Route
+// GET /lobbies/{id}/members
+pub async fn members(context: &Context, id: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id: s, .. } => s,
+    _ => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let id = String::from(id);
+
+  if lobby_details_for_user(context, &id, uid).await?.is_none() {
+    return denied(context, &id).await;
+  }
+
+  let members = load_members(context, &id).await?;
+  Response::ok_json(interchange::http::LobbyMemberList { members }).map(|r| r.cors(context.cors()))
+}
+
 // Route
 // GET /lobbies
 pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
@@ -174,7 +218,7 @@ where
   R: Read + Unpin,
 {
   let uid = match context.authority() {
-    Authority::User { id: s, token: _ } => s,
+    Authority::User { id: s, .. } => s,
     _ => return Ok(Response::unauthorized().cors(context.cors())),
   };
 
@@ -9,12 +9,15 @@ use std::marker::Unpin;
 use crate::{constants, errors, interchange, read_size_async, Authority, Context, Response};
 
 const TOO_MANY_MEMBERS: &'static str = "errors.lobbies.too_many_members";
+const INVALID_LOBBY_ID: &str = "errors.lobbies.invalid_id";
+const UNABLE_TO_JOIN: &str = "errors.lobbies.unable_to_join";
 
 #[derive(Deserialize, Debug)]
 pub struct DestroyMembershipPayload {
   lobby_id: String,
 }
 
+// Users that are already members of the lobby, or lobbies that have been closed, can't be joined.
 async fn join_jobby(
   context: &Context,
   lobby_id: &String,
@@ -32,7 +35,7 @@ async fn join_jobby(
   .into_iter()
   .nth(0)
   .map(|row| (row.member_id, row.lobby_id, row.user_id))
-  .ok_or_else(|| errors::e("Unable to join lobby"))
+  .ok_or_else(|| errors::ApiError::Conflict(String::from(UNABLE_TO_JOIN)).into())
 }
 
 async fn count_members(context: &Context, lobby_id: &String) -> Result<Option<i64>> {
@@ -57,7 +60,8 @@ async fn replace_short_id(context: &Context, lobby_id: &String) -> Result<String
   }
 
   if lobby_id.len() < 5 {
-    return Err(errors::e(format!("lobby id too short - '{}'", lobby_id)));
+    warn!("lobby id too short - '{}'", lobby_id);
+    return Err(errors::ApiError::BadRequest(String::from(INVALID_LOBBY_ID)).into());
   }
 
   info!("attempting to resolve short id '{}'", lobby_id);
@@ -72,7 +76,7 @@ async fn replace_short_id(context: &Context, lobby_id: &String) -> Result<String
   .into_iter()
   .nth(0)
   .map(|row| row.id)
-  .ok_or(errors::e(format!("bad lobby id - '{}'", lobby_id)))
+  .ok_or_else(|| errors::ApiError::NotFound.into())
 }
 
 // Route
@@ -98,7 +102,7 @@ where
     }
     Some(value) if value >= constants::MAX_LOBBY_MEMBERS.into() => {
       warn!("too many members in '{}' to join", lobby_id);
-      return Ok(Response::bad_request(TOO_MANY_MEMBERS).cors(context.cors()));
+      return Ok(Response::conflict(TOO_MANY_MEMBERS).cors(context.cors()));
     }
     Some(value) => info!("member count for '{}' satisfactory ({})", lobby_id, value),
   };
@@ -121,9 +125,9 @@ async fn leave_lobby(
   context: &Context,
   lobby_id: &String,
   user_id: &String,
-) -> Result<(String, String)> {
+) -> Result<Option<(String, String)>> {
   let mut conn = context.records_connection().await?;
-  query_file!(
+  let left = query_file!(
     "src/routes/lobby_memberships/data-store/leave-lobby-for-user.sql",
     lobby_id,
     user_id
@@ -133,8 +137,9 @@ async fn leave_lobby(
   .map_err(errors::humanize_error)?
   .into_iter()
   .nth(0)
-  .map(|row| (row.member_id, row.lobby_id))
-  .ok_or_else(|| errors::e("Unable to leave lobby"))
+  .map(|row| (row.member_id, row.lobby_id));
+
+  Ok(left)
 }
 
 // Route
@@ -156,15 +161,16 @@ where
     uid, payload.lobby_id
   );
 
-  let (member_id, lobby_id) = leave_lobby(context, &payload.lobby_id, &uid).await?;
-
-  if member_id.len() == 0 {
-    warn!(
-      "unable to find row to delete user[{}] lobby[{}]",
-      uid, payload.lobby_id
-    );
-    return Ok(Response::not_found().cors(context.cors()));
-  }
+  let (member_id, lobby_id) = match leave_lobby(context, &payload.lobby_id, &uid).await? {
+    Some(left) => left,
+    None => {
+      warn!(
+        "unable to find row to delete user[{}] lobby[{}]",
+        uid, payload.lobby_id
+      );
+      return Ok(Response::not_found().cors(context.cors()));
+    }
+  };
 
   info!("marking membership '{}' as left", member_id);
   let details = interchange::jobs::CleanupLobbyMembership {
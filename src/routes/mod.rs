@@ -1,20 +1,45 @@
-use log::info;
+use async_std::io::Read as AsyncRead;
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::from_slice as deserialize;
 use sqlx::query_file;
 use std::io::Result;
+use std::marker::Unpin;
 
+pub mod admin;
+pub mod api_keys;
 pub mod games;
+pub mod identities;
 pub mod jobs;
+pub mod leaderboards;
 pub mod lobbies;
 pub mod lobby_memberships;
+pub mod reports;
 pub mod rounds;
+pub mod sessions;
+pub mod users;
 
+use crate::constants::GUEST_EMAIL_DOMAIN;
 use crate::http::{query as qs, Uri};
-use crate::interchange::http::{SessionData, SessionUserData};
-use crate::{errors, Authority, Context, Response};
+use crate::interchange::http::{SessionData, SessionTokenData, SessionUserData};
+use crate::{errors, oauth, read_size_async, session, Authority, Context, Response};
+
+#[derive(Debug, Deserialize)]
+struct RefreshPayload {
+  pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestPayload {
+  pub name: String,
+}
+
+const INVALID_GUEST_NAME: &str = "errors.guests.invalid_name";
+const GUEST_NAME_TAKEN: &str = "errors.guests.name_taken";
 
 pub async fn destroy(context: &Context, uri: &Uri) -> Result<Response> {
   let token = match context.authority() {
-    Authority::User { id: _, token } => Some(token.clone()),
+    Authority::User { token, .. } => Some(token.clone()),
 
     Authority::None => uri
       .query()
@@ -26,17 +51,102 @@ pub async fn destroy(context: &Context, uri: &Uri) -> Result<Response> {
   info!("destroying session from token: {}", token);
   context.session().destroy(&token).await?;
 
-  Ok(Response::redirect(&context.config().krumi.auth_uri))
+  let response = Response::redirect(&context.config().krumi.auth_uri);
+
+  match context.config().krumi.session_cookie {
+    true => Ok(
+      oauth::session_cookies(context, "", 0)
+        .into_iter()
+        .fold(response, |response, cookie| response.cookie(cookie)),
+    ),
+    false => Ok(response),
+  }
+}
+
+// Route
+// POST /auth/refresh
+//
+// Exchanges a refresh token for a new session token; the refresh token is rotated each time it is
+// used, so clients must hold on to the one returned in the response.
+pub async fn refresh<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<RefreshPayload>(&contents)?;
+
+  match context.session().refresh(&payload.refresh_token).await? {
+    Some(tokens) => Response::ok_json(SessionTokenData {
+      token: tokens.token,
+      refresh_token: tokens.refresh_token,
+    })
+    .map(|r| r.cors(context.cors())),
+    None => {
+      warn!("unable to refresh session from unknown token");
+      Ok(Response::unauthorized().cors(context.cors()))
+    }
+  }
+}
+
+async fn make_guest(context: &Context, name: &str) -> Result<String> {
+  // Guests have no address of their own, but emails are unique per user.
+  let email = format!("guest-{}@{}", session::random_token(), GUEST_EMAIL_DOMAIN);
+  let mut conn = context.records_connection().await?;
+
+  query_file!("src/data-store/create-guest.sql", email, name)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .map(|row| row.id)
+    .ok_or_else(|| errors::e("Unable to find recently created guest"))
+}
+
+// Route
+// POST /auth/guest
+//
+// Creates a temporary user with the chosen name that can play without logging in with a provider.
+// Guests can later link a provider identity to keep their history as a full account. Names are
+// unique across guests and full accounts alike.
+pub async fn guest<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<GuestPayload>(&contents)?;
+
+  let name = match users::valid_name(&payload.name) {
+    Some(name) => name,
+    None => return Ok(Response::bad_request(INVALID_GUEST_NAME).cors(context.cors())),
+  };
+
+  let uid = match make_guest(context, name).await {
+    Ok(uid) => uid,
+    Err(error) if errors::is_conflict(&error) => {
+      return Ok(Response::conflict(GUEST_NAME_TAKEN).cors(context.cors()));
+    }
+    Err(error) => return Err(error),
+  };
+  info!("created guest user '{}'", uid);
+  let tokens = context.session().create(&uid, context.user_agent()).await?;
+
+  Response::ok_json(SessionTokenData {
+    token: tokens.token,
+    refresh_token: tokens.refresh_token,
+  })
+  .map(|r| r.cors(context.cors()))
 }
 
 pub async fn identify(context: &Context) -> Result<Response> {
   let uid = match context.authority() {
-    Authority::User { id, token: _ } => id,
+    Authority::User { id, .. } => id,
     Authority::None => return Ok(Response::not_found().cors(context.cors())),
   };
 
   info!("loading sesison for user {}", uid);
   let mut conn = context.records_connection().await?;
+  let achievements = users::achievements_for_user(context, uid).await?;
 
   query_file!("src/data-store/user-for-session.sql", uid)
     .fetch_all(&mut conn)
@@ -49,8 +159,67 @@ pub async fn identify(context: &Context) -> Result<Response> {
         id: row.user_id,
         name: row.user_name,
         email: row.user_email,
+        guest: row.guest,
       },
+      achievements,
     })
-    .ok_or_else(|| errors::e("Not found"))
+    .ok_or_else(|| errors::ApiError::NotFound.into())
     .and_then(|tenant| Response::ok_json(&tenant).map(|r| r.cors(context.cors())))
 }
+
+#[cfg(test)]
+mod test {
+  use super::make_guest;
+  use crate::context::{load_authorization, test_helpers as context_helpers};
+  use crate::{errors, Authority};
+  use async_std::task::block_on;
+  use sqlx::query;
+
+  #[test]
+  fn guest_sessions_until_expired() {
+    block_on(async {
+      let context = context_helpers::with_auth(Authority::None);
+      let uid = make_guest(&context, "routes.guest_sessions").await.unwrap();
+      let tokens = context.session().create(&uid, None).await.unwrap();
+
+      let authority =
+        load_authorization(tokens.token.clone(), context.session(), context.records())
+          .await
+          .unwrap();
+      assert_eq!(
+        authority,
+        Authority::user(uid.clone(), tokens.token.clone())
+      );
+
+      let mut conn = context.records_connection().await.unwrap();
+      query!(
+        "update krumnet.users set expired_at = now() where id = $1",
+        uid
+      )
+      .execute(&mut conn)
+      .await
+      .unwrap();
+
+      let authority =
+        load_authorization(tokens.token.clone(), context.session(), context.records())
+          .await
+          .unwrap();
+      assert_eq!(authority, Authority::None);
+
+      context.session().revoke_all(&uid).await.unwrap();
+      context_helpers::cleanup_user(&uid).await;
+    });
+  }
+
+  #[test]
+  fn guest_names_taken() {
+    block_on(async {
+      let (context, uid) = context_helpers::with_user_by_name("routes.guest_names_taken").await;
+
+      let taken = make_guest(&context, "ROUTES.GUEST_NAMES_TAKEN").await;
+      assert!(errors::is_conflict(&taken.unwrap_err()));
+
+      context_helpers::cleanup_user(&uid).await;
+    });
+  }
+}
@@ -0,0 +1,20 @@
+insert into
+  krumnet.reports
+  (reporter_id, user_id, entry_id, reason)
+select
+  members.user_id,
+  entries.user_id,
+  entries.id,
+  cast($3 as varchar)
+from
+  krumnet.game_round_entries as entries
+inner join
+  krumnet.game_memberships as members
+on
+  members.game_id = entries.game_id
+where
+  entries.id = $1
+and
+  members.user_id = $2
+returning
+  id;
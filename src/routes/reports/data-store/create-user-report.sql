@@ -0,0 +1,13 @@
+insert into
+  krumnet.reports
+  (reporter_id, user_id, reason)
+select
+  cast($2 as varchar),
+  users.id,
+  cast($3 as varchar)
+from
+  krumnet.users as users
+where
+  users.id = $1
+returning
+  id;
@@ -0,0 +1,27 @@
+select
+  reports.id         as report_id,
+  reports.reason     as reason,
+  reports.created_at as created_at,
+  reporters.id       as reporter_id,
+  reporters.name     as reporter_name,
+  reports.user_id    as user_id,
+  users.name         as "user_name?",
+  reports.entry_id   as entry_id,
+  entries.entry      as entry,
+  entries.hidden_at  as hidden_at
+from
+  krumnet.reports as reports
+inner join
+  krumnet.users as reporters
+on
+  reporters.id = reports.reporter_id
+left join
+  krumnet.users as users
+on
+  users.id = reports.user_id
+left join
+  krumnet.game_round_entries as entries
+on
+  entries.id = reports.entry_id
+order by
+  reports.created_at desc;
@@ -0,0 +1,9 @@
+update
+  krumnet.game_round_entries as entries
+set
+  hidden_at = null,
+  hidden_by = null
+where
+  entries.id = $1
+returning
+  entries.id;
@@ -0,0 +1,395 @@
+use async_std::io::Read as AsyncRead;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::from_slice as deserialize;
+use sqlx::query_file;
+use std::io::Result;
+use std::marker::Unpin;
+
+use crate::routes::admin::admin_id;
+use crate::{errors, interchange, read_size_async, Authority, Context, Response};
+
+const MISSING_REASON: &str = "errors.reports.missing_reason";
+const REASON_TOO_LONG: &str = "errors.reports.reason_too_long";
+const INVALID_TARGET: &str = "errors.reports.invalid_target";
+
+const MAX_REASON_LENGTH: usize = 255;
+
+#[derive(Debug, Deserialize)]
+struct ReportPayload {
+  pub entry_id: Option<String>,
+  pub user_id: Option<String>,
+  pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HiddenEntryPayload {
+  pub entry_id: String,
+}
+
+async fn report_entry(
+  context: &Context,
+  reporter_id: &String,
+  entry_id: &String,
+  reason: &str,
+) -> Result<Option<String>> {
+  let mut conn = context.records_connection().await?;
+  let report = query_file!(
+    "src/routes/reports/data-store/create-entry-report.sql",
+    entry_id,
+    reporter_id,
+    reason
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  .map(|row| row.id);
+
+  Ok(report)
+}
+
+async fn report_user(
+  context: &Context,
+  reporter_id: &String,
+  user_id: &String,
+  reason: &str,
+) -> Result<Option<String>> {
+  let mut conn = context.records_connection().await?;
+  let report = query_file!(
+    "src/routes/reports/data-store/create-user-report.sql",
+    user_id,
+    reporter_id,
+    reason
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  .map(|row| row.id);
+
+  Ok(report)
+}
+
+// Route
+// POST /reports
+//
+// Flags either a round entry (visible to the reporter as a member of its game) or a user.
+pub async fn create<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match context.authority() {
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+    Authority::User { id, .. } => id,
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<ReportPayload>(&contents)?;
+  let reason = payload.reason.trim();
+
+  if reason.is_empty() {
+    return Ok(Response::bad_request(MISSING_REASON).cors(context.cors()));
+  }
+
+  if reason.chars().count() > MAX_REASON_LENGTH {
+    return Ok(Response::bad_request(REASON_TOO_LONG).cors(context.cors()));
+  }
+
+  let report = match (&payload.entry_id, &payload.user_id) {
+    (Some(entry_id), None) => report_entry(context, uid, entry_id, reason).await?,
+    (None, Some(user_id)) => report_user(context, uid, user_id, reason).await?,
+    _ => {
+      warn!("user '{}' submitted report without single target", uid);
+      return Ok(Response::bad_request(INVALID_TARGET).cors(context.cors()));
+    }
+  };
+
+  match report {
+    Some(id) => {
+      info!("user '{}' created report '{}'", uid, id);
+      Response::ok_json(interchange::http::NewReport { id }).map(|r| r.cors(context.cors()))
+    }
+    None => {
+      warn!("unable to create report for user '{}' - {:?}", uid, payload);
+      Ok(Response::not_found().cors(context.cors()))
+    }
+  }
+}
+
+// Route
+// GET /reports
+pub async fn find(context: &Context) -> Result<Response> {
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  debug!("loading reports for admin '{}'", uid);
+  let mut conn = context.records_connection().await?;
+
+  query_file!("src/routes/reports/data-store/load-reports.sql")
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| {
+      Ok(interchange::http::Report {
+        id: row.report_id,
+        reason: row.reason,
+        created: row
+          .created_at
+          .ok_or_else(|| errors::e("Unable to parse report created timestamp"))?,
+        reporter_id: row.reporter_id,
+        reporter_name: row.reporter_name,
+        user_id: row.user_id,
+        user_name: row.user_name,
+        entry_id: row.entry_id,
+        entry: row.entry,
+        hidden: row.hidden_at.is_some(),
+      })
+    })
+    .collect::<Result<Vec<interchange::http::Report>>>()
+    .and_then(|reports| Response::ok_json(interchange::http::ReportList { reports }))
+    .map(|response| response.cors(context.cors()))
+}
+
+async fn set_entry_visibility<R>(
+  context: &Context,
+  reader: &mut R,
+  hidden: bool,
+) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match admin_id(context) {
+    Some(id) => id,
+    None => return Ok(Response::not_found().cors(context.cors())),
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let HiddenEntryPayload { entry_id } = deserialize::<HiddenEntryPayload>(&contents)?;
+  let mut conn = context.records_connection().await?;
+
+  let updated = match hidden {
+    true => query_file!(
+      "src/routes/reports/data-store/hide-entry.sql",
+      entry_id,
+      uid
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .map(|row| row.id),
+    false => query_file!("src/routes/reports/data-store/show-entry.sql", entry_id)
+      .fetch_all(&mut conn)
+      .await
+      .map_err(errors::humanize_error)?
+      .into_iter()
+      .next()
+      .map(|row| row.id),
+  };
+
+  match updated {
+    Some(id) => {
+      info!("admin '{}' set entry '{}' hidden: {}", uid, id, hidden);
+      Ok(Response::default().cors(context.cors()))
+    }
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// POST /hidden-entries
+pub async fn hide_entry<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  set_entry_visibility(context, reader, true).await
+}
+
+// Route
+// DELETE /hidden-entries
+pub async fn show_entry<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  set_entry_visibility(context, reader, false).await
+}
+
+#[cfg(test)]
+mod test {
+  use super::{report_entry, report_user};
+  use crate::{
+    context::{test_helpers as context_helpers, Context},
+    test_helpers::{cleanup_lobby, game_for_users, GameFixture},
+  };
+  use async_std::task::block_on;
+  use sqlx::{query, query_file};
+
+  struct EntryContext {
+    lobby_id: String,
+    round_id: String,
+    entry_id: String,
+  }
+
+  // Creates a game for the two users, with a single entry in the first round by the author.
+  async fn entry_for_users(context: &Context, user_id: &String, author: &String) -> EntryContext {
+    let GameFixture { lobby_id, game_id } =
+      game_for_users(context.records(), user_id, &[author], None).await;
+
+    let mut conn = context
+      .records_connection()
+      .await
+      .expect("unable to connect");
+
+    let (round_id, entry_id) = query!(
+      "
+      insert into krumnet.game_round_entries (user_id, member_id, round_id, game_id, lobby_id, entry)
+      select members.user_id, members.id, rounds.id, rounds.game_id, rounds.lobby_id, 'reported'
+      from krumnet.game_rounds as rounds
+      inner join krumnet.game_memberships as members on members.game_id = rounds.game_id
+      where rounds.game_id = $1 and rounds.position = 0 and members.user_id = $2
+      returning id, round_id
+      ",
+      game_id,
+      author
+    )
+    .fetch_all(&mut conn)
+    .await
+    .expect("unable to insert")
+    .into_iter()
+    .next()
+    .map(|row| (row.round_id, row.id))
+    .expect("missing entry");
+
+    EntryContext {
+      lobby_id,
+      round_id,
+      entry_id,
+    }
+  }
+
+  async fn cleanup_reports(context: &Context, reporter_id: &String) {
+    let mut conn = context
+      .records_connection()
+      .await
+      .expect("unable to connect");
+
+    query!(
+      "delete from krumnet.reports where reporter_id = $1",
+      reporter_id
+    )
+    .execute(&mut conn)
+    .await
+    .expect("unable to delete");
+  }
+
+  #[test]
+  fn report_entry_as_member() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.reports.report_entry_as_member").await;
+      let author = context_helpers::make_user("routes.reports.report_entry_as_member.1").await;
+      let stranger = context_helpers::make_user("routes.reports.report_entry_as_member.2").await;
+      let entry = entry_for_users(&ctx, &user_id, &author).await;
+
+      let report = report_entry(&ctx, &user_id, &entry.entry_id, "rude").await;
+      assert!(report.unwrap().is_some());
+
+      let report = report_entry(&ctx, &stranger, &entry.entry_id, "rude").await;
+      assert!(report.unwrap().is_none());
+
+      cleanup_lobby(&ctx, &entry.lobby_id).await;
+      cleanup_reports(&ctx, &user_id).await;
+      context_helpers::cleanup_user(&author).await;
+      context_helpers::cleanup_user(&stranger).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn report_existing_user() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.reports.report_existing_user").await;
+      let other = context_helpers::make_user("routes.reports.report_existing_user.1").await;
+
+      let report = report_user(&ctx, &user_id, &other, "rude").await;
+      assert!(report.unwrap().is_some());
+
+      let report = report_user(&ctx, &user_id, &String::from("bogus"), "rude").await;
+      assert!(report.unwrap().is_none());
+
+      cleanup_reports(&ctx, &user_id).await;
+      context_helpers::cleanup_user(&other).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn hidden_entries_redacted() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.reports.hidden_entries_redacted").await;
+      let author = context_helpers::make_user("routes.reports.hidden_entries_redacted.1").await;
+      let entry = entry_for_users(&ctx, &user_id, &author).await;
+      let mut conn = ctx.records_connection().await.expect("unable to connect");
+
+      query_file!(
+        "src/routes/reports/data-store/hide-entry.sql",
+        entry.entry_id,
+        user_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .expect("unable to hide");
+
+      let (entry_text, hidden_at) = query_file!(
+        "src/routes/rounds/data-store/load-round-entries.sql",
+        std::slice::from_ref(&entry.round_id),
+        user_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .expect("unable to load")
+      .into_iter()
+      .next()
+      .map(|row| (row.entry, row.hidden_at))
+      .expect("missing entry");
+
+      assert_eq!(entry_text, None);
+      assert!(hidden_at.is_some());
+
+      query_file!(
+        "src/routes/reports/data-store/show-entry.sql",
+        entry.entry_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .expect("unable to show");
+
+      let entry_text = query_file!(
+        "src/routes/rounds/data-store/load-round-entries.sql",
+        std::slice::from_ref(&entry.round_id),
+        user_id
+      )
+      .fetch_all(&mut conn)
+      .await
+      .expect("unable to load")
+      .into_iter()
+      .next()
+      .and_then(|row| row.entry);
+
+      assert_eq!(entry_text, Some(String::from("reported")));
+
+      cleanup_lobby(&ctx, &entry.lobby_id).await;
+      context_helpers::cleanup_user(&author).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+}
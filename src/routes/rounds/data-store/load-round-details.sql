@@ -13,6 +13,6 @@ right join
 on
   members.game_id = rounds.game_id
 where
-  rounds.id = $2
+  rounds.id = any(cast($2 as varchar[]))
 and
   members.user_id = $1;
@@ -1,12 +1,19 @@
 select
-  entries.id          as entry_id,
-  entries.round_id    as round_id,
-  entries.member_id   as member_id,
+  entries.id          as "entry_id!",
+  entries.round_id    as "round_id!",
+  entries.member_id   as "member_id!",
   entries.created_at  as created_at,
-  entries.user_id     as user_id,
-  users.name          as user_name,
-  entries.entry       as entry,
-  rounds.fulfilled_at as fulfilled
+  entries.user_id     as "user_id!",
+  entries.team_id     as team_id,
+  users.name          as "user_name!",
+  users.avatar_url    as user_avatar_url,
+  case
+    when entries.hidden_at is null then entries.entry
+    else null
+  end                 as entry,
+  entries.hidden_at   as hidden_at,
+  rounds.fulfilled_at as fulfilled,
+  viewers.team_id     as viewer_team_id
 from
   krumnet.game_round_entries as entries
 left join
@@ -17,5 +24,11 @@ left join
   krumnet.users as users
 on
   users.id = entries.user_id
+left join
+  krumnet.game_memberships as viewers
+on
+  viewers.game_id = entries.game_id
+and
+  viewers.user_id = $2
 where
-  entries.round_id = $1;
+  entries.round_id = any(cast($1 as varchar[]));
@@ -1,8 +1,9 @@
 select
-  results.id      as result_id,
-  results.user_id as user_id,
-  users.name      as user_name,
-  results.place   as round_place
+  results.id       as "result_id!",
+  results.round_id as "round_id!",
+  results.user_id  as "user_id!",
+  users.name       as "user_name!",
+  results.place    as "round_place!"
 from
   krumnet.game_member_round_placement_results as results
 left join
@@ -10,4 +11,4 @@ left join
 on
   users.id = results.user_id
 where
-  results.round_id = $1;
+  results.round_id = any(cast($1 as varchar[]));
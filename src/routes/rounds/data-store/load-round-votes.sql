@@ -1,5 +1,6 @@
 select
   votes.id         as id,
+  votes.round_id   as round_id,
   votes.entry_id   as entry_id,
   votes.member_id  as member_id,
   votes.user_id    as user_id,
@@ -7,4 +8,4 @@ select
 from
   krumnet.game_round_entry_votes as votes
 where
-  votes.round_id = $1;
+  votes.round_id = any(cast($1 as varchar[]));
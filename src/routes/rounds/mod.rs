@@ -1,19 +1,34 @@
 use chrono::{DateTime, Utc};
 use log::{debug, info, warn};
 use sqlx::{query_file, query_file_as, FromRow};
+use std::collections::HashMap;
 use std::io::{Error, Result};
 
 use crate::{
+  constants::MAX_BATCH_IDS,
   errors,
   http::{query_values, Uri},
   interchange, Authority, Context, Response,
 };
 
-fn log_err<E: std::error::Error>(error: E) -> Error {
+const TOO_MANY_IDS: &str = "errors.rounds.too_many_ids";
+
+fn log_err<E: std::error::Error + 'static>(error: E) -> Error {
   warn!("error - {}", error);
   errors::humanize_error(error)
 }
 
+// Groups rows that were loaded for several rounds at once by the round they belong to.
+fn by_round<T>(rows: Vec<(String, T)>) -> HashMap<String, Vec<T>> {
+  let mut grouped: HashMap<String, Vec<T>> = HashMap::new();
+
+  for (round_id, row) in rows {
+    grouped.entry(round_id).or_default().push(row);
+  }
+
+  grouped
+}
+
 #[derive(FromRow)]
 struct RoundDetailRow {
   round_id: String,
@@ -28,23 +43,96 @@ struct RoundDetailRow {
 async fn round_details(
   context: &Context,
   user_id: &String,
-  round_id: &String,
-) -> Result<RoundDetailRow> {
+  round_ids: &[String],
+) -> Result<Vec<RoundDetailRow>> {
   let mut conn = context.records_connection().await?;
   query_file_as!(
     RoundDetailRow,
     "src/routes/rounds/data-store/load-round-details.sql",
     user_id,
-    round_id
+    round_ids
   )
   .fetch_all(&mut conn)
   .await
-  .map_err(log_err)?
-  .into_iter()
-  .nth(0)
-  .ok_or_else(|| errors::e(format!("Unable to find round '{}'", round_id)))
+  .map_err(log_err)
 }
 
+// Loads the complete details of every round in `ids` whose game the user is a member of, with a
+// fixed number of queries regardless of how many ids are requested. Rounds are returned in the
+// order of `ids`.
+async fn load_rounds(
+  context: &Context,
+  uid: &String,
+  ids: &[String],
+) -> Result<Vec<interchange::http::GameRoundDetails>> {
+  let rows = round_details(context, uid, ids).await?;
+
+  if rows.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let found_ids = rows
+    .iter()
+    .map(|row| row.round_id.clone())
+    .collect::<Vec<String>>();
+  debug!("found rounds {:?}, parsing into response", found_ids);
+
+  let mut entries = entries_for_rounds(context, uid, &found_ids).await?;
+  let mut results = results_for_rounds(context, &found_ids).await?;
+  let mut votes = votes_for_rounds(context, &found_ids).await?;
+
+  let mut rounds = rows
+    .into_iter()
+    .map(|row| {
+      let RoundDetailRow {
+        round_id: id,
+        prompt,
+        pos: position,
+        created_at: created,
+        fulfilled_at: fulfilled,
+        completed_at: completed,
+        started_at: started,
+      } = row;
+
+      let details = interchange::http::GameRoundDetails {
+        entries: entries.remove(&id).unwrap_or_default(),
+        results: results.remove(&id).unwrap_or_default(),
+        votes: votes.remove(&id).unwrap_or_default(),
+        id: id.clone(),
+        position,
+        fulfilled,
+        prompt,
+        created,
+        completed,
+        started,
+      };
+
+      (id, details)
+    })
+    .collect::<HashMap<String, interchange::http::GameRoundDetails>>();
+
+  Ok(ids.iter().filter_map(|id| rounds.remove(id)).collect())
+}
+
+// Route
+// GET /rounds/{id}
+pub async fn find_one(context: &Context, rid: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  match load_rounds(context, uid, &[String::from(rid)]).await?.pop() {
+    Some(details) => Response::ok_json(details).map(|res| res.cors(context.cors())),
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// GET /rounds
+//
+// Responds with a list of every round in `ids[]` the user is able to see. A single id responds with
+// the round itself, as this route did before it accepted several.
 pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
   let uid = match context.authority() {
     Authority::User { id, .. } => id,
@@ -53,94 +141,91 @@ pub async fn find(context: &Context, uri: &Uri) -> Result<Response> {
 
   let ids = query_values(uri, "ids[]");
 
-  if ids.len() != 1 {
-    debug!("find all rounds not implemented yet");
+  if ids.len() > MAX_BATCH_IDS {
+    warn!("user '{}' requested {} rounds", uid, ids.len());
+    return Ok(Response::bad_request(TOO_MANY_IDS).cors(context.cors()));
+  }
+
+  if ids.is_empty() {
+    debug!("no round ids provided");
     return Ok(Response::not_found().cors(context.cors()));
   }
 
-  let rid = ids.iter().nth(0).ok_or(errors::e("invalid id"))?;
-  debug!("attempting to find round from single id - {:?}", rid);
-  let RoundDetailRow {
-    round_id: id,
-    prompt,
-    pos: position,
-    created_at: created,
-    fulfilled_at: fulfilled,
-    completed_at: completed,
-    started_at: started,
-  } = round_details(context, &uid, &rid).await?;
-
-  debug!("found round row '{}', parsing into response", id);
-  let entries = entries_for_round(context, &uid, &id).await?;
-  let results = results_for_round(context, &id).await?;
-  let votes = votes_for_round(context, &id).await?;
-
-  let details = interchange::http::GameRoundDetails {
-    id,
-    entries,
-    results,
-    votes,
-    position,
-    fulfilled,
-    prompt,
-    created,
-    completed,
-    started,
-  };
+  if let [rid] = ids.as_slice() {
+    return find_one(context, rid).await;
+  }
 
-  Response::ok_json(details).map(|res| res.cors(context.cors()))
+  let rounds = load_rounds(context, uid, &ids).await?;
+  Response::ok_json(interchange::http::GameRoundList { rounds }).map(|res| res.cors(context.cors()))
 }
 
-async fn votes_for_round(
+async fn votes_for_rounds(
   context: &Context,
-  round_id: &String,
-) -> Result<Vec<interchange::http::GameRoundVote>> {
+  round_ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameRoundVote>>> {
   let mut conn = context.records_connection().await?;
-  info!("loading votes for round '{}'", round_id);
-  query_file_as!(
-    interchange::http::GameRoundVote,
+  info!("loading votes for rounds {:?}", round_ids);
+  let rows = query_file!(
     "src/routes/rounds/data-store/load-round-votes.sql",
-    round_id
+    round_ids
   )
   .fetch_all(&mut conn)
   .await
-  .map_err(log_err)
+  .map_err(log_err)?
+  .into_iter()
+  .map(|row| {
+    let vote = interchange::http::GameRoundVote {
+      id: row.id,
+      member_id: row.member_id,
+      user_id: row.user_id,
+      entry_id: row.entry_id,
+      created: row.created,
+    };
+    (row.round_id, vote)
+  })
+  .collect();
+
+  Ok(by_round(rows))
 }
 
-async fn results_for_round(
+async fn results_for_rounds(
   context: &Context,
-  round_id: &String,
-) -> Result<Vec<interchange::http::GameRoundPlacement>> {
+  round_ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameRoundPlacement>>> {
   let mut conn = context.records_connection().await?;
 
-  query_file!(
+  let rows = query_file!(
     "src/routes/rounds/data-store/load-round-results.sql",
-    round_id
+    round_ids
   )
   .fetch_all(&mut conn)
   .await
   .map_err(log_err)?
   .into_iter()
   .map(|row| {
-    Ok(interchange::http::GameRoundPlacement {
+    let placement = interchange::http::GameRoundPlacement {
       id: row.result_id,
       user_name: row.user_name,
       user_id: row.user_id,
       place: row.round_place,
-    })
+    };
+    (row.round_id, placement)
   })
-  .collect()
+  .collect();
+
+  Ok(by_round(rows))
 }
 
-async fn entries_for_round(
+async fn entries_for_rounds(
   context: &Context,
   active_user_id: &String,
-  round_id: &String,
-) -> Result<Vec<interchange::http::GameRoundEntry>> {
+  round_ids: &[String],
+) -> Result<HashMap<String, Vec<interchange::http::GameRoundEntry>>> {
   let mut conn = context.records_connection().await?;
   query_file!(
     "src/routes/rounds/data-store/load-round-entries.sql",
-    round_id
+    round_ids,
+    active_user_id
   )
   .fetch_all(&mut conn)
   .await
@@ -148,22 +233,29 @@ async fn entries_for_round(
   .into_iter()
   .map(|row| {
     let fulfilled = row.fulfilled;
-    let entry = match (&row.user_id == active_user_id) || fulfilled.is_some() {
+    let teammate = row.team_id.is_some() && row.team_id == row.viewer_team_id;
+    let entry = match (&row.user_id == active_user_id) || teammate || fulfilled.is_some() {
       true => row.entry,
       false => None,
     };
 
-    Ok(interchange::http::GameRoundEntry {
+    let round_entry = interchange::http::GameRoundEntry {
       id: row.entry_id,
-      round_id: row.round_id,
+      round_id: row.round_id.clone(),
       member_id: row.member_id,
       created: row
         .created_at
         .ok_or_else(|| errors::e("Unable to load round entry created timestamp"))?,
       user_id: row.user_id,
       user_name: row.user_name,
+      user_avatar_url: row.user_avatar_url,
+      team_id: row.team_id,
+      hidden: row.hidden_at.is_some(),
       entry,
-    })
+    };
+
+    Ok((row.round_id, round_entry))
   })
-  .collect()
+  .collect::<Result<Vec<_>>>()
+  .map(by_round)
 }
@@ -0,0 +1,66 @@
+use log::{info, warn};
+use std::io::Result;
+
+use crate::{interchange, session::session_id, Authority, Context, Response};
+
+// Route
+// GET /sessions
+//
+// Lists the active sessions of the current user, flagging the one used to make the request.
+pub async fn find(context: &Context) -> Result<Response> {
+  let (uid, token) = match context.authority() {
+    Authority::User { id, token, .. } => (id, token),
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let current = session_id(token);
+  let sessions = context
+    .session()
+    .list(uid)
+    .await?
+    .into_iter()
+    .map(|record| interchange::http::ActiveSession {
+      current: current.as_ref() == Some(&record.id),
+      id: record.id,
+      created: record.created,
+      last_seen: record.last_seen,
+      user_agent: record.user_agent,
+    })
+    .collect();
+
+  Response::ok_json(interchange::http::ActiveSessionList { sessions })
+    .map(|r| r.cors(context.cors()))
+}
+
+// Route
+// DELETE /sessions/{id}
+pub async fn destroy(context: &Context, sid: &str) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  match context.session().revoke(uid, &sid.to_string()).await? {
+    true => Ok(Response::default().cors(context.cors())),
+    false => {
+      warn!("user '{}' unable to revoke session '{}'", uid, sid);
+      Ok(Response::not_found().cors(context.cors()))
+    }
+  }
+}
+
+// Route
+// DELETE /sessions
+//
+// Signs the user out everywhere, including the session used to make the request.
+pub async fn destroy_all(context: &Context) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let count = context.session().revoke_all(uid).await?;
+  info!("user '{}' revoked {} sessions", uid, count);
+
+  Response::ok_json(interchange::http::RevokedSessions { count }).map(|r| r.cors(context.cors()))
+}
@@ -0,0 +1,4 @@
+delete from
+  krumnet.user_avatars
+where
+  user_id = $1;
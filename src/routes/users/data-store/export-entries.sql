@@ -0,0 +1,19 @@
+select
+  entries.id         as entry_id,
+  entries.game_id    as game_id,
+  entries.round_id   as round_id,
+  rounds.prompt      as prompt,
+  entries.entry      as entry,
+  entries.auto       as auto,
+  entries.hidden_at  as hidden_at,
+  entries.created_at as created_at
+from
+  krumnet.game_round_entries as entries
+inner join
+  krumnet.game_rounds as rounds
+on
+  rounds.id = entries.round_id
+where
+  entries.user_id = $1
+order by
+  entries.created_at asc;
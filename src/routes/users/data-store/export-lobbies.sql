@@ -0,0 +1,16 @@
+select
+  lobbies.id             as lobby_id,
+  lobbies.name           as lobby_name,
+  memberships.invited_by as invited_by,
+  memberships.joined_at  as joined_at,
+  memberships.left_at    as left_at
+from
+  krumnet.lobby_memberships as memberships
+inner join
+  krumnet.lobbies as lobbies
+on
+  lobbies.id = memberships.lobby_id
+where
+  memberships.user_id = $1
+order by
+  memberships.joined_at asc;
@@ -0,0 +1,16 @@
+select
+  placements.game_id    as game_id,
+  games.name            as game_name,
+  placements.place      as place,
+  placements.vote_count as vote_count,
+  placements.created_at as created_at
+from
+  krumnet.game_member_placement_results as placements
+inner join
+  krumnet.games as games
+on
+  games.id = placements.game_id
+where
+  placements.user_id = $1
+order by
+  placements.created_at asc;
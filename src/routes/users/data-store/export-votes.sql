@@ -0,0 +1,12 @@
+select
+  votes.id         as vote_id,
+  votes.game_id    as game_id,
+  votes.round_id   as round_id,
+  votes.entry_id   as entry_id,
+  votes.created_at as created_at
+from
+  krumnet.game_round_entry_votes as votes
+where
+  votes.user_id = $1
+order by
+  votes.created_at asc;
@@ -0,0 +1,19 @@
+select
+  results.round_id   as round_id,
+  results.game_id    as game_id,
+  rounds.prompt      as prompt,
+  results.place      as place,
+  results.vote_count as vote_count
+from
+  krumnet.game_member_round_placement_results as results
+inner join
+  krumnet.game_rounds as rounds
+on
+  rounds.id = results.round_id
+where
+  results.user_id = $1
+order by
+  results.vote_count desc,
+  results.place asc,
+  results.created_at asc
+limit 1;
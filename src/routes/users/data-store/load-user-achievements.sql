@@ -0,0 +1,12 @@
+select
+  achievements.id         as id,
+  achievements.kind       as kind,
+  achievements.game_id    as game_id,
+  achievements.round_id   as round_id,
+  achievements.awarded_at as awarded_at
+from
+  krumnet.user_achievements as achievements
+where
+  achievements.user_id = $1
+order by
+  achievements.awarded_at asc;
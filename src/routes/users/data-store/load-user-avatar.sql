@@ -0,0 +1,8 @@
+select
+  avatars.content_type as content_type,
+  avatars.image        as image
+from
+  krumnet.user_avatars as avatars
+where
+  avatars.user_id = $1
+limit 1;
@@ -0,0 +1,12 @@
+select
+  users.id            as user_id,
+  users.name          as user_name,
+  users.default_email as user_email,
+  users.avatar_url    as avatar_url,
+  users.guest         as guest,
+  users.created_at    as created_at
+from
+  krumnet.users as users
+where
+  users.id = $1
+limit 1;
@@ -0,0 +1,24 @@
+select
+  users.id                                                 as user_id,
+  users.name                                               as user_name,
+  count(placements.id)                                     as games_played,
+  count(placements.id) filter (where placements.place = 1) as wins,
+  cast(avg(placements.place) as double precision)          as average_place,
+  cast(coalesce(sum(placements.vote_count), 0) as bigint)  as total_votes,
+  coalesce(ratings.rating, $2)                             as rating,
+  coalesce(ratings.games_rated, 0)                         as rated_games
+from
+  krumnet.users as users
+left join
+  krumnet.game_member_placement_results as placements
+on
+  placements.user_id = users.id
+left join
+  krumnet.user_ratings as ratings
+on
+  ratings.user_id = users.id
+where
+  users.id = $1
+group by
+  users.id,
+  ratings.user_id;
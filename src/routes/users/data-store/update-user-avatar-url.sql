@@ -0,0 +1,6 @@
+update
+  krumnet.users
+set
+  avatar_url = $2
+where
+  id = $1;
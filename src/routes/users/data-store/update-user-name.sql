@@ -0,0 +1,6 @@
+update
+  krumnet.users
+set
+  name = $2
+where
+  id = $1;
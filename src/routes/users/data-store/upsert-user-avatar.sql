@@ -0,0 +1,11 @@
+insert into krumnet.user_avatars
+  (user_id, content_type, image, updated_at)
+values
+  ($1, $2, $3, now())
+on conflict (user_id) do update
+set
+  content_type = excluded.content_type,
+  image = excluded.image,
+  updated_at = excluded.updated_at
+returning
+  updated_at;
@@ -0,0 +1,671 @@
+use async_std::io::Read as AsyncRead;
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::{Deserialize, Deserializer};
+use serde_json::from_slice as deserialize;
+use sqlx::query_file;
+use std::io::Result;
+use std::marker::Unpin;
+
+use crate::constants::{MAX_AVATAR_SIZE, MAX_AVATAR_URL_LENGTH, MAX_USER_NAME_LENGTH};
+use crate::http::Url;
+use crate::routes::identities;
+use crate::{
+  achievements::Achievement, errors, interchange, ratings, read_size_async, Authority, Context,
+  Response,
+};
+
+const INVALID_NAME: &str = "errors.users.invalid_name";
+const NAME_TAKEN: &str = "errors.users.name_taken";
+const INVALID_AVATAR_URL: &str = "errors.users.invalid_avatar_url";
+const INVALID_AVATAR: &str = "errors.users.invalid_avatar";
+const AVATAR_TOO_LARGE: &str = "errors.users.avatar_too_large";
+
+// Distinguishes between a field that was left out of the payload and one explicitly set to null.
+fn nullable<'de, D>(deserializer: D) -> std::result::Result<Option<Option<String>>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfilePayload {
+  name: Option<String>,
+  #[serde(default, deserialize_with = "nullable")]
+  avatar_url: Option<Option<String>>,
+}
+
+// Display names are trimmed and must be non-empty, short and free of control characters.
+pub fn valid_name(name: &str) -> Option<&str> {
+  let name = name.trim();
+  let length = name.chars().count();
+
+  match length > 0 && length <= MAX_USER_NAME_LENGTH && !name.chars().any(char::is_control) {
+    true => Some(name),
+    false => None,
+  }
+}
+
+// Avatars hosted elsewhere must be served over https.
+fn valid_avatar_url(url: &str) -> bool {
+  url.len() <= MAX_AVATAR_URL_LENGTH
+    && Url::parse(url)
+      .map(|parsed| parsed.scheme() == "https" && parsed.host_str().is_some())
+      .unwrap_or(false)
+}
+
+// Uploaded images are identified by their leading bytes rather than trusting the client.
+fn image_content_type(image: &[u8]) -> Option<&'static str> {
+  match image {
+    [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+    [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+    [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+    [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+    _ => None,
+  }
+}
+
+async fn profile_for_user(
+  context: &Context,
+  user_id: &str,
+) -> Result<Option<interchange::http::UserProfile>> {
+  let mut conn = context.records_connection().await?;
+
+  let profile = query_file!("src/routes/users/data-store/load-user-profile.sql", user_id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .map(|row| interchange::http::UserProfile {
+      id: row.user_id,
+      name: row.user_name,
+      email: row.user_email,
+      avatar_url: row.avatar_url,
+      guest: row.guest,
+      created: row.created_at,
+    });
+
+  Ok(profile)
+}
+
+async fn best_round(
+  context: &Context,
+  user_id: &str,
+) -> Result<Option<interchange::http::UserBestRound>> {
+  let mut conn = context.records_connection().await?;
+
+  let round = query_file!("src/routes/users/data-store/load-best-round.sql", user_id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next()
+    .map(|row| interchange::http::UserBestRound {
+      round_id: row.round_id,
+      game_id: row.game_id,
+      prompt: row.prompt,
+      place: row.place,
+      vote_count: row.vote_count,
+    });
+
+  Ok(round)
+}
+
+async fn stats_for_user(
+  context: &Context,
+  user_id: &str,
+) -> Result<Option<interchange::http::UserStats>> {
+  let mut conn = context.records_connection().await?;
+
+  let row = match query_file!(
+    "src/routes/users/data-store/load-user-stats.sql",
+    user_id,
+    ratings::INITIAL_RATING
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  {
+    Some(row) => row,
+    None => return Ok(None),
+  };
+
+  Ok(Some(interchange::http::UserStats {
+    user_id: row.user_id,
+    user_name: row.user_name,
+    games_played: row.games_played.unwrap_or_default(),
+    wins: row.wins.unwrap_or_default(),
+    average_place: row.average_place,
+    total_votes: row.total_votes.unwrap_or_default(),
+    rating: row.rating.unwrap_or(ratings::INITIAL_RATING),
+    rated_games: row.rated_games.unwrap_or_default(),
+    best_round: best_round(context, user_id).await?,
+  }))
+}
+
+// Route
+// GET /users/{id}/stats
+//
+// Aggregates the game and round placement results of a user across every game they've finished.
+pub async fn stats(context: &Context, user_id: &str) -> Result<Response> {
+  if let Authority::None = context.authority() {
+    return Ok(Response::unauthorized().cors(context.cors()));
+  }
+
+  debug!("loading stats for user '{}'", user_id);
+
+  match stats_for_user(context, user_id).await? {
+    Some(stats) => Response::ok_json(stats).map(|r| r.cors(context.cors())),
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Loads the achievements awarded to a user, skipping any whose kind is no longer known.
+pub async fn achievements_for_user(
+  context: &Context,
+  user_id: &str,
+) -> Result<Vec<interchange::http::UserAchievement>> {
+  let mut conn = context.records_connection().await?;
+
+  query_file!(
+    "src/routes/users/data-store/load-user-achievements.sql",
+    user_id
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .filter_map(|row| match Achievement::from_kind(&row.kind) {
+    Some(achievement) => Some((achievement, row)),
+    None => {
+      warn!("unknown achievement kind '{}' for '{}'", row.kind, user_id);
+      None
+    }
+  })
+  .map(|(achievement, row)| {
+    Ok(interchange::http::UserAchievement {
+      id: row.id,
+      kind: row.kind,
+      title: String::from(achievement.title()),
+      description: achievement.description(),
+      game_id: row.game_id,
+      round_id: row.round_id,
+      awarded: row
+        .awarded_at
+        .ok_or_else(|| errors::e("Unable to parse achievement awarded timestamp"))?,
+    })
+  })
+  .collect()
+}
+
+// Route
+// GET /users/{id}/achievements
+pub async fn achievements(context: &Context, user_id: &str) -> Result<Response> {
+  if let Authority::None = context.authority() {
+    return Ok(Response::unauthorized().cors(context.cors()));
+  }
+
+  debug!("loading achievements for user '{}'", user_id);
+  let achievements = achievements_for_user(context, user_id).await?;
+
+  Response::ok_json(interchange::http::UserAchievementList { achievements })
+    .map(|r| r.cors(context.cors()))
+}
+
+// Route
+// GET /users/me
+pub async fn me(context: &Context) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  match profile_for_user(context, uid).await? {
+    Some(profile) => Response::ok_json(profile).map(|r| r.cors(context.cors())),
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// PATCH /users/me
+//
+// Updates the display name and/or avatar url of the current user. Names are unique, ignoring
+// case, and setting `avatar_url` to null removes any avatar, including an uploaded one.
+pub async fn update<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let contents = read_size_async(reader, context.pending()).await?;
+  let payload = deserialize::<ProfilePayload>(&contents)?;
+  update_profile(context, uid, payload).await
+}
+
+async fn update_profile(context: &Context, uid: &str, payload: ProfilePayload) -> Result<Response> {
+  let name = match payload.name.as_deref().map(valid_name) {
+    Some(Some(name)) => Some(name),
+    Some(None) => return Ok(Response::bad_request(INVALID_NAME).cors(context.cors())),
+    None => None,
+  };
+
+  if let Some(Some(url)) = &payload.avatar_url {
+    if !valid_avatar_url(url) {
+      return Ok(Response::bad_request(INVALID_AVATAR_URL).cors(context.cors()));
+    }
+  }
+
+  let mut conn = context.records_connection().await?;
+
+  if let Some(name) = name {
+    // Uniqueness is enforced by the database, so two users can't claim the same name at once.
+    let renamed = query_file!(
+      "src/routes/users/data-store/update-user-name.sql",
+      uid,
+      name
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(errors::humanize_error);
+
+    if let Err(error) = renamed {
+      return match errors::is_conflict(&error) {
+        true => Ok(Response::conflict(NAME_TAKEN).cors(context.cors())),
+        false => Err(error),
+      };
+    }
+
+    info!("user '{}' changed name to '{}'", uid, name);
+  }
+
+  if let Some(url) = payload.avatar_url {
+    query_file!("src/routes/users/data-store/delete-user-avatar.sql", uid)
+      .execute(&mut conn)
+      .await
+      .map_err(errors::humanize_error)?;
+
+    query_file!(
+      "src/routes/users/data-store/update-user-avatar-url.sql",
+      uid,
+      url
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?;
+  }
+
+  me(context).await
+}
+
+// Route
+// PUT /users/me/avatar
+//
+// Stores the raw image sent in the body as the avatar of the current user. The avatar url of the
+// user points at the image and changes with each upload so clients don't show a stale copy.
+pub async fn upload_avatar<R>(context: &Context, reader: &mut R) -> Result<Response>
+where
+  R: AsyncRead + Unpin,
+{
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  if context.pending() > MAX_AVATAR_SIZE {
+    return Ok(Response::bad_request(AVATAR_TOO_LARGE).cors(context.cors()));
+  }
+
+  let image = read_size_async(reader, context.pending()).await?;
+  store_avatar(context, uid, image).await
+}
+
+async fn store_avatar(context: &Context, uid: &str, image: Vec<u8>) -> Result<Response> {
+  let content_type = match image_content_type(&image) {
+    Some(content_type) => content_type,
+    None => return Ok(Response::bad_request(INVALID_AVATAR).cors(context.cors())),
+  };
+
+  let mut conn = context.records_connection().await?;
+
+  let updated = query_file!(
+    "src/routes/users/data-store/upsert-user-avatar.sql",
+    uid,
+    content_type,
+    image
+  )
+  .fetch_all(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?
+  .into_iter()
+  .next()
+  .and_then(|row| row.updated_at)
+  .ok_or_else(|| errors::e("Unable to store avatar"))?;
+
+  let url = format!("/users/{}/avatar?v={}", uid, updated.timestamp_millis());
+  query_file!(
+    "src/routes/users/data-store/update-user-avatar-url.sql",
+    uid,
+    url
+  )
+  .execute(&mut conn)
+  .await
+  .map_err(errors::humanize_error)?;
+
+  info!("user '{}' uploaded {} avatar", uid, content_type);
+  me(context).await
+}
+
+// Route
+// GET /users/{id}/avatar
+//
+// Uploaded avatars are public so that they can be used directly as image sources.
+pub async fn avatar(context: &Context, user_id: &str) -> Result<Response> {
+  let mut conn = context.records_connection().await?;
+
+  let avatar = query_file!("src/routes/users/data-store/load-user-avatar.sql", user_id)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .next();
+
+  match avatar {
+    Some(row) => Ok(Response::ok_bytes(&row.content_type, row.image).cors(context.cors())),
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+async fn export_for_user(
+  context: &Context,
+  uid: &str,
+) -> Result<Option<interchange::http::UserDataExport>> {
+  let profile = match profile_for_user(context, uid).await? {
+    Some(profile) => profile,
+    None => return Ok(None),
+  };
+
+  let mut conn = context.records_connection().await?;
+
+  let lobbies = query_file!("src/routes/users/data-store/export-lobbies.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| interchange::http::ExportedLobby {
+      id: row.lobby_id,
+      name: row.lobby_name,
+      invited_by: row.invited_by,
+      joined: row.joined_at,
+      left: row.left_at,
+    })
+    .collect();
+
+  let entries = query_file!("src/routes/users/data-store/export-entries.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| interchange::http::ExportedEntry {
+      id: row.entry_id,
+      game_id: row.game_id,
+      round_id: row.round_id,
+      prompt: row.prompt,
+      entry: row.entry,
+      auto: row.auto.unwrap_or_default(),
+      hidden: row.hidden_at.is_some(),
+      created: row.created_at,
+    })
+    .collect();
+
+  let votes = query_file!("src/routes/users/data-store/export-votes.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| interchange::http::ExportedVote {
+      id: row.vote_id,
+      game_id: row.game_id,
+      round_id: row.round_id,
+      entry_id: row.entry_id,
+      created: row.created_at,
+    })
+    .collect();
+
+  let placements = query_file!("src/routes/users/data-store/export-placements.sql", uid)
+    .fetch_all(&mut conn)
+    .await
+    .map_err(errors::humanize_error)?
+    .into_iter()
+    .map(|row| interchange::http::ExportedPlacement {
+      game_id: row.game_id,
+      game_name: row.game_name,
+      place: row.place,
+      vote_count: row.vote_count,
+      created: row.created_at,
+    })
+    .collect();
+
+  Ok(Some(interchange::http::UserDataExport {
+    exported: Utc::now(),
+    profile,
+    identities: identities::identities_for_user(context, uid).await?,
+    achievements: achievements_for_user(context, uid).await?,
+    lobbies,
+    entries,
+    votes,
+    placements,
+  }))
+}
+
+// Route
+// GET /users/me/export
+//
+// Collects everything stored about the current user into a single json document.
+pub async fn export(context: &Context) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  info!("exporting data for user '{}'", uid);
+
+  match export_for_user(context, uid).await? {
+    Some(export) => Response::ok_json(export).map(|r| r.cors(context.cors())),
+    None => Ok(Response::not_found().cors(context.cors())),
+  }
+}
+
+// Route
+// DELETE /users/me
+//
+// Signs the user out everywhere and queues a job to anonymize the account. Rows referencing the
+// user are kept so that games they played in remain intact for everyone else.
+pub async fn destroy(context: &Context) -> Result<Response> {
+  let uid = match context.authority() {
+    Authority::User { id, .. } => id,
+    Authority::None => return Ok(Response::unauthorized().cors(context.cors())),
+  };
+
+  let revoked = context.session().revoke_all(uid).await?;
+
+  let job_id = context
+    .jobs()
+    .queue(&interchange::jobs::Job::AnonymizeUser(
+      interchange::jobs::AnonymizeUser {
+        user_id: uid.clone(),
+        result: None,
+      },
+    ))
+    .await?;
+
+  info!(
+    "user '{}' deleted account, revoked {} sessions (job '{}')",
+    uid, revoked, job_id
+  );
+
+  Response::ok_json(interchange::http::JobHandle {
+    id: job_id,
+    result: None,
+  })
+  .map(|r| r.cors(context.cors()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::{
+    avatar, export_for_user, image_content_type, profile_for_user, stats_for_user, store_avatar,
+    update_profile, valid_name, ProfilePayload,
+  };
+  use crate::context::test_helpers as context_helpers;
+  use async_std::task::block_on;
+
+  const PNG: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+  fn payload(body: &str) -> ProfilePayload {
+    serde_json::from_str(body).unwrap()
+  }
+
+  #[test]
+  fn name_rules() {
+    assert_eq!(valid_name("  alice "), Some("alice"));
+    assert_eq!(valid_name("   "), None);
+    assert_eq!(valid_name("tab\tname"), None);
+    assert_eq!(valid_name(&"a".repeat(33)), None);
+    assert_eq!(image_content_type(&PNG), Some("image/png"));
+    assert_eq!(image_content_type(b"<svg>"), None);
+  }
+
+  #[test]
+  fn update_name_and_avatar_url() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.users.update_name_and_avatar_url").await;
+      let (other, _) = context_helpers::with_user_by_name("routes.users.name_taken").await;
+
+      let response = update_profile(&ctx, &user_id, payload(r#"{"name":" "}"#))
+        .await
+        .unwrap();
+      assert!(format!("{}", response).contains("errors.users.invalid_name"));
+
+      let body = r#"{"name":"ROUTES.USERS.NAME_TAKEN"}"#;
+      let response = update_profile(&ctx, &user_id, payload(body)).await.unwrap();
+      assert!(format!("{}", response).contains("errors.users.name_taken"));
+
+      let body = r#"{"avatar_url":"http://example.com/a.png"}"#;
+      let response = update_profile(&ctx, &user_id, payload(body)).await.unwrap();
+      assert!(format!("{}", response).contains("errors.users.invalid_avatar_url"));
+
+      let body = r#"{"name":"renamed","avatar_url":"https://example.com/a.png"}"#;
+      update_profile(&ctx, &user_id, payload(body)).await.unwrap();
+      let profile = profile_for_user(&ctx, &user_id).await.unwrap().unwrap();
+      assert_eq!(profile.name, "renamed");
+      assert_eq!(
+        profile.avatar_url.as_deref(),
+        Some("https://example.com/a.png")
+      );
+
+      // Leaving the avatar out keeps it, while null removes it.
+      update_profile(&ctx, &user_id, payload(r#"{"name":"renamed"}"#))
+        .await
+        .unwrap();
+      let profile = profile_for_user(&ctx, &user_id).await.unwrap().unwrap();
+      assert!(profile.avatar_url.is_some());
+      update_profile(&ctx, &user_id, payload(r#"{"avatar_url":null}"#))
+        .await
+        .unwrap();
+      let profile = profile_for_user(&ctx, &user_id).await.unwrap().unwrap();
+      assert_eq!(profile.avatar_url, None);
+
+      context_helpers::cleanup(&ctx).await;
+      context_helpers::cleanup(&other).await;
+    });
+  }
+
+  #[test]
+  fn upload_and_serve_avatar() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.users.upload_and_serve_avatar").await;
+
+      let response = store_avatar(&ctx, &user_id, b"not an image".to_vec())
+        .await
+        .unwrap();
+      assert!(format!("{}", response).contains("errors.users.invalid_avatar"));
+
+      store_avatar(&ctx, &user_id, PNG.to_vec()).await.unwrap();
+      let profile = profile_for_user(&ctx, &user_id).await.unwrap().unwrap();
+      let url = profile.avatar_url.unwrap();
+      assert!(url.starts_with(&format!("/users/{}/avatar?v=", user_id)));
+
+      let response = avatar(&ctx, &user_id).await.unwrap();
+      assert!(format!("{}", response).contains("content-type: image/png"));
+      assert!(response.to_bytes().ends_with(&PNG));
+
+      update_profile(&ctx, &user_id, payload(r#"{"avatar_url":null}"#))
+        .await
+        .unwrap();
+      let response = avatar(&ctx, &user_id).await.unwrap();
+      assert!(format!("{}", response).starts_with("HTTP/1.1 404"));
+
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn stats_for_new_user() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.users.stats_for_new_user").await;
+
+      let stats = stats_for_user(&ctx, &user_id)
+        .await
+        .expect("unable to load")
+        .expect("missing stats");
+
+      assert_eq!(stats.games_played, 0);
+      assert_eq!(stats.wins, 0);
+      assert_eq!(stats.average_place, None);
+      assert_eq!(stats.rating, 1500.0);
+      assert!(stats.best_round.is_none());
+
+      let missing = stats_for_user(&ctx, "bogus").await;
+      assert!(missing.unwrap().is_none());
+
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+
+  #[test]
+  fn export_includes_lobbies() {
+    block_on(async {
+      let (ctx, user_id) =
+        context_helpers::with_user_by_name("routes.users.export_includes_lobbies").await;
+      let lobby_id = crate::bg::handlers::lobbies::make_lobby(
+        ctx.records(),
+        &String::from("routes.users.export_includes_lobbies"),
+        &user_id,
+      )
+      .await
+      .expect("unable to create lobby");
+
+      let export = export_for_user(&ctx, &user_id).await.unwrap().unwrap();
+      assert_eq!(export.profile.id, user_id);
+      assert_eq!(export.lobbies.len(), 1);
+      assert_eq!(export.lobbies[0].id, lobby_id);
+      assert!(export.entries.is_empty());
+      assert!(export.votes.is_empty());
+
+      assert!(export_for_user(&ctx, "bogus").await.unwrap().is_none());
+
+      crate::test_helpers::cleanup_lobby(&ctx, &lobby_id).await;
+      context_helpers::cleanup(&ctx).await;
+    });
+  }
+}
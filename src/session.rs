@@ -1,20 +1,183 @@
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_std::net::TcpStream;
 use async_std::sync::RwLock;
 
-use jsonwebtoken::{encode, EncodingKey, Header};
-use kramer::{execute, Arity, Command, Insertion, StringCommand};
-use log::{info, trace, warn};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::errors::ErrorKind as TokenErrorKind;
+use jsonwebtoken::{
+  dangerous_insecure_decode, decode, encode, DecodingKey, EncodingKey, Header, Validation,
+};
+use kramer::{execute, Arity, Command, HashCommand, Insertion, StringCommand};
+use log::{debug, info, trace, warn};
+use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::configuration::Configuration;
+use crate::constants::{
+  DEFAULT_REFRESH_TIMEOUT, DEFAULT_SESSION_ISSUER, OAUTH_STATE_TIMEOUT, SESSION_TOUCH_INTERVAL,
+  TOKEN_LEEWAY,
+};
+use crate::errors;
+
+const REFRESH_PREFIX: &str = "refresh";
+const USER_PREFIX: &str = "user";
+const OAUTH_PREFIX: &str = "oauth";
+const SEEN_SUFFIX: &str = "seen";
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SessionClaims {
   uid: String,
+  #[serde(default)]
+  sid: Option<String>,
   created: SystemTime,
+  #[serde(default)]
+  iss: Option<String>,
+  #[serde(default)]
+  exp: u64,
+}
+
+// The reasons a token can be turned away before it is ever looked up in the session store.
+#[derive(Debug, PartialEq)]
+pub enum TokenRejection {
+  Malformed,
+  Signature,
+  Expired,
+  Issuer,
+  Created,
+  Unknown,
+}
+
+#[derive(Debug, Default)]
+struct TokenRejections {
+  malformed: AtomicU64,
+  signature: AtomicU64,
+  expired: AtomicU64,
+  issuer: AtomicU64,
+  created: AtomicU64,
+  unknown: AtomicU64,
+}
+
+// A snapshot of the number of tokens rejected for each reason since the session store was opened.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TokenRejectionCounts {
+  pub malformed: u64,
+  pub signature: u64,
+  pub expired: u64,
+  pub issuer: u64,
+  pub created: u64,
+  pub unknown: u64,
+}
+
+impl TokenRejections {
+  fn record(&self, rejection: &TokenRejection) {
+    let counter = match rejection {
+      TokenRejection::Malformed => &self.malformed,
+      TokenRejection::Signature => &self.signature,
+      TokenRejection::Expired => &self.expired,
+      TokenRejection::Issuer => &self.issuer,
+      TokenRejection::Created => &self.created,
+      TokenRejection::Unknown => &self.unknown,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+  }
+
+  fn counts(&self) -> TokenRejectionCounts {
+    TokenRejectionCounts {
+      malformed: self.malformed.load(Ordering::Relaxed),
+      signature: self.signature.load(Ordering::Relaxed),
+      expired: self.expired.load(Ordering::Relaxed),
+      issuer: self.issuer.load(Ordering::Relaxed),
+      created: self.created.load(Ordering::Relaxed),
+      unknown: self.unknown.load(Ordering::Relaxed),
+    }
+  }
+}
+
+// The value stored behind a refresh token; enough to find the session record being refreshed.
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshClaims {
+  uid: String,
+  sid: String,
+}
+
+// A single sign-in for a user. The token and refresh token rotate each time the session is
+// refreshed, while the id and creation time stay the same.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionRecord {
+  pub id: String,
+  pub token: String,
+  pub refresh_token: String,
+  pub created: DateTime<Utc>,
+  pub last_seen: DateTime<Utc>,
+  pub user_agent: Option<String>,
+}
+
+// Generates an opaque, url-safe token from 32 random bytes.
+pub fn random_token() -> String {
+  thread_rng()
+    .gen::<[u8; 32]>()
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
+
+// The claims signed into the `state` parameter of an oauth redirect. The nonce is the key of the
+// PKCE code verifier held in the session store until the callback is received from the provider.
+#[derive(Debug, Serialize, Deserialize)]
+struct OAuthStateClaims {
+  nonce: String,
+  provider: String,
+  #[serde(default)]
+  browser: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  link: Option<String>,
+  exp: u64,
+}
+
+// An oauth flow that has been started; the state and challenge are sent along with the redirect.
+#[derive(Debug, PartialEq)]
+// The browser value is sent as a cookie, and has to accompany the state back to the callback.
+pub struct OAuthState {
+  pub state: String,
+  pub code_challenge: String,
+  pub browser: String,
+}
+
+// The result of exchanging a valid state during an oauth callback. When `link` is present the flow
+// was started by that user to attach another identity to their account rather than to log in.
+#[derive(Debug, PartialEq)]
+pub struct OAuthFlow {
+  pub verifier: String,
+  pub link: Option<String>,
+}
+
+// States only carry a digest of the browser value, since they pass through the provider in urls.
+fn browser_binding(browser: &str) -> String {
+  let digest = Sha256::digest(format!("oauth:{}", browser).as_bytes());
+  base64::encode_config(digest, base64::URL_SAFE_NO_PAD)
+}
+
+// PKCE challenges are the unpadded, url-safe base64 encoding of the verifier's sha256 digest.
+pub fn code_challenge(verifier: &str) -> String {
+  base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+// Cookie sessions are paired with a token the client must echo back in a header for any request
+// that can change something. It is derived from the session token so that it needs no storage.
+pub fn csrf_token(token: &str) -> String {
+  let digest = Sha256::digest(format!("csrf:{}", token).as_bytes());
+  base64::encode_config(digest, base64::URL_SAFE_NO_PAD)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SessionTokens {
+  pub token: String,
+  pub refresh_token: String,
 }
 
 fn lookup_command<S: std::fmt::Display>(prefix: S, key: &String) -> StringCommand<String, String> {
@@ -25,12 +188,23 @@ fn destroy_command<S: std::fmt::Display>(prefix: S, key: &String) -> Command<Str
   Command::Del::<_, String>(Arity::One(format!("{}:{}", prefix, key)))
 }
 
+// Returns the session id embedded in a token's claims. The claims are not verified here; callers
+// are expected to have already exchanged the token with the session store.
+pub fn session_id(token: &str) -> Option<String> {
+  dangerous_insecure_decode::<SessionClaims>(token)
+    .ok()
+    .and_then(|data| data.claims.sid)
+}
+
 pub struct Session {
   _stream: RwLock<TcpStream>,
-  _secret: String,
   _encoding_key: EncodingKey,
+  _decoding_keys: Vec<DecodingKey<'static>>,
+  _issuer: String,
+  _rejections: TokenRejections,
   _session_prefix: String,
   _expiration_timeout: Option<Duration>,
+  _refresh_timeout: Duration,
 }
 
 impl Session {
@@ -46,23 +220,101 @@ impl Session {
     );
     let key = EncodingKey::from_secret(configuration.session_store.secret.as_bytes());
 
+    // Tokens are always signed with the current secret, while any previous secrets are still
+    // accepted so that rotating the secret does not sign everyone out at once.
+    let decoding_keys = std::iter::once(&configuration.session_store.secret)
+      .chain(configuration.session_store.previous_secrets.iter())
+      .map(|secret| DecodingKey::from_secret(secret.as_bytes()).into_static())
+      .collect();
+
     Ok(Session {
       _stream: RwLock::new(stream),
       _session_prefix: configuration.session_store.session_prefix.clone(),
-      _secret: configuration.session_store.secret.clone(),
+      _decoding_keys: decoding_keys,
+      _issuer: configuration
+        .session_store
+        .issuer
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SESSION_ISSUER.to_string()),
+      _rejections: TokenRejections::default(),
       _expiration_timeout: configuration
         .session_store
         .expiration_timeout
         .map(|secs| Duration::from_secs(secs)),
+      _refresh_timeout: Duration::from_secs(
+        configuration
+          .session_store
+          .refresh_timeout
+          .unwrap_or(DEFAULT_REFRESH_TIMEOUT),
+      ),
       _encoding_key: key,
     })
   }
 
+  async fn command<C>(&self, command: C) -> Result<kramer::Response, Error>
+  where
+    C: std::fmt::Display,
+  {
+    let mut stream = self._stream.write().await;
+    execute(&mut (*stream), command).await
+  }
+
+  fn index_key<S: std::fmt::Display>(&self, id: S) -> String {
+    format!("{}:{}:{}", self._session_prefix, USER_PREFIX, id)
+  }
+
+  // Last seen times live in their own hash so touching a session never rewrites its record, which
+  // would race with a refresh rotating the tokens stored there.
+  fn seen_key<S: std::fmt::Display>(&self, id: S) -> String {
+    format!("{}:{}", self.index_key(id), SEEN_SUFFIX)
+  }
+
+  async fn last_seen(&self, uid: &str) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+    let lookup = Command::Hashes::<_, &str>(HashCommand::Get(self.seen_key(uid), None));
+
+    let values = match self.command(lookup).await? {
+      kramer::Response::Array(values) => values,
+      _ => return Ok(HashMap::new()),
+    };
+
+    let mut seen = HashMap::with_capacity(values.len() / 2);
+    let mut values = values.into_iter();
+
+    while let (Some(sid), Some(time)) = (values.next(), values.next()) {
+      if let (kramer::ResponseValue::String(sid), kramer::ResponseValue::String(time)) = (sid, time)
+      {
+        if let Ok(time) = DateTime::parse_from_rfc3339(&time) {
+          seen.insert(sid, time.with_timezone(&Utc));
+        }
+      }
+    }
+
+    Ok(seen)
+  }
+
+  // Removes the session from both the user's index and the last seen times.
+  async fn unindex(&self, uid: &str, sid: &str) -> Result<(), Error> {
+    for key in [self.index_key(uid), self.seen_key(uid)].iter() {
+      let removal =
+        Command::Hashes::<_, &str>(HashCommand::Del(key.clone(), Arity::One(sid.to_string())));
+      self.command(removal).await?;
+    }
+
+    Ok(())
+  }
+
+  // Removes the token from the session store, along with its entry in the user's session index.
   pub async fn destroy(&self, key: &String) -> Result<(), Error> {
     info!("removing key {}", key);
+
+    if let Ok(data) = dangerous_insecure_decode::<SessionClaims>(key) {
+      if let Some(sid) = data.claims.sid {
+        self.revoke(&data.claims.uid, &sid).await?;
+      }
+    }
+
     let des = destroy_command(&self._session_prefix, key);
-    let mut stream = self._stream.write().await;
-    match execute(&mut (*stream), des).await? {
+    match self.command(des).await? {
       kramer::Response::Item(kramer::ResponseValue::Integer(1)) => Ok(()),
       kramer::Response::Item(kramer::ResponseValue::Integer(0)) => {
         info!("unable to find session");
@@ -75,13 +327,116 @@ impl Session {
     }
   }
 
+  // Removes a single-use key, returning true only when this call is the one that removed it. Keys
+  // are read before they are claimed, so two requests may both find the same key; only the one that
+  // claims it may act on it.
+  async fn claim(&self, key: &String) -> Result<bool, Error> {
+    match self
+      .command(destroy_command(&self._session_prefix, key))
+      .await?
+    {
+      kramer::Response::Item(kramer::ResponseValue::Integer(1)) => Ok(true),
+      kramer::Response::Item(kramer::ResponseValue::Integer(0)) => Ok(false),
+      r => Err(errors::e(format!(
+        "Unable to claim key '{}' - {:?}",
+        key, r
+      ))),
+    }
+  }
+
+  pub fn refresh_timeout(&self) -> Duration {
+    self._refresh_timeout
+  }
+
+  pub fn rejections(&self) -> TokenRejectionCounts {
+    self._rejections.counts()
+  }
+
+  // Tokens issued before expiry and issuer claims were added carry neither. They are still honoured
+  // for as long as a refresh token issued alongside them would have been, and only while their
+  // session remains in the store.
+  fn legacy_claims(&self, token: &str, key: &DecodingKey) -> Option<SessionClaims> {
+    let validation = Validation {
+      leeway: TOKEN_LEEWAY,
+      validate_exp: false,
+      ..Validation::default()
+    };
+
+    let claims = decode::<SessionClaims>(token, key, &validation)
+      .ok()?
+      .claims;
+
+    match claims.iss.is_none() && claims.exp == 0 {
+      true if claims.created + self._refresh_timeout > SystemTime::now() => {
+        debug!("accepting legacy token for user '{}'", claims.uid);
+        Some(claims)
+      }
+      _ => None,
+    }
+  }
+
+  // Checks the signature of the token against every accepted secret before validating the expiry,
+  // issuer and creation time of its claims.
+  fn verify(&self, token: &str) -> Result<SessionClaims, TokenRejection> {
+    let validation = Validation {
+      leeway: TOKEN_LEEWAY,
+      iss: Some(self._issuer.clone()),
+      ..Validation::default()
+    };
+
+    let mut rejection = TokenRejection::Signature;
+
+    for key in &self._decoding_keys {
+      let claims = match decode::<SessionClaims>(token, key, &validation) {
+        Ok(data) => data.claims,
+        Err(e) => {
+          rejection = match e.kind() {
+            TokenErrorKind::InvalidSignature => continue,
+            TokenErrorKind::ExpiredSignature => TokenRejection::Expired,
+            TokenErrorKind::InvalidIssuer => TokenRejection::Issuer,
+            TokenErrorKind::InvalidToken
+            | TokenErrorKind::InvalidAlgorithm
+            | TokenErrorKind::Base64(_)
+            | TokenErrorKind::Json(_)
+            | TokenErrorKind::Utf8(_) => TokenRejection::Malformed,
+            _ => TokenRejection::Unknown,
+          };
+
+          match rejection {
+            TokenRejection::Expired | TokenRejection::Issuer => {
+              match self.legacy_claims(token, key) {
+                Some(claims) => claims,
+                None => break,
+              }
+            }
+            _ => break,
+          }
+        }
+      };
+
+      let limit = SystemTime::now() + Duration::from_secs(TOKEN_LEEWAY);
+
+      return match claims.created <= limit {
+        true => Ok(claims),
+        false => Err(TokenRejection::Created),
+      };
+    }
+
+    Err(rejection)
+  }
+
   pub async fn get(&self, key: &String) -> Result<String, Error> {
+    let claims = self.verify(key).map_err(|rejection| {
+      warn!("rejected session token - {:?}", rejection);
+      self._rejections.record(&rejection);
+      errors::e(format!("Invalid token ({:?})", rejection))
+    })?;
+
     let lookup = lookup_command(&self._session_prefix, key);
     trace!("writing command {} to redis connection", lookup);
-    let mut stream = self._stream.write().await;
 
-    match execute(&mut (*stream), lookup).await? {
-      kramer::Response::Item(kramer::ResponseValue::String(id)) => Ok(id),
+    match self.command(lookup).await? {
+      kramer::Response::Item(kramer::ResponseValue::String(id)) if id == claims.uid => Ok(id),
       r => {
         warn!("strange response from session lookup - {:?}", r);
         Err(Error::new(
@@ -92,13 +447,58 @@ impl Session {
     }
   }
 
-  pub async fn create<S>(&self, id: S) -> Result<String, Error>
+  async fn record(&self, uid: &str, sid: &str) -> Result<Option<SessionRecord>, Error> {
+    let lookup = Command::Hashes::<_, &str>(HashCommand::Get(
+      self.index_key(uid),
+      Some(Arity::One(sid.to_string())),
+    ));
+
+    let mut record = match self.command(lookup).await? {
+      kramer::Response::Item(kramer::ResponseValue::String(serialized)) => {
+        serde_json::from_str::<SessionRecord>(&serialized)?
+      }
+      _ => return Ok(None),
+    };
+
+    if let Some(seen) = self.last_seen(uid).await?.remove(sid) {
+      record.last_seen = record.last_seen.max(seen);
+    }
+
+    Ok(Some(record))
+  }
+
+  async fn store_record(&self, uid: &str, record: &SessionRecord) -> Result<(), Error> {
+    let serialized = serde_json::to_string(record)?;
+    let insert = Command::Hashes(HashCommand::Set(
+      self.index_key(uid),
+      Arity::One((record.id.clone(), serialized)),
+      Insertion::Always,
+    ));
+    self.command(insert).await.map(|_| ())
+  }
+
+  // Writes a fresh token and refresh token for the session, recording both in the user's index.
+  async fn issue<S>(
+    &self,
+    id: S,
+    sid: String,
+    created: DateTime<Utc>,
+    user_agent: Option<String>,
+  ) -> Result<SessionTokens, Error>
   where
     S: std::fmt::Display,
   {
+    let issued = SystemTime::now();
+    let exp = (issued + self._refresh_timeout)
+      .duration_since(UNIX_EPOCH)
+      .map_err(errors::e)?
+      .as_secs();
     let claims = SessionClaims {
       uid: format!("{}", id),
-      created: SystemTime::now(),
+      sid: Some(sid.clone()),
+      created: issued,
+      iss: Some(self._issuer.clone()),
+      exp,
     };
 
     let token = encode(&Header::default(), &claims, &self._encoding_key)
@@ -110,12 +510,695 @@ impl Session {
       self._expiration_timeout,
       Insertion::Always,
     );
-    let mut stream = self._stream.write().await;
-    execute(&mut (*stream), insert).await?;
+    self.command(insert).await?;
+
+    let refresh_token = random_token();
+    let refresh_claims = serde_json::to_string(&RefreshClaims {
+      uid: claims.uid.clone(),
+      sid: sid.clone(),
+    })?;
+    let key = format!(
+      "{}:{}:{}",
+      self._session_prefix, REFRESH_PREFIX, refresh_token
+    );
+    let insert = StringCommand::Set(
+      Arity::One((&key, &refresh_claims)),
+      Some(self._refresh_timeout),
+      Insertion::Always,
+    );
+    self.command(insert).await?;
+
+    let record = SessionRecord {
+      id: sid,
+      token: token.clone(),
+      refresh_token: refresh_token.clone(),
+      created,
+      last_seen: Utc::now(),
+      user_agent,
+    };
+    self.store_record(&claims.uid, &record).await?;
+
     info!(
       "creating session for user id: {} (timeout: {:?})",
       id, self._expiration_timeout
     );
-    Ok(token)
+
+    Ok(SessionTokens {
+      token,
+      refresh_token,
+    })
+  }
+
+  // Creates a new session along with a long-lived refresh token that can be exchanged for another
+  // token once the original has expired.
+  pub async fn create<S>(&self, id: S, user_agent: Option<&str>) -> Result<SessionTokens, Error>
+  where
+    S: std::fmt::Display,
+  {
+    let sid = uuid::Uuid::new_v4().to_string();
+    self
+      .issue(id, sid, Utc::now(), user_agent.map(String::from))
+      .await
+  }
+
+  // Sessions with an expiration timeout slide forward on authenticated requests; the key is only
+  // re-written if it still exists so expired sessions are never revived. Sessions that were seen
+  // within the touch interval are left alone, so most requests only read the session record.
+  pub async fn touch<S>(&self, key: &String, id: S) -> Result<(), Error>
+  where
+    S: std::fmt::Display,
+  {
+    let uid = format!("{}", id);
+    let record = match session_id(key) {
+      Some(sid) => self.record(&uid, &sid).await?,
+      None => None,
+    };
+
+    // Short expiration timeouts are extended at least twice within their lifetime.
+    let interval = self
+      ._expiration_timeout
+      .map_or(SESSION_TOUCH_INTERVAL, |timeout| timeout.as_secs() / 2)
+      .min(SESSION_TOUCH_INTERVAL);
+    let last_seen = Utc::now();
+
+    if let Some(record) = &record {
+      if last_seen.signed_duration_since(record.last_seen)
+        < chrono::Duration::seconds(interval as i64)
+      {
+        trace!("session '{}' recently seen, skipping touch", record.id);
+        return Ok(());
+      }
+    }
+
+    if let Some(timeout) = self._expiration_timeout {
+      let token_key = format!("{}:{}", self._session_prefix, key);
+      let insert = StringCommand::Set(
+        Arity::One((&token_key, &id)),
+        Some(timeout),
+        Insertion::IfExists,
+      );
+      self.command(insert).await?;
+      debug!(
+        "extended session for user id: {} (timeout: {:?})",
+        id, timeout
+      );
+    }
+
+    match record {
+      Some(record) => {
+        let insert = Command::Hashes(HashCommand::Set(
+          self.seen_key(&uid),
+          Arity::One((record.id, last_seen.to_rfc3339())),
+          Insertion::Always,
+        ));
+        self.command(insert).await.map(|_| ())
+      }
+      None => {
+        debug!("no session record for user '{}'", uid);
+        Ok(())
+      }
+    }
+  }
+
+  // Exchanges a refresh token for a new token on the same session. Refresh tokens are single use;
+  // the token provided is removed and a new one is issued alongside the session. When the same
+  // refresh token is used concurrently, only one of the requests receives new tokens.
+  pub async fn refresh(&self, refresh_token: &String) -> Result<Option<SessionTokens>, Error> {
+    let key = format!("{}:{}", REFRESH_PREFIX, refresh_token);
+    let lookup = lookup_command(&self._session_prefix, &key);
+
+    let claims = match self.command(lookup).await? {
+      kramer::Response::Item(kramer::ResponseValue::String(serialized)) => {
+        serde_json::from_str::<RefreshClaims>(&serialized)?
+      }
+      _ => return Ok(None),
+    };
+
+    if !self.claim(&key).await? {
+      warn!("refresh token for session '{}' already used", claims.sid);
+      return Ok(None);
+    }
+
+    let record = match self.record(&claims.uid, &claims.sid).await? {
+      Some(record) => record,
+      None => {
+        warn!("refresh token for revoked session '{}'", claims.sid);
+        return Ok(None);
+      }
+    };
+
+    self
+      .command(destroy_command(&self._session_prefix, &record.token))
+      .await?;
+
+    info!("refreshing session for user id: {}", claims.uid);
+    self
+      .issue(&claims.uid, record.id, record.created, record.user_agent)
+      .await
+      .map(Some)
+  }
+
+  // Lists the sessions that can still be used, either directly or by refreshing. Records whose
+  // tokens have both expired are removed from the index along the way.
+  pub async fn list(&self, uid: &String) -> Result<Vec<SessionRecord>, Error> {
+    let lookup = Command::Hashes::<_, &str>(HashCommand::Get(self.index_key(uid), None));
+
+    let values = match self.command(lookup).await? {
+      kramer::Response::Array(values) => values,
+      _ => return Ok(vec![]),
+    };
+
+    let mut seen = self.last_seen(uid).await?;
+    let mut sessions = Vec::with_capacity(values.len() / 2);
+
+    for value in values.into_iter().skip(1).step_by(2) {
+      let mut record = match value {
+        kramer::ResponseValue::String(serialized) => {
+          serde_json::from_str::<SessionRecord>(&serialized)?
+        }
+        _ => continue,
+      };
+
+      if let Some(time) = seen.remove(&record.id) {
+        record.last_seen = record.last_seen.max(time);
+      }
+
+      let exists = Command::Exists::<_, String>(Arity::Many(vec![
+        format!("{}:{}", self._session_prefix, record.token),
+        format!(
+          "{}:{}:{}",
+          self._session_prefix, REFRESH_PREFIX, record.refresh_token
+        ),
+      ]));
+
+      match self.command(exists).await? {
+        kramer::Response::Item(kramer::ResponseValue::Integer(0)) => {
+          debug!("pruning expired session '{}'", record.id);
+          self.unindex(uid, &record.id).await?;
+        }
+        _ => sessions.push(record),
+      }
+    }
+
+    sessions.sort_by_key(|record| std::cmp::Reverse(record.last_seen));
+    Ok(sessions)
+  }
+
+  // Revokes a single session, returning false if the user has no session with the id provided.
+  pub async fn revoke(&self, uid: &String, sid: &String) -> Result<bool, Error> {
+    let record = match self.record(uid, sid).await? {
+      Some(record) => record,
+      None => return Ok(false),
+    };
+
+    let refresh_key = format!("{}:{}", REFRESH_PREFIX, record.refresh_token);
+    self
+      .command(destroy_command(&self._session_prefix, &record.token))
+      .await?;
+    self
+      .command(destroy_command(&self._session_prefix, &refresh_key))
+      .await?;
+
+    self.unindex(uid, sid).await?;
+
+    info!("revoked session '{}' for user '{}'", sid, uid);
+    Ok(true)
+  }
+
+  // Revokes every session belonging to the user, returning the number of sessions removed.
+  pub async fn revoke_all(&self, uid: &String) -> Result<usize, Error> {
+    let sessions = self.list(uid).await?;
+    let mut count = 0;
+
+    for session in &sessions {
+      if self.revoke(uid, &session.id).await? {
+        count += 1;
+      }
+    }
+
+    Ok(count)
+  }
+
+  // Starts an oauth flow, storing a fresh PKCE verifier that is only valid for a short time and
+  // returning the signed state that will be needed to retrieve it during the callback, along with
+  // the browser value the callback must be made with.
+  pub async fn create_oauth_state(
+    &self,
+    provider: &str,
+    link: Option<&str>,
+  ) -> Result<OAuthState, Error> {
+    let nonce = random_token();
+    let verifier = random_token();
+    let browser = random_token();
+    let exp = (SystemTime::now() + Duration::from_secs(OAUTH_STATE_TIMEOUT))
+      .duration_since(UNIX_EPOCH)
+      .map_err(errors::e)?
+      .as_secs();
+
+    let state = encode(
+      &Header::default(),
+      &OAuthStateClaims {
+        nonce: nonce.clone(),
+        provider: provider.to_string(),
+        browser: browser_binding(&browser),
+        link: link.map(String::from),
+        exp,
+      },
+      &self._encoding_key,
+    )
+    .map_err(errors::e)?;
+
+    let key = format!("{}:{}:{}", self._session_prefix, OAUTH_PREFIX, nonce);
+    let insert = StringCommand::Set(
+      Arity::One((&key, &verifier)),
+      Some(Duration::from_secs(OAUTH_STATE_TIMEOUT)),
+      Insertion::Always,
+    );
+    self.command(insert).await?;
+
+    Ok(OAuthState {
+      state,
+      code_challenge: code_challenge(&verifier),
+      browser,
+    })
+  }
+
+  // Exchanges the state received in an oauth callback for the PKCE verifier created with it. States
+  // can only be used once, only with the provider they were created for and only by the browser
+  // that started the flow; `None` is returned for forged, expired or previously used states.
+  pub async fn consume_oauth_state(
+    &self,
+    state: &str,
+    provider: &str,
+    browser: Option<&str>,
+  ) -> Result<Option<OAuthFlow>, Error> {
+    let validation = Validation {
+      leeway: TOKEN_LEEWAY,
+      ..Validation::default()
+    };
+
+    let claims = self
+      ._decoding_keys
+      .iter()
+      .find_map(|key| decode::<OAuthStateClaims>(state, key, &validation).ok())
+      .map(|data| data.claims);
+
+    let (nonce, link) = match claims {
+      Some(claims) if claims.provider != provider => {
+        warn!(
+          "oauth state for '{}' used with '{}'",
+          claims.provider, provider
+        );
+        return Ok(None);
+      }
+      Some(claims) if browser.map(browser_binding).as_deref() != Some(claims.browser.as_str()) => {
+        warn!("oauth state '{}' used without its browser", claims.nonce);
+        return Ok(None);
+      }
+      Some(claims) => (claims.nonce, claims.link),
+      None => {
+        warn!("rejected invalid oauth state");
+        return Ok(None);
+      }
+    };
+
+    let key = format!("{}:{}", OAUTH_PREFIX, nonce);
+    let verifier = match self
+      .command(lookup_command(&self._session_prefix, &key))
+      .await?
+    {
+      kramer::Response::Item(kramer::ResponseValue::String(verifier)) => verifier,
+      _ => {
+        warn!("oauth state '{}' expired or already used", nonce);
+        return Ok(None);
+      }
+    };
+
+    if !self.claim(&key).await? {
+      warn!("oauth state '{}' already used", nonce);
+      return Ok(None);
+    }
+
+    Ok(Some(OAuthFlow { verifier, link }))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{
+    code_challenge, random_token, session_id, Session, SessionRecord, TokenRejectionCounts,
+  };
+  use crate::configuration::test_helpers::load_test_config;
+  use async_std::task::{block_on, spawn};
+  use chrono::Utc;
+  use jsonwebtoken::{encode, Header};
+  use kramer::{Arity, Command, HashCommand, Insertion, StringCommand};
+  use serde::Serialize;
+  use std::sync::Arc;
+  use std::time::{Duration, SystemTime};
+
+  #[test]
+  fn random_token_hex() {
+    let token = random_token();
+    assert_eq!(token.len(), 64);
+    assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(token, random_token());
+  }
+
+  #[test]
+  fn refresh_single_use() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let id = String::from("session.refresh_single_use");
+      let tokens = session.create(&id, None).await.unwrap();
+      assert_eq!(session.get(&tokens.token).await.unwrap(), id);
+
+      let refreshed = session
+        .refresh(&tokens.refresh_token)
+        .await
+        .unwrap()
+        .unwrap();
+      assert_eq!(session.get(&refreshed.token).await.unwrap(), id);
+      assert!(session.get(&tokens.token).await.is_err());
+      assert_eq!(session_id(&refreshed.token), session_id(&tokens.token));
+      assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+      assert!(session
+        .refresh(&tokens.refresh_token)
+        .await
+        .unwrap()
+        .is_none());
+
+      session.revoke_all(&id).await.unwrap();
+    });
+  }
+
+  #[test]
+  fn refresh_concurrently() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let id = String::from("session.refresh_concurrently");
+      let session = Session::open(&config).await.unwrap();
+      let tokens = session.create(&id, None).await.unwrap();
+
+      // Each attempt has its own connection, as it would from separate server processes.
+      let mut sessions = Vec::new();
+      for _ in 0..8 {
+        sessions.push(Arc::new(Session::open(&config).await.unwrap()));
+      }
+
+      let attempts = sessions
+        .into_iter()
+        .map(|session| {
+          let refresh_token = tokens.refresh_token.clone();
+          spawn(async move { session.refresh(&refresh_token).await.unwrap() })
+        })
+        .collect::<Vec<_>>();
+
+      let mut refreshed = Vec::new();
+      for attempt in attempts {
+        refreshed.extend(attempt.await);
+      }
+
+      assert_eq!(refreshed.len(), 1);
+      assert_eq!(session.get(&refreshed[0].token).await.unwrap(), id);
+
+      session.revoke_all(&id).await.unwrap();
+    });
+  }
+
+  #[test]
+  fn touch_throttled() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let id = String::from("session.touch_throttled");
+      let tokens = session.create(&id, None).await.unwrap();
+      let created = session.list(&id).await.unwrap().pop().unwrap();
+
+      session.touch(&tokens.token, &id).await.unwrap();
+      let touched = session.list(&id).await.unwrap().pop().unwrap();
+      assert_eq!(touched.last_seen, created.last_seen);
+
+      let stale = Utc::now() - chrono::Duration::minutes(5);
+      session
+        .store_record(
+          &id,
+          &SessionRecord {
+            last_seen: stale,
+            ..created.clone()
+          },
+        )
+        .await
+        .unwrap();
+      session.touch(&tokens.token, &id).await.unwrap();
+      let touched = session.list(&id).await.unwrap().pop().unwrap();
+      assert!(touched.last_seen > stale);
+
+      // The record itself is left alone, so a concurrent refresh can't have its tokens rewritten.
+      let lookup = Command::Hashes::<_, &str>(HashCommand::Get(
+        session.index_key(&id),
+        Some(Arity::One(created.id.clone())),
+      ));
+      let stored = match session.command(lookup).await.unwrap() {
+        kramer::Response::Item(kramer::ResponseValue::String(serialized)) => {
+          serde_json::from_str::<SessionRecord>(&serialized).unwrap()
+        }
+        r => panic!("unexpected response - {:?}", r),
+      };
+      assert_eq!(stored.last_seen, stale);
+      assert_eq!(
+        session
+          .record(&id, &created.id)
+          .await
+          .unwrap()
+          .unwrap()
+          .last_seen,
+        touched.last_seen
+      );
+
+      session.revoke_all(&id).await.unwrap();
+    });
+  }
+
+  #[test]
+  fn list_and_revoke() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let id = String::from("session.list_and_revoke");
+      let first = session.create(&id, Some("first-agent")).await.unwrap();
+      let second = session.create(&id, None).await.unwrap();
+
+      let sessions = session.list(&id).await.unwrap();
+      assert_eq!(sessions.len(), 2);
+
+      let sid = session_id(&first.token).unwrap();
+      assert!(session.revoke(&id, &sid).await.unwrap());
+      assert!(!session.revoke(&id, &sid).await.unwrap());
+      assert!(session.get(&first.token).await.is_err());
+      assert!(session
+        .refresh(&first.refresh_token)
+        .await
+        .unwrap()
+        .is_none());
+
+      let sessions = session.list(&id).await.unwrap();
+      assert_eq!(
+        sessions
+          .iter()
+          .map(|s| s.id.clone())
+          .collect::<Vec<String>>(),
+        vec![session_id(&second.token).unwrap()]
+      );
+
+      assert_eq!(session.revoke_all(&id).await.unwrap(), 1);
+      assert!(session.get(&second.token).await.is_err());
+      assert!(session.list(&id).await.unwrap().is_empty());
+    });
+  }
+
+  #[test]
+  fn verify_tokens() {
+    block_on(async {
+      let mut config = load_test_config().unwrap();
+      let original = Session::open(&config).await.unwrap();
+      let secret = config.session_store.secret.clone();
+      config.session_store.secret = String::from("session.verify_tokens");
+      let unknown = Session::open(&config).await.unwrap();
+      config.session_store.previous_secrets = vec![secret];
+      let rotated = Session::open(&config).await.unwrap();
+      config.session_store.issuer = Some(String::from("elsewhere"));
+      let elsewhere = Session::open(&config).await.unwrap();
+
+      let id = String::from("session.verify_tokens");
+      let tokens = original.create(&id, None).await.unwrap();
+      assert_eq!(rotated.get(&tokens.token).await.unwrap(), id);
+
+      assert!(unknown.get(&tokens.token).await.is_err());
+      assert!(unknown.get(&String::from("not-a-token")).await.is_err());
+      assert!(elsewhere.get(&tokens.token).await.is_err());
+
+      assert_eq!(
+        unknown.rejections(),
+        TokenRejectionCounts {
+          signature: 1,
+          malformed: 1,
+          ..TokenRejectionCounts::default()
+        }
+      );
+      assert_eq!(elsewhere.rejections().issuer, 1);
+      assert_eq!(rotated.rejections(), TokenRejectionCounts::default());
+
+      original.revoke_all(&id).await.unwrap();
+    });
+  }
+
+  #[derive(Serialize)]
+  struct LegacyClaims {
+    uid: String,
+    created: SystemTime,
+  }
+
+  #[test]
+  fn legacy_tokens() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let id = String::from("session.legacy_tokens");
+      let legacy = |created: SystemTime| {
+        let claims = LegacyClaims {
+          uid: id.clone(),
+          created,
+        };
+        encode(&Header::default(), &claims, &session._encoding_key).unwrap()
+      };
+
+      let token = legacy(SystemTime::now());
+      assert!(session.get(&token).await.is_err());
+
+      let key = format!("{}:{}", session._session_prefix, token);
+      let insert = StringCommand::Set(Arity::One((&key, &id)), None, Insertion::Always);
+      session.command(insert).await.unwrap();
+      assert_eq!(session.get(&token).await.unwrap(), id);
+
+      let stale = SystemTime::now() - session.refresh_timeout() - Duration::from_secs(60);
+      let stale = legacy(stale);
+      let key = format!("{}:{}", session._session_prefix, stale);
+      let insert = StringCommand::Set(Arity::One((&key, &id)), None, Insertion::Always);
+      session.command(insert).await.unwrap();
+      assert!(session.get(&stale).await.is_err());
+      assert_eq!(session.rejections().expired, 1);
+
+      session.destroy(&token).await.unwrap();
+      session.destroy(&stale).await.unwrap();
+    });
+  }
+
+  #[test]
+  fn pkce_challenge() {
+    // Example verifier and challenge from RFC 7636, appendix B.
+    assert_eq!(
+      code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+      "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+    );
+  }
+
+  #[test]
+  fn oauth_state_single_use() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let state = session.create_oauth_state("google", None).await.unwrap();
+
+      assert!(session
+        .consume_oauth_state(&state.state, "github", Some(&state.browser))
+        .await
+        .unwrap()
+        .is_none());
+
+      // States are only accepted from the browser that started the flow.
+      assert!(session
+        .consume_oauth_state(&state.state, "google", None)
+        .await
+        .unwrap()
+        .is_none());
+      assert!(session
+        .consume_oauth_state(&state.state, "google", Some(&random_token()))
+        .await
+        .unwrap()
+        .is_none());
+
+      let flow = session
+        .consume_oauth_state(&state.state, "google", Some(&state.browser))
+        .await
+        .unwrap()
+        .unwrap();
+      assert_eq!(code_challenge(&flow.verifier), state.code_challenge);
+      assert_eq!(flow.link, None);
+      assert!(session
+        .consume_oauth_state(&state.state, "google", Some(&state.browser))
+        .await
+        .unwrap()
+        .is_none());
+      assert!(session
+        .consume_oauth_state("not-a-state", "google", Some(&state.browser))
+        .await
+        .unwrap()
+        .is_none());
+    });
+  }
+
+  #[test]
+  fn oauth_state_consumed_concurrently() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let state = session.create_oauth_state("google", None).await.unwrap();
+
+      let mut sessions = Vec::new();
+      for _ in 0..8 {
+        sessions.push(Arc::new(Session::open(&config).await.unwrap()));
+      }
+
+      let attempts = sessions
+        .into_iter()
+        .map(|session| {
+          let (state, browser) = (state.state.clone(), state.browser.clone());
+          spawn(async move {
+            session
+              .consume_oauth_state(&state, "google", Some(&browser))
+              .await
+              .unwrap()
+          })
+        })
+        .collect::<Vec<_>>();
+
+      let mut flows = Vec::new();
+      for attempt in attempts {
+        flows.extend(attempt.await);
+      }
+
+      assert_eq!(flows.len(), 1);
+      assert_eq!(code_challenge(&flows[0].verifier), state.code_challenge);
+    });
+  }
+
+  #[test]
+  fn oauth_state_link() {
+    block_on(async {
+      let config = load_test_config().unwrap();
+      let session = Session::open(&config).await.unwrap();
+      let state = session
+        .create_oauth_state("github", Some("user-1"))
+        .await
+        .unwrap();
+      let flow = session
+        .consume_oauth_state(&state.state, "github", Some(&state.browser))
+        .await
+        .unwrap()
+        .unwrap();
+      assert_eq!(flow.link.as_deref(), Some("user-1"));
+    });
   }
 }